@@ -0,0 +1,248 @@
+use crate::{Component, Context, DrawTextureOpts, Error, Schedule, Stage, System, Texture, V2};
+
+/// A single image of a [`Clip`], either a whole texture or a region of a sprite sheet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub texture: Texture,
+    /// Position and size of the frame within the texture, `None` for the whole texture.
+    pub source: Option<(V2, V2)>,
+    /// Seconds the frame is shown for. Frames lasting no time at all are skipped.
+    pub duration: f64,
+}
+
+impl Frame {
+    pub fn new(texture: Texture, duration: f64) -> Self {
+        Self {
+            texture,
+            source: None,
+            duration,
+        }
+    }
+
+    pub fn source(self, pos: V2, size: V2) -> Self {
+        Self {
+            source: Some((pos, size)),
+            ..self
+        }
+    }
+
+    /// Options drawing only this frame, to be extended with a size, opacity etc.
+    pub fn draw_opts(&self) -> DrawTextureOpts {
+        DrawTextureOpts::new().source(self.source)
+    }
+}
+
+/// A sequence of frames, played once unless made [`Clip::looping`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+    frames: Vec<Frame>,
+    looping: bool,
+}
+
+impl Clip {
+    /// Panics if `frames` is empty, as there would be nothing to draw.
+    pub fn new(frames: Vec<Frame>) -> Self {
+        assert!(!frames.is_empty(), "a clip needs at least one frame");
+        Self {
+            frames,
+            looping: false,
+        }
+    }
+
+    /// One frame per texture, each shown for `frame_duration` seconds.
+    pub fn from_textures(textures: impl IntoIterator<Item = Texture>, frame_duration: f64) -> Self {
+        Self::new(
+            textures
+                .into_iter()
+                .map(|texture| Frame::new(texture, frame_duration))
+                .collect(),
+        )
+    }
+
+    /// `count` frames of `frame_size`, laid out left to right in the given row of a sprite sheet.
+    pub fn from_sheet_row(
+        texture: Texture,
+        frame_size: V2,
+        row: u32,
+        count: u32,
+        frame_duration: f64,
+    ) -> Self {
+        Self::new(
+            (0..count)
+                .map(|column| {
+                    let pos = V2::new(column as f64 * frame_size.x, row as f64 * frame_size.y);
+                    Frame::new(texture, frame_duration).source(pos, frame_size)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn looping(self) -> Self {
+        Self {
+            looping: true,
+            ..self
+        }
+    }
+
+    /// Overrides the duration of each frame, frames without a duration keep theirs.
+    pub fn with_durations(mut self, durations: &[f64]) -> Self {
+        for (frame, duration) in self.frames.iter_mut().zip(durations) {
+            frame.duration = *duration;
+        }
+        self
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn is_looping(&self) -> bool {
+        self.looping
+    }
+
+    /// Seconds it takes to play every frame once.
+    pub fn duration(&self) -> f64 {
+        self.frames
+            .iter()
+            .map(|frame| frame.duration.max(0.0))
+            .sum()
+    }
+}
+
+/// Plays a [`Clip`], advanced by [`AnimationSystem`].
+#[derive(Component, Clone, Debug)]
+pub struct Animation {
+    clip: Clip,
+    frame: usize,
+    time_in_frame: f64,
+    finished: bool,
+}
+
+impl Animation {
+    pub fn new(clip: Clip) -> Self {
+        Self {
+            clip,
+            frame: 0,
+            time_in_frame: 0.0,
+            finished: false,
+        }
+    }
+
+    /// Switches to another clip, starting from its first frame.
+    pub fn play(&mut self, clip: Clip) {
+        *self = Self::new(clip);
+    }
+
+    pub fn clip(&self) -> &Clip {
+        &self.clip
+    }
+
+    pub fn frame(&self) -> &Frame {
+        &self.clip.frames[self.frame]
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.frame
+    }
+
+    /// Clips which don't loop finish after their last frame, and keep showing it.
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        if self.finished {
+            return;
+        }
+        if self.clip.looping && self.clip.duration() <= 0.0 {
+            // every frame would be skipped forever, so stay on the current one
+            return;
+        }
+        self.time_in_frame += delta;
+        if self.clip.looping {
+            // skip whole loops at once, so long deltas don't spin through every frame
+            self.time_in_frame %= self.clip.duration();
+        }
+        loop {
+            let duration = self.frame().duration.max(0.0);
+            if self.time_in_frame < duration {
+                return;
+            }
+            if self.frame + 1 < self.clip.frames.len() {
+                self.frame += 1;
+            } else if self.clip.looping {
+                self.frame = 0;
+            } else {
+                self.finished = true;
+                return;
+            }
+            self.time_in_frame -= duration;
+        }
+    }
+}
+
+/// Advances every [`Animation`].
+pub struct AnimationSystem(pub u64);
+impl System for AnimationSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay)
+    }
+
+    fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
+        for (_, animation) in ctx.components_mut::<Animation>() {
+            animation.update(delta);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn animations_advance_by_frame_duration_and_loop_or_finish() {
+    let texture = Texture(0);
+    let clip = Clip::from_sheet_row(texture, V2::new(32.0, 16.0), 1, 3, 0.1).with_durations(&[0.2]);
+    assert!((clip.duration() - 0.4).abs() < 1e-9);
+    assert_eq!(
+        clip.frames()[2].source,
+        Some((V2::new(64.0, 16.0), V2::new(32.0, 16.0)))
+    );
+
+    let mut once = Animation::new(clip.clone());
+    once.update(0.15);
+    assert_eq!(once.frame_index(), 0, "the first frame lasts 0.2s");
+    once.update(0.1);
+    assert_eq!(once.frame_index(), 1);
+    once.update(1.0);
+    assert!(once.finished());
+    assert_eq!(
+        once.frame_index(),
+        2,
+        "finished clips stay on their last frame"
+    );
+
+    let mut looping = Animation::new(clip.looping());
+    looping.update(0.45);
+    assert!(!looping.finished());
+    assert_eq!(looping.frame_index(), 0);
+    looping.update(0.2);
+    assert_eq!(looping.frame_index(), 1);
+}
+
+#[test]
+fn frames_without_duration_are_skipped_and_never_hang() {
+    let textures = [Texture(0), Texture(1), Texture(2)];
+    let mut still = Animation::new(Clip::from_textures(textures, 0.0).looping());
+    still.update(0.5);
+    assert_eq!(still.frame_index(), 0);
+    assert!(!still.finished());
+
+    let clip = Clip::from_textures(textures, 0.1).with_durations(&[0.0, -1.0]);
+    assert!((clip.duration() - 0.1).abs() < 1e-9);
+    let mut looping = Animation::new(clip.clone().looping());
+    looping.update(0.05);
+    assert_eq!(looping.frame_index(), 2, "the first two frames are skipped");
+    looping.update(0.1);
+    assert_eq!(looping.frame_index(), 2);
+    let mut once = Animation::new(clip);
+    once.update(0.1);
+    assert!(once.finished());
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use crate::{
+    capture::Image, input::Input, text::TextRun, DisplayMode, DrawTextureOpts, Error, Id, Texture,
+    V2,
+};
+
+/// Everything `Game` and `Context` need from the platform layer.
+/// Implemented by the SDL backend and by the headless backend used in tests.
+pub(crate) trait Backend {
+    fn poll_events(&mut self, input: &mut Input);
+
+    fn clear(&mut self, rgb: (u8, u8, u8));
+
+    fn present(&mut self);
+
+    fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), Error>;
+
+    fn load_texture(&mut self, id: Id, path: &Path) -> Result<(), Error>;
+
+    fn load_font(&mut self, id: Id, path: &Path, size: u16) -> Result<(), Error>;
+
+    /// Frees a texture, e.g. one of text which isn't drawn anymore.
+    fn unload_texture(&mut self, id: Id);
+
+    fn unload_font(&mut self, id: Id);
+
+    /// Renders `text` into a new texture with the given id and returns its size.
+    fn render_text(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        text: &str,
+        rgb: (u8, u8, u8),
+    ) -> Result<(u32, u32), Error>;
+
+    /// Renders runs of text, each at its own position and in its own color,
+    /// into a new texture of the given size with the given id.
+    fn render_text_runs(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        runs: &[TextRun],
+        size: (u32, u32),
+    ) -> Result<(), Error>;
+
+    fn text_size(&mut self, font_id: Id, text: &str) -> Result<(u32, u32), Error>;
+
+    fn texture_size(&mut self, texture: Id) -> Result<(u32, u32), Error>;
+
+    fn draw_texture(&mut self, texture: Id, pos: V2, opts: &DrawTextureOpts) -> Result<(), Error>;
+
+    /// Draws the same texture several times, which backends can do without looking it up again.
+    fn draw_textures(
+        &mut self,
+        texture: Id,
+        draws: &[(V2, &DrawTextureOpts)],
+    ) -> Result<(), Error> {
+        for (pos, opts) in draws {
+            self.draw_texture(texture, *pos, opts)?;
+        }
+        Ok(())
+    }
+
+    fn fill_rect(
+        &mut self,
+        rgba: (u8, u8, u8, u8),
+        pos: (i32, i32),
+        size: (u32, u32),
+    ) -> Result<(), Error>;
+
+    /// The pixels drawn to the canvas since it was last cleared.
+    fn read_pixels(&mut self) -> Result<Image, Error>;
+
+    fn draw_calls(&self) -> &[DrawCall] {
+        &[]
+    }
+}
+
+/// A draw recorded by the headless backend.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCall {
+    Texture {
+        texture: Texture,
+        pos: V2,
+        size: V2,
+    },
+    Rect {
+        rgba: (u8, u8, u8, u8),
+        pos: (i32, i32),
+        size: (u32, u32),
+    },
+}
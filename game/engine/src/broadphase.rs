@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use crate::{physics::Rect, Entity};
+
+/// Rects covering more cells than this are checked against every query instead,
+/// so a huge or runaway rect doesn't fill the grid.
+const MAX_CELLS_PER_RECT: i64 = 1024;
+
+/// Uniform grid of rects, finding the ones which may overlap a rect without comparing
+/// against every other. [`CollisionSystem`](crate::CollisionSystem) keeps one of every
+/// [`RigidBody`](crate::rigid_body::RigidBody) as a resource, rebuilt each update.
+pub struct Broadphase {
+    cell_size: f64,
+    entries: Vec<(Entity, Rect)>,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    oversized: Vec<usize>,
+}
+
+impl Broadphase {
+    pub const DEFAULT_CELL_SIZE: f64 = 128.0;
+
+    /// Cells should be around the size of the bodies, as bodies are checked against
+    /// every other in the cells they cover.
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            entries: Vec::new(),
+            cells: HashMap::new(),
+            oversized: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.cells.clear();
+        self.oversized.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of cells covered, saturating for rects at the limits of `i64`.
+    fn cell_count(((x0, x1), (y0, y1)): ((i64, i64), (i64, i64))) -> i64 {
+        let span = |from: i64, to: i64| to.saturating_sub(from).saturating_add(1);
+        span(x0, x1).saturating_mul(span(y0, y1))
+    }
+
+    fn cell_range(&self, rect: Rect) -> ((i64, i64), (i64, i64)) {
+        let cell = |value: f64| (value / self.cell_size).floor() as i64;
+        (
+            (cell(rect.pos.x), cell(rect.pos.x + rect.size.x)),
+            (cell(rect.pos.y), cell(rect.pos.y + rect.size.y)),
+        )
+    }
+
+    pub fn insert(&mut self, entity: Entity, rect: Rect) {
+        let index = self.entries.len();
+        self.entries.push((entity, rect));
+        let range = self.cell_range(rect);
+        if !(1..=MAX_CELLS_PER_RECT).contains(&Self::cell_count(range)) {
+            self.oversized.push(index);
+            return;
+        }
+        let ((x0, x1), (y0, y1)) = range;
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.cells.entry((x, y)).or_default().push(index);
+            }
+        }
+    }
+
+    /// Entities inserted with rects overlapping or touching `rect`,
+    /// each once and in the order they were inserted.
+    pub fn query(&self, rect: Rect) -> Vec<Entity> {
+        let range = self.cell_range(rect);
+        let mut indices = if (1..=MAX_CELLS_PER_RECT).contains(&Self::cell_count(range)) {
+            let ((x0, x1), (y0, y1)) = range;
+            let mut indices = self.oversized.clone();
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    indices.extend(self.cells.get(&(x, y)).into_iter().flatten());
+                }
+            }
+            indices
+        } else {
+            (0..self.entries.len()).collect()
+        };
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| self.entries[index])
+            .filter(|(_, other)| rects_touch(rect, *other))
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+}
+
+impl Default for Broadphase {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CELL_SIZE)
+    }
+}
+
+fn rects_touch(a: Rect, b: Rect) -> bool {
+    a.pos.x <= b.pos.x + b.size.x
+        && a.pos.x + a.size.x >= b.pos.x
+        && a.pos.y <= b.pos.y + b.size.y
+        && a.pos.y + a.size.y >= b.pos.y
+}
+
+#[test]
+fn broadphase_finds_the_rects_which_may_overlap() {
+    let mut entities = crate::entity::Entities::default();
+    let mut spawn = || entities.spawn();
+    let (a, b, c, floor, runaway) = (spawn(), spawn(), spawn(), spawn(), spawn());
+
+    let mut grid = Broadphase::new(10.0);
+    grid.insert(a, Rect::from_f64(0.0, 0.0, 5.0, 5.0));
+    grid.insert(b, Rect::from_f64(12.0, 2.0, 5.0, 5.0));
+    grid.insert(c, Rect::from_f64(-40.0, -40.0, 5.0, 5.0));
+    grid.insert(floor, Rect::from_f64(-50.0, 20.0, 100.0, 5.0));
+    grid.insert(runaway, Rect::from_f64(-1e12, 0.0, 2e12, 1.0));
+    assert_eq!(grid.len(), 5);
+
+    assert_eq!(grid.query(Rect::from_f64(4.0, 4.0, 9.0, 1.0)), vec![a, b]);
+    assert_eq!(grid.query(Rect::from_f64(-38.0, -38.0, 1.0, 1.0)), vec![c]);
+    // touching counts, so bodies resting on each other are found
+    assert_eq!(
+        grid.query(Rect::from_f64(30.0, 10.0, 5.0, 10.0)),
+        vec![floor]
+    );
+    assert_eq!(
+        grid.query(Rect::from_f64(-1e9, -1e9, 2e9, 2e9)),
+        vec![a, b, c, floor, runaway]
+    );
+
+    grid.clear();
+    assert!(grid.is_empty());
+    assert_eq!(grid.query(Rect::from_f64(0.0, 0.0, 5.0, 5.0)), vec![]);
+}
@@ -0,0 +1,190 @@
+use crate::{clamp, max, Context, Error, Schedule, Stage, System, V2};
+
+/// Maps world coordinates to the screen.
+/// Insert it as a resource and add [`CameraSystem`] to use it,
+/// world space drawing then goes through [`Context::draw_texture_world`].
+#[derive(Clone, Debug)]
+pub struct Camera {
+    pos: V2,
+    zoom: f64,
+    target_pos: V2,
+    target_zoom: f64,
+    screen_size: V2,
+    bounds: Option<(V2, V2)>,
+    /// How quickly the camera catches up with its target, per second.
+    smoothing: f64,
+    shake_strength: f64,
+    shake_duration: f64,
+    shake_time_left: f64,
+    shake_offset: V2,
+}
+
+impl Camera {
+    /// A camera looking at the world as if it was drawn directly in screen coordinates.
+    pub fn new(screen_size: V2) -> Self {
+        let pos = screen_size.extend(0.5);
+        Self {
+            pos,
+            zoom: 1.0,
+            target_pos: pos,
+            target_zoom: 1.0,
+            screen_size,
+            bounds: None,
+            smoothing: 4.0,
+            shake_strength: 0.0,
+            shake_duration: 0.0,
+            shake_time_left: 0.0,
+            shake_offset: V2::new(0.0, 0.0),
+        }
+    }
+
+    /// Keeps the view within the given world area, zooming in if the area is smaller than the view.
+    pub fn with_bounds(self, pos: V2, size: V2) -> Self {
+        Self {
+            bounds: Some((pos, size)),
+            ..self
+        }
+    }
+
+    pub fn with_smoothing(self, smoothing: f64) -> Self {
+        Self { smoothing, ..self }
+    }
+
+    /// The world position at the center of the screen.
+    pub fn pos(&self) -> V2 {
+        self.pos
+    }
+
+    pub fn zoom(&self) -> f64 {
+        self.zoom
+    }
+
+    pub fn screen_size(&self) -> V2 {
+        self.screen_size
+    }
+
+    /// Moves smoothly towards centering `target` at the given zoom.
+    pub fn follow(&mut self, target: V2, zoom: f64) {
+        self.target_pos = target;
+        self.target_zoom = zoom;
+    }
+
+    /// Jumps straight to the target, e.g. when a match starts.
+    pub fn snap(&mut self) {
+        self.zoom = self.clamp_zoom(self.target_zoom);
+        self.pos = self.clamp_pos(self.target_pos, self.zoom);
+    }
+
+    /// Shakes the view by up to `strength` pixels, fading out over `duration` seconds.
+    /// A weaker shake doesn't cut a stronger one short.
+    pub fn shake(&mut self, strength: f64, duration: f64) {
+        let current = if self.shake_duration > 0.0 {
+            self.shake_strength * self.shake_time_left / self.shake_duration
+        } else {
+            0.0
+        };
+        if strength < current {
+            return;
+        }
+        self.shake_strength = strength;
+        self.shake_duration = duration;
+        self.shake_time_left = duration;
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        let t = 1.0 - (-self.smoothing * delta).exp();
+        self.zoom = self.clamp_zoom(self.zoom + (self.target_zoom - self.zoom) * t);
+        let pos = self.pos + (self.target_pos - self.pos).extend(t);
+        self.pos = self.clamp_pos(pos, self.zoom);
+
+        self.shake_time_left = max(self.shake_time_left - delta, 0.0);
+        self.shake_offset = if self.shake_time_left > 0.0 {
+            let amplitude = self.shake_strength * self.shake_time_left / self.shake_duration;
+            V2::new(
+                rand::random::<f64>() * 2.0 - 1.0,
+                rand::random::<f64>() * 2.0 - 1.0,
+            )
+            .extend(amplitude)
+        } else {
+            V2::new(0.0, 0.0)
+        };
+    }
+
+    pub fn world_to_screen(&self, pos: V2) -> V2 {
+        (pos - self.pos).extend(self.zoom) + self.screen_size.extend(0.5) + self.shake_offset
+    }
+
+    pub fn screen_to_world(&self, pos: V2) -> V2 {
+        (pos - self.screen_size.extend(0.5) - self.shake_offset).div_comps(self.zoom) + self.pos
+    }
+
+    /// Converts a size in world space to a size on screen.
+    pub fn scale(&self, size: V2) -> V2 {
+        size.extend(self.zoom)
+    }
+
+    fn clamp_zoom(&self, zoom: f64) -> f64 {
+        let Some((_, size)) = self.bounds else {
+            return zoom;
+        };
+        let min_zoom = max(self.screen_size.x / size.x, self.screen_size.y / size.y);
+        max(zoom, min_zoom)
+    }
+
+    fn clamp_pos(&self, pos: V2, zoom: f64) -> V2 {
+        let Some((bounds_pos, bounds_size)) = self.bounds else {
+            return pos;
+        };
+        let half_view = self.screen_size.extend(0.5).div_comps(zoom);
+        V2::new(
+            clamp(
+                pos.x,
+                bounds_pos.x + half_view.x,
+                bounds_pos.x + bounds_size.x - half_view.x,
+            ),
+            clamp(
+                pos.y,
+                bounds_pos.y + half_view.y,
+                bounds_pos.y + bounds_size.y - half_view.y,
+            ),
+        )
+    }
+}
+
+/// Moves the [`Camera`] resource towards its target and updates screen shake.
+pub struct CameraSystem(pub u64);
+impl System for CameraSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay)
+    }
+
+    fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
+        if let Some(camera) = ctx.try_resource::<Camera>() {
+            camera.update(delta);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn camera_maps_between_world_and_screen_and_stays_in_bounds() {
+    let screen = V2::new(1280.0, 720.0);
+    let mut camera = Camera::new(screen).with_bounds(V2::new(0.0, 0.0), screen);
+    assert_eq!(
+        camera.world_to_screen(V2::new(100.0, 200.0)),
+        V2::new(100.0, 200.0)
+    );
+
+    camera.follow(V2::new(1200.0, 100.0), 2.0);
+    camera.snap();
+    assert_eq!(camera.pos(), V2::new(1280.0 - 320.0, 180.0));
+    let world = V2::new(1000.0, 300.0);
+    assert_eq!(camera.screen_to_world(camera.world_to_screen(world)), world);
+    assert_eq!(camera.scale(V2::new(10.0, 20.0)), V2::new(20.0, 40.0));
+
+    camera.follow(V2::new(640.0, 360.0), 0.5);
+    for _ in 0..1000 {
+        camera.update(1.0 / 120.0);
+    }
+    assert!((camera.zoom() - 1.0).abs() < 1e-9, "zoom {}", camera.zoom());
+}
@@ -0,0 +1,451 @@
+use std::{
+    collections::VecDeque,
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
+
+/// Pixels read back from the canvas, as rows of RGBA bytes from the top left.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    /// A transparent black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, Error> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err(format!(
+                "{} bytes is not a {width}x{height} rgba image",
+                pixels.len()
+            )
+            .into());
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn index(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = self.index(x, y);
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+
+    pub fn set_pixel(&mut self, x: u32, y: u32, rgba: [u8; 4]) {
+        let i = self.index(x, y);
+        self.pixels[i..i + 4].copy_from_slice(&rgba);
+    }
+
+    /// Blends `image` over this one with its top left corner at `pos`, by the alpha of both.
+    pub fn draw_over(&mut self, image: &Image, (x, y): (i32, i32)) {
+        for source_y in 0..image.height {
+            let Ok(dest_y) = u32::try_from(y + source_y as i32) else {
+                continue;
+            };
+            for source_x in 0..image.width {
+                let Ok(dest_x) = u32::try_from(x + source_x as i32) else {
+                    continue;
+                };
+                if dest_x >= self.width || dest_y >= self.height {
+                    continue;
+                }
+                let [r, g, b, a] = image.pixel(source_x, source_y);
+                if a == 0 {
+                    continue;
+                }
+                let [dest_r, dest_g, dest_b, dest_a] = self.pixel(dest_x, dest_y);
+                let (a, dest_a) = (f64::from(a) / 255.0, f64::from(dest_a) / 255.0);
+                let out_a = a + dest_a * (1.0 - a);
+                let mix = |source: u8, dest: u8| {
+                    ((f64::from(source) * a + f64::from(dest) * dest_a * (1.0 - a)) / out_a).round()
+                        as u8
+                };
+                let rgba = [
+                    mix(r, dest_r),
+                    mix(g, dest_g),
+                    mix(b, dest_b),
+                    (out_a * 255.0).round() as u8,
+                ];
+                self.set_pixel(dest_x, dest_y, rgba);
+            }
+        }
+    }
+
+    /// Shrinks the image by a whole factor, averaging each square of pixels.
+    pub fn downscale(&self, factor: u32) -> Self {
+        let factor = factor.max(1);
+        let mut image = Self::new(self.width / factor, self.height / factor);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let mut sum = [0u32; 4];
+                for sy in 0..factor {
+                    for sx in 0..factor {
+                        let pixel = self.pixel(x * factor + sx, y * factor + sy);
+                        for (sum, channel) in sum.iter_mut().zip(pixel) {
+                            *sum += u32::from(channel);
+                        }
+                    }
+                }
+                let count = factor * factor;
+                image.set_pixel(x, y, sum.map(|sum| (sum / count) as u8));
+            }
+        }
+        image
+    }
+
+    /// Stretches or shrinks the image to the given size, picking the nearest pixel.
+    pub fn resize(&self, width: u32, height: u32) -> Self {
+        let mut image = Self::new(width, height);
+        if self.width == 0 || self.height == 0 {
+            return image;
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let source_x = (u64::from(x) * u64::from(self.width) / u64::from(width)) as u32;
+                let source_y = (u64::from(y) * u64::from(self.height) / u64::from(height)) as u32;
+                image.set_pixel(x, y, self.pixel(source_x, source_y));
+            }
+        }
+        image
+    }
+
+    /// Number of pixels where any channel differs by more than `tolerance`.
+    pub fn diff(&self, other: &Image, tolerance: u8) -> Result<usize, Error> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(format!(
+                "a {}x{} image can't be compared to a {}x{} image",
+                self.width, self.height, other.width, other.height
+            )
+            .into());
+        }
+        Ok(self
+            .pixels
+            .chunks_exact(4)
+            .zip(other.pixels.chunks_exact(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > tolerance))
+            .count())
+    }
+
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let file = File::create(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|err| format!("{}: {err}", path.display()).into())
+    }
+
+    pub fn load_png<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let err = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+        let file = File::open(path).map_err(|e| err(&e))?;
+        let mut decoder = png::Decoder::new(BufReader::new(file));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+        let mut reader = decoder.read_info().map_err(|e| err(&e))?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer).map_err(|e| err(&e))?;
+        buffer.truncate(info.buffer_size());
+        let pixels = match info.color_type {
+            png::ColorType::Rgba => buffer,
+            png::ColorType::Rgb => buffer
+                .chunks_exact(3)
+                .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255])
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buffer
+                .chunks_exact(2)
+                .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+                .collect(),
+            png::ColorType::Grayscale => buffer.iter().flat_map(|g| [*g, *g, *g, 255]).collect(),
+            png::ColorType::Indexed => unreachable!("expanded by the decoder"),
+        };
+        Self::from_rgba(info.width, info.height, pixels)
+    }
+}
+
+/// Sent once a frame requested with [`Context::capture_frame`](crate::Context::capture_frame) is drawn.
+#[derive(Clone)]
+pub struct FrameCaptured(pub Image);
+
+/// Writes the frames as an animated gif which loops forever. Frames have to share a size.
+pub fn save_gif<P: AsRef<Path>>(frames: &[Image], fps: f64, path: P) -> Result<(), Error> {
+    let path = path.as_ref();
+    let err = |err: &dyn std::fmt::Display| format!("{}: {err}", path.display());
+    let Some(first) = frames.first() else {
+        return Err(err(&"no frames to save").into());
+    };
+    if let Some(other) = frames
+        .iter()
+        .find(|image| (image.width, image.height) != (first.width, first.height))
+    {
+        return Err(err(&format!(
+            "a {}x{} frame can't follow {}x{} frames",
+            other.width, other.height, first.width, first.height
+        ))
+        .into());
+    }
+    let (Ok(width), Ok(height)) = (u16::try_from(first.width), u16::try_from(first.height)) else {
+        return Err(err(&"frames are too large for a gif").into());
+    };
+    let file = File::create(path).map_err(|e| err(&e))?;
+    let mut encoder =
+        gif::Encoder::new(BufWriter::new(file), width, height, &[]).map_err(|e| err(&e))?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| err(&e))?;
+    // gif delays are in hundredths of a second
+    let delay = (100.0 / fps).round() as u16;
+    for image in frames {
+        let mut pixels = image.pixels.clone();
+        let mut frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        frame.delay = delay;
+        encoder.write_frame(&frame).map_err(|e| err(&e))?;
+    }
+    Ok(())
+}
+
+/// Writes each frame as a numbered png in `dir`, which is created if needed.
+pub fn save_png_sequence<P: AsRef<Path>>(frames: &[Image], dir: P) -> Result<Vec<PathBuf>, Error> {
+    let dir = dir.as_ref();
+    std::fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, image)| {
+            let path = dir.join(format!("frame_{i:04}.png"));
+            image.save_png(&path)?;
+            Ok(path)
+        })
+        .collect()
+}
+
+/// Compares a captured frame to the golden image at `path`, for rendering regression tests.
+/// The golden image is written instead if it doesn't exist yet or `UPDATE_GOLDEN` is set.
+/// On a mismatch the frame is saved next to it with the extension `actual.png`, to be inspected.
+pub fn check_golden<P: AsRef<Path>>(image: &Image, path: P, tolerance: u8) -> Result<(), Error> {
+    let path = path.as_ref();
+    if !path.exists() || std::env::var_os("UPDATE_GOLDEN").is_some() {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+        }
+        return image.save_png(path);
+    }
+    let golden = Image::load_png(path)?;
+    let differing = image.diff(&golden, tolerance)?;
+    if differing == 0 {
+        return Ok(());
+    }
+    let actual = path.with_extension("actual.png");
+    image.save_png(&actual)?;
+    Err(format!(
+        "{differing} pixels differ from {}, see {}",
+        path.display(),
+        actual.display()
+    )
+    .into())
+}
+
+/// Keeps the frames of the last few seconds, captured at a fixed rate, e.g. to export highlights.
+pub struct FrameRecorder {
+    frames: VecDeque<Image>,
+    max_frames: usize,
+    fps: f64,
+    until_next: f64,
+    downscale: u32,
+}
+
+impl FrameRecorder {
+    pub fn new(seconds: f64, fps: f64) -> Self {
+        Self {
+            frames: VecDeque::new(),
+            max_frames: (seconds * fps).ceil().max(1.0) as usize,
+            fps,
+            until_next: 0.0,
+            downscale: 1,
+        }
+    }
+
+    /// Keeps frames at a fraction of their size, as a few seconds of full frames take a lot of memory.
+    pub fn downscale(self, factor: u32) -> Self {
+        Self {
+            downscale: factor,
+            ..self
+        }
+    }
+
+    pub fn fps(&self) -> f64 {
+        self.fps
+    }
+
+    /// Oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &Image> {
+        self.frames.iter()
+    }
+
+    /// Advances the clock, returning whether the current frame should be captured.
+    pub(crate) fn tick(&mut self, delta: f64) -> bool {
+        let due = self.until_next <= 0.0;
+        if due {
+            // don't try to catch up on frames missed during a long pause
+            self.until_next = (self.until_next + 1.0 / self.fps).max(0.0);
+        }
+        self.until_next -= delta;
+        due
+    }
+
+    /// Frames are kept at the size of the oldest one, so a window resized or made fullscreen
+    /// while recording still exports as a single gif.
+    pub(crate) fn push(&mut self, image: Image) {
+        if self.frames.len() == self.max_frames {
+            self.frames.pop_front();
+        }
+        let image = match self.downscale {
+            0 | 1 => image,
+            factor => image.downscale(factor),
+        };
+        let image = match self.frames.front() {
+            Some(first) if (first.width, first.height) != (image.width, image.height) => {
+                image.resize(first.width, first.height)
+            }
+            _ => image,
+        };
+        self.frames.push_back(image);
+    }
+}
+
+#[test]
+fn recorder_keeps_the_last_frames_which_export_as_gif_and_png() {
+    let mut recorder = FrameRecorder::new(0.5, 4.0).downscale(2);
+    let mut captured = Vec::new();
+    for i in 0..10u8 {
+        if recorder.tick(0.125) {
+            captured.push(i);
+            let mut image = Image::new(4, 2);
+            image.set_pixel(0, 0, [i * 20, 0, 0, 255]);
+            recorder.push(image);
+        }
+    }
+    assert_eq!(captured, vec![0, 2, 4, 6, 8], "every other frame at 4 fps");
+    let frames = recorder.frames().cloned().collect::<Vec<_>>();
+    assert_eq!(frames.len(), 2, "half a second at 4 fps");
+    assert_eq!((frames[1].width, frames[1].height), (2, 1));
+    assert_eq!(
+        frames[1].pixel(0, 0),
+        [40, 0, 0, 63],
+        "averaged by downscaling"
+    );
+
+    let dir = std::env::temp_dir().join(format!("recorder_{}", std::process::id()));
+    let paths = save_png_sequence(&frames, &dir).unwrap();
+    assert_eq!(Image::load_png(&paths[1]).unwrap(), frames[1]);
+    let gif = dir.join("highlight.gif");
+    save_gif(&frames, recorder.fps(), &gif).unwrap();
+    assert!(std::fs::metadata(&gif).unwrap().len() > 0);
+    assert!(save_gif(&[frames[0].clone(), Image::new(4, 2)], 4.0, &gif).is_err());
+
+    // e.g. the window made fullscreen while recording
+    recorder.push(Image::new(8, 8));
+    let last = recorder.frames().last().unwrap();
+    assert_eq!((last.width, last.height), (2, 1));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn headless_frames_match_the_golden_image() {
+    use crate::{Context, DrawTextureOpts, Game, System, Texture, V2};
+
+    struct Scene(Texture);
+    impl System for Scene {
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.draw_rect((200, 40, 40), 100, 100, 300, 200)?;
+            ctx.draw_rect_alpha((40, 40, 200), 128, 250, 150, 300, 200)?;
+            ctx.draw_texture(
+                self.0,
+                V2::new(700.0, 100.0),
+                DrawTextureOpts::new().size(V2::new(64.0, 64.0)),
+            )?;
+            ctx.draw_texture(
+                self.0,
+                V2::new(700.0, 400.0),
+                DrawTextureOpts::new()
+                    .size(V2::new(256.0, 128.0))
+                    .source(Some((V2::new(2.0, 0.0), V2::new(2.0, 4.0))))
+                    .color_mod((255, 128, 0))
+                    .opacity(0.75),
+            )
+        }
+    }
+
+    let mut checker = Image::new(4, 4);
+    for y in 0..4 {
+        for x in 0..4 {
+            let shade = if (x + y) % 2 == 0 { 255 } else { 60 };
+            checker.set_pixel(x, y, [shade, shade, shade, 255]);
+        }
+    }
+    let path = std::env::temp_dir().join(format!("checker_{}.png", std::process::id()));
+    checker.save_png(&path).unwrap();
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    let texture = ctx.load_texture(&path).unwrap();
+    ctx.add_system(move |_| Scene(texture));
+    game.step();
+    std::fs::remove_file(path).unwrap();
+
+    let frame = game.capture().unwrap();
+    assert_eq!(frame.pixel(0, 0), [60, 180, 180, 255], "cleared");
+    assert_eq!(frame.pixel(120, 120), [200, 40, 40, 255]);
+    let golden = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/headless_frame.png");
+    check_golden(&frame, golden, 1).unwrap();
+}
+
+#[test]
+fn requested_frames_are_sent_as_events_and_recorded_frames_kept() {
+    use crate::Game;
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.start_recording(FrameRecorder::new(1.0, 60.0).downscale(4));
+    ctx.capture_frame();
+    game.step();
+    game.step();
+
+    let captured = game.context().read_events::<FrameCaptured>();
+    assert_eq!(
+        captured.len(),
+        1,
+        "readable during the update after it's drawn"
+    );
+    assert_eq!((captured[0].0.width, captured[0].0.height), (1280, 720));
+    game.step();
+    let ctx = game.context();
+    let recorded = ctx.recorder().unwrap().frames().collect::<Vec<_>>();
+    assert_eq!(recorded.len(), 2, "every other update at 60 fps");
+    assert_eq!((recorded[0].width, recorded[0].height), (320, 180));
+}
@@ -1,11 +1,12 @@
 use std::{collections::HashSet, ops::ControlFlow, rc::Rc};
 
 use crate::{
+    broadphase::Broadphase,
     max, min,
-    physics::{Intersection, Line, Movable, Moving, OctoDirection, QuadDirection, Rect},
-    query,
-    rigid_body::RigidBody,
-    Component, Context, Error, Id, System, V2,
+    physics::{Contact, Movable, OctoDirection, QuadDirection, Rect},
+    query, query_entities,
+    rigid_body::{DragSystem, GravitySystem, RigidBody, VelocitySystem},
+    Component, Context, Entity, Error, Schedule, Stage, System, V2,
 };
 
 pub trait CollisionResolver {
@@ -53,15 +54,71 @@ pub fn resolve_position_default(body: &mut RigidBody, pos: V2, size: V2, dir: Qu
     }
 }
 
+/// Bitset of collision layers. Colliders are on some layers and collide with the colliders
+/// on the layers in their mask, if those collide with them as well. Games name their own
+/// layers, e.g. `const TERRAIN: CollisionLayers = CollisionLayers::layer(1);`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionLayers(pub u32);
+
+impl CollisionLayers {
+    pub const NONE: Self = Self(0);
+    pub const ALL: Self = Self(u32::MAX);
+    /// Layer colliders are on unless told otherwise.
+    pub const DEFAULT: Self = Self::layer(0);
+
+    /// Layer number `index`, up to 31.
+    pub const fn layer(index: u32) -> Self {
+        Self(1 << index)
+    }
+
+    pub const fn with(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn without(self, other: Self) -> Self {
+        Self(self.0 & !other.0)
+    }
+
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl std::ops::BitOr for CollisionLayers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.with(rhs)
+    }
+}
+
+/// Whether colliders on `layers` with `mask` and colliders on `other_layers`
+/// with `other_mask` collide, which both have to agree on.
+pub fn layers_collide(
+    (layers, mask): (CollisionLayers, CollisionLayers),
+    (other_layers, other_mask): (CollisionLayers, CollisionLayers),
+) -> bool {
+    mask.intersects(other_layers) && other_mask.intersects(layers)
+}
+
+/// Collider which only blocks bodies moving into it from its directions, like one-way
+/// platforms. Specific bodies can be let through for a while, see [`ShallowCollider::ignore`].
 #[derive(Component, Clone)]
 pub struct ShallowCollider {
     directions: HashSet<QuadDirection>,
+    layers: CollisionLayers,
+    mask: CollisionLayers,
+    /// Bodies passing through and the seconds left until they collide again.
+    ignored: Vec<(Entity, f64)>,
 }
 
 impl ShallowCollider {
     pub fn new() -> Self {
         Self {
             directions: HashSet::new(),
+            layers: CollisionLayers::DEFAULT,
+            mask: CollisionLayers::ALL,
+            ignored: Vec::new(),
         }
     }
 
@@ -69,14 +126,50 @@ impl ShallowCollider {
         self.directions.insert(dir);
         self
     }
+
+    pub fn layers(self, layers: CollisionLayers) -> Self {
+        Self { layers, ..self }
+    }
+
+    pub fn mask(self, mask: CollisionLayers) -> Self {
+        Self { mask, ..self }
+    }
+
+    /// Lets `body` pass through for `seconds`, e.g. to drop through a platform.
+    /// Ignoring a body which is already ignored keeps the longer of the two.
+    pub fn ignore(&mut self, body: Entity, seconds: f64) {
+        match self
+            .ignored
+            .iter_mut()
+            .find(|(ignored, _)| *ignored == body)
+        {
+            Some((_, left)) => *left = left.max(seconds),
+            None => self.ignored.push((body, seconds)),
+        }
+    }
+
+    pub fn ignores(&self, body: Entity) -> bool {
+        self.ignored.iter().any(|(ignored, _)| *ignored == body)
+    }
+
+    fn update_ignored(&mut self, delta: f64) {
+        self.ignored.retain_mut(|(_, left)| {
+            *left -= delta;
+            *left > 0.0
+        });
+    }
 }
 
+/// Collider of bodies which block or are blocked by others. Bodies resolving collisions
+/// collide with bodies which don't, as long as their layers and masks agree.
 #[derive(Component, Clone)]
 pub struct SolidCollider {
     pub resolver: Option<Rc<dyn CollisionResolver>>,
     pub colliding: Option<OctoDirection>,
     pub size: Option<V2>,
     pub offset: V2,
+    pub layers: CollisionLayers,
+    pub mask: CollisionLayers,
 }
 
 impl SolidCollider {
@@ -86,6 +179,8 @@ impl SolidCollider {
             colliding: None,
             size: None,
             offset: V2::new(0.0, 0.0),
+            layers: CollisionLayers::DEFAULT,
+            mask: CollisionLayers::ALL,
         }
     }
 
@@ -105,6 +200,67 @@ impl SolidCollider {
     pub fn offset(self, offset: V2) -> Self {
         Self { offset, ..self }
     }
+
+    pub fn layers(self, layers: CollisionLayers) -> Self {
+        Self { layers, ..self }
+    }
+
+    pub fn mask(self, mask: CollisionLayers) -> Self {
+        Self { mask, ..self }
+    }
+}
+
+/// Collider which detects overlapping colliders without blocking them, see
+/// [`trigger_overlaps`]. Covers its body unless given a size, offset from the body.
+#[derive(Component, Clone)]
+pub struct TriggerCollider {
+    pub size: Option<V2>,
+    pub offset: V2,
+    pub layers: CollisionLayers,
+    pub mask: CollisionLayers,
+    /// Entities overlapping the trigger as of the last [`TriggerSystem`] update.
+    pub overlapping: Vec<Entity>,
+}
+
+impl TriggerCollider {
+    pub fn new() -> Self {
+        Self {
+            size: None,
+            offset: V2::new(0.0, 0.0),
+            layers: CollisionLayers::DEFAULT,
+            mask: CollisionLayers::ALL,
+            overlapping: Vec::new(),
+        }
+    }
+
+    pub fn size(self, size: V2) -> Self {
+        Self {
+            size: Some(size),
+            ..self
+        }
+    }
+
+    pub fn offset(self, offset: V2) -> Self {
+        Self { offset, ..self }
+    }
+
+    pub fn layers(self, layers: CollisionLayers) -> Self {
+        Self { layers, ..self }
+    }
+
+    pub fn mask(self, mask: CollisionLayers) -> Self {
+        Self { mask, ..self }
+    }
+
+    pub fn rect(&self, body: &RigidBody) -> Rect {
+        Rect::new(body.pos + self.offset, self.size.unwrap_or(body.size))
+    }
+}
+
+impl Default for TriggerCollider {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 struct Collision {
@@ -115,10 +271,23 @@ struct Collision {
 
 pub struct CollisionSystem(pub u64);
 impl System for CollisionSystem {
+    /// Collisions are predicted from the velocity a body is about to move with,
+    /// so they are resolved after forces are applied and before bodies are moved.
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Physics)
+            .after::<GravitySystem>()
+            .after::<DragSystem>()
+            .before::<VelocitySystem>()
+    }
+
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
         use QuadDirection::*;
 
-        for id in query!(ctx, RigidBody, SolidCollider) {
+        update_broadphase(ctx, delta);
+        for (_, collider) in ctx.components_mut::<ShallowCollider>() {
+            collider.update_ignored(delta);
+        }
+        for id in query_entities!(ctx, RigidBody, SolidCollider) {
             let collider = ctx.select::<SolidCollider>(id).clone();
             let Some(resolver) = collider.resolver else {
                 continue;
@@ -126,12 +295,14 @@ impl System for CollisionSystem {
 
             let collider = ctx.select::<SolidCollider>(id);
             collider.colliding = None;
+            let layers = (collider.layers, collider.mask);
 
             let body = ctx.select::<RigidBody>(id).clone();
 
+            let candidates = ctx.resource::<Broadphase>().query(reach(&body, delta));
             let mut collisions = Vec::<Collision>::new();
-            find_shallow_collisions(&mut collisions, ctx, id, &body, delta);
-            find_solid_collisions(&mut collisions, ctx, id, &body, delta);
+            find_shallow_collisions(&mut collisions, ctx, id, &body, layers, &candidates, delta);
+            find_solid_collisions(&mut collisions, ctx, id, &body, layers, &candidates, delta);
 
             collisions.sort_by(|a, b| a.distance_factor.total_cmp(&b.distance_factor));
 
@@ -164,21 +335,55 @@ impl System for CollisionSystem {
     }
 }
 
+/// Everywhere the body may be by the end of the update, wherever collisions send it.
+fn reach(body: &RigidBody, delta: f64) -> Rect {
+    let delta_pos = body.vel.extend(delta);
+    let margin = V2::new(delta_pos.x.abs(), delta_pos.y.abs());
+    Rect::new(body.pos - margin, body.size + margin.extend(2.0))
+}
+
+/// Rebuilds the [`Broadphase`] resource from the reach of every body and the trigger
+/// colliders on them, so it holds for the rest of the update, after bodies are moved.
+fn update_broadphase(ctx: &mut Context, delta: f64) {
+    let reaches = query!(ctx, RigidBody)
+        .map(|(id, body)| {
+            let reach = reach(body, delta);
+            match ctx.get::<TriggerCollider>(id) {
+                Some(trigger) => (id, reach.union(trigger.rect(body))),
+                None => (id, reach),
+            }
+        })
+        .collect::<Vec<_>>();
+    if ctx.try_resource::<Broadphase>().is_none() {
+        ctx.insert_resource(Broadphase::default());
+    }
+    let broadphase = ctx.resource::<Broadphase>();
+    broadphase.clear();
+    for (id, reach) in reaches {
+        broadphase.insert(id, reach);
+    }
+}
+
 fn find_solid_collisions(
     collisions: &mut Vec<Collision>,
     ctx: &mut Context,
-    id: u64,
+    id: Entity,
     body: &RigidBody,
+    layers: (CollisionLayers, CollisionLayers),
+    candidates: &[Entity],
     delta: f64,
 ) {
-    for other_id in query!(ctx, RigidBody, SolidCollider) {
+    for &other_id in candidates {
         if id == other_id {
             continue;
         }
-
-        let collider = ctx.select::<SolidCollider>(id).clone();
-        let other_collider = ctx.select::<SolidCollider>(other_id).clone();
-        if other_collider.resolver.is_some() && collider.resolver.is_some() {
+        // bodies resolving collisions don't collide with each other
+        let Some(other_collider) = ctx.try_select::<SolidCollider>(other_id) else {
+            continue;
+        };
+        if other_collider.resolver.is_some()
+            || !layers_collide(layers, (other_collider.layers, other_collider.mask))
+        {
             continue;
         }
 
@@ -199,17 +404,27 @@ fn correct_delta_pos(side: OctoDirection, delta_pos: V2) -> bool {
 fn find_shallow_collisions(
     collisions: &mut Vec<Collision>,
     ctx: &mut Context,
-    id: Id,
+    id: Entity,
     body: &RigidBody,
+    layers: (CollisionLayers, CollisionLayers),
+    candidates: &[Entity],
     delta: f64,
 ) {
-    for other_id in query!(ctx, RigidBody, ShallowCollider) {
+    for &other_id in candidates {
         if id == other_id {
             continue;
         }
+        let Some(other_collider) = ctx.try_select::<ShallowCollider>(other_id) else {
+            continue;
+        };
+        if !layers_collide(layers, (other_collider.layers, other_collider.mask))
+            || other_collider.ignores(id)
+        {
+            continue;
+        }
 
         let other_body = ctx.select::<RigidBody>(other_id).clone();
-        let other_collider = ctx.select::<ShallowCollider>(other_id).clone();
+        let other_collider = ctx.select::<ShallowCollider>(other_id);
 
         find_collisions(collisions, body, &other_body, delta, |side, delta_pos| {
             other_collider.directions.contains(&side) && correct_delta_pos(side.into(), delta_pos)
@@ -217,74 +432,430 @@ fn find_shallow_collisions(
     }
 }
 
-fn delta_pos_is_opposite_dir(side: QuadDirection, delta_pos: V2) -> bool {
-    use QuadDirection::*;
-    match side {
-        Top => delta_pos.y < 0.0,
-        Right => delta_pos.x > 0.0,
-        Bottom => delta_pos.y > 0.0, 
-        Left => delta_pos.x < 0.0
+/// An entity started overlapping a [`TriggerCollider`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub other: Entity,
+}
+
+/// An entity kept overlapping a [`TriggerCollider`], sent every update after it entered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerStayed {
+    pub trigger: Entity,
+    pub other: Entity,
+}
+
+/// An entity stopped overlapping a [`TriggerCollider`], or was despawned while overlapping.
+/// Nothing is sent when the trigger itself is despawned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub other: Entity,
+}
+
+/// Sends [`TriggerEntered`], [`TriggerStayed`] and [`TriggerExited`] events
+/// for every [`TriggerCollider`], once bodies have moved.
+pub struct TriggerSystem(pub u64);
+impl System for TriggerSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Physics).after::<VelocitySystem>()
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        for trigger in query_entities!(ctx, RigidBody, TriggerCollider) {
+            let overlapping = trigger_overlaps(ctx, trigger);
+            let collider = ctx.select::<TriggerCollider>(trigger);
+            let previously = std::mem::replace(&mut collider.overlapping, overlapping.clone());
+            for &other in &overlapping {
+                if previously.contains(&other) {
+                    ctx.send_event(TriggerStayed { trigger, other });
+                } else {
+                    ctx.send_event(TriggerEntered { trigger, other });
+                }
+            }
+            for other in previously {
+                if !overlapping.contains(&other) {
+                    ctx.send_event(TriggerExited { trigger, other });
+                }
+            }
+        }
+        Ok(())
     }
 }
 
+/// Layers, mask and rect of every collider on the entity.
+fn colliders_of(ctx: &Context, id: Entity) -> Vec<(CollisionLayers, CollisionLayers, Rect)> {
+    let Some(body) = ctx.get::<RigidBody>(id) else {
+        return Vec::new();
+    };
+    let rect = Rect::new(body.pos, body.size);
+    let mut colliders = Vec::new();
+    if let Some(collider) = ctx.get::<SolidCollider>(id) {
+        colliders.push((collider.layers, collider.mask, rect));
+    }
+    if let Some(collider) = ctx.get::<ShallowCollider>(id) {
+        colliders.push((collider.layers, collider.mask, rect));
+    }
+    if let Some(collider) = ctx.get::<TriggerCollider>(id) {
+        colliders.push((collider.layers, collider.mask, collider.rect(body)));
+    }
+    colliders
+}
+
+/// Entities with colliders overlapping the [`TriggerCollider`] of `trigger_id`,
+/// whose layers and masks agree with it. Uses the [`Broadphase`] kept by
+/// [`CollisionSystem`] if there is one.
+pub fn trigger_overlaps(ctx: &mut Context, trigger_id: Entity) -> Vec<Entity> {
+    let Some(body) = ctx.try_select::<RigidBody>(trigger_id).cloned() else {
+        return Vec::new();
+    };
+    let Some(trigger) = ctx.try_select::<TriggerCollider>(trigger_id).cloned() else {
+        return Vec::new();
+    };
+    let rect = trigger.rect(&body);
+    let candidates = match ctx.try_resource::<Broadphase>() {
+        Some(broadphase) => broadphase.query(rect),
+        None => query_entities!(ctx, RigidBody),
+    };
+    candidates
+        .into_iter()
+        .filter(|&other_id| other_id != trigger_id)
+        .filter(|&other_id| {
+            colliders_of(ctx, other_id)
+                .into_iter()
+                .any(|(layers, mask, other_rect)| {
+                    rect.overlaps(other_rect)
+                        && layers_collide((trigger.layers, trigger.mask), (layers, mask))
+                })
+        })
+        .collect()
+}
+
+/// Finds when `body` first touches `other_body` while moving during `delta`,
+/// if the side of `other_body` it touches is checked.
 fn find_collisions<F: Fn(QuadDirection, V2) -> bool>(
-    intersections: &mut Vec<Collision>,
+    collisions: &mut Vec<Collision>,
     body: &RigidBody,
     other_body: &RigidBody,
     delta: f64,
     direction_checked: F,
 ) {
-    use QuadDirection::*;
-
     let delta_pos = body.vel.extend(delta);
     let rect = Rect::new(body.pos, body.size).moving(delta_pos);
-
     let other_rect = Rect::new(other_body.pos, other_body.size);
 
     if !rect.rect_within_reach(other_rect) {
         return;
     }
+    let Some(Contact { time, side, pos }) = rect.sweep(other_rect) else {
+        return;
+    };
+    if !direction_checked(side.reverse(), delta_pos) {
+        return;
+    }
+    collisions.push(Collision {
+        pos,
+        direction: side,
+        distance_factor: time,
+    });
+}
 
-    for side in [Top, Right, Bottom, Left] {
-        if !direction_checked(side.into(), delta_pos) {
-            continue;
-        }
-        if delta_pos_is_opposite_dir(side, delta_pos) {
-            continue;
-        }
-        let (p0, p1) = rect.side_corners(side.reverse());
-        let (c0, c1) = other_rect.side_corners(side);
-        for p in [p0, p1] {
-            if let Some(Intersection {
-                pos,
-                distance_factor,
-            }) = p
-                .moving(delta_pos)
-                .line_segment_intersect(Line::new(c0, c1))
-            {
-                intersections.push(Collision {
-                    pos,
-                    direction: side.reverse(),
-                    distance_factor,
-                });
-                return;
-            }
-        }
-        for p in [c0, c1] {
-            if let Some(Intersection {
-                pos: _,
-                distance_factor,
-            }) = p
-                .moving(delta_pos.reverse())
-                .line_segment_intersect(Line::new(p0, p1))
-            {
-                intersections.push(Collision {
-                    pos: p,
-                    direction: side.reverse(),
-                    distance_factor,
-                });
-                return;
-            }
-        }
+#[test]
+fn headless_falling_body_comes_to_rest_on_collider() {
+    let mut game = crate::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(VelocitySystem);
+    ctx.add_system(GravitySystem);
+    let body_id = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_size(V2::new(32.0, 32.0))
+            .with_gravity(),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(-100.0, 200.0))
+            .with_size(V2::new(300.0, 50.0)),
+        SolidCollider::new(),
+    );
+
+    for _ in 0..300 {
+        game.step();
+    }
+
+    let mut ctx = game.context();
+    let body = ctx.select::<RigidBody>(body_id).clone();
+    assert!(
+        (body.pos.y + body.size.y - 200.0).abs() < 1.0,
+        "expected body to rest on top of collider, got {body:?}"
+    );
+    let collider = ctx.select::<SolidCollider>(body_id);
+    assert_eq!(collider.colliding, Some(OctoDirection::Bottom));
+}
+
+#[test]
+fn fast_bodies_stop_at_thin_colliders_instead_of_tunnelling() {
+    let mut game = crate::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(VelocitySystem);
+    let launched = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(0.0, 0.0))
+            .with_size(V2::new(32.0, 32.0))
+            .with_vel(V2::new(40_000.0, 0.0)),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    let falling = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(1000.0, -500.0))
+            .with_size(V2::new(32.0, 32.0))
+            .with_vel(V2::new(0.0, 60_000.0)),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    // thinner than either body moves in a single update
+    crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(500.0, -50.0))
+            .with_size(V2::new(4.0, 100.0)),
+        SolidCollider::new(),
+    );
+    crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(900.0, 200.0))
+            .with_size(V2::new(300.0, 4.0)),
+        SolidCollider::new(),
+    );
+
+    for _ in 0..10 {
+        game.step();
+    }
+
+    let mut ctx = game.context();
+    let body = ctx.select::<RigidBody>(launched).clone();
+    assert!(
+        body.pos.x + body.size.x <= 500.0 && body.vel.x == 0.0,
+        "expected body to stop at the wall, got {body:?}"
+    );
+    let body = ctx.select::<RigidBody>(falling).clone();
+    assert!(
+        (body.pos.y + body.size.y - 200.0).abs() < 1.0 && body.vel.y == 0.0,
+        "expected body to land on the platform, got {body:?}"
+    );
+    assert_eq!(ctx.resource::<Broadphase>().len(), 4);
+}
+
+#[test]
+fn layers_and_masks_decide_what_collides_and_what_triggers_overlap() {
+    const TERRAIN: CollisionLayers = CollisionLayers::layer(1);
+    const PLAYERS: CollisionLayers = CollisionLayers::layer(2);
+    const PROJECTILES: CollisionLayers = CollisionLayers::layer(3);
+    const SECRETS: CollisionLayers = CollisionLayers::layer(4);
+
+    let mut game = crate::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(VelocitySystem);
+    ctx.add_system(GravitySystem);
+    let falling = |x| {
+        RigidBody::new()
+            .with_pos(V2::new(x, 0.0))
+            .with_size(V2::new(32.0, 32.0))
+            .with_gravity()
+    };
+    let player = crate::spawn!(
+        &mut ctx,
+        falling(0.0),
+        SolidCollider::new()
+            .resolving(DefaultResolver)
+            .layers(PLAYERS)
+            .mask(CollisionLayers::ALL.without(SECRETS)),
+    );
+    let projectile = crate::spawn!(
+        &mut ctx,
+        falling(100.0),
+        SolidCollider::new()
+            .resolving(DefaultResolver)
+            .layers(PROJECTILES)
+            .mask(PLAYERS),
+    );
+    let floor = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(-100.0, 200.0))
+            .with_size(V2::new(300.0, 50.0)),
+        SolidCollider::new().layers(TERRAIN),
+    );
+    let zone = RigidBody::new()
+        .with_pos(V2::new(-100.0, 150.0))
+        .with_size(V2::new(300.0, 100.0));
+    let player_zone = crate::spawn!(&mut ctx, zone.clone(), TriggerCollider::new().mask(PLAYERS));
+    let secret_zone = crate::spawn!(&mut ctx, zone, TriggerCollider::new().layers(SECRETS));
+
+    for _ in 0..300 {
+        game.step();
+    }
+
+    let mut ctx = game.context();
+    let body = ctx.select::<RigidBody>(player).clone();
+    assert!(
+        (body.pos.y + body.size.y - 200.0).abs() < 1.0,
+        "expected the player to land, got {body:?}"
+    );
+    let body = ctx.select::<RigidBody>(projectile).clone();
+    assert!(
+        body.pos.y > 250.0,
+        "expected the projectile to pass through the floor, got {body:?}"
+    );
+    assert_eq!(trigger_overlaps(&mut ctx, player_zone), vec![player]);
+    assert_eq!(trigger_overlaps(&mut ctx, secret_zone), vec![floor]);
+}
+
+#[test]
+fn triggers_send_enter_stay_and_exit_events_per_pair() {
+    let mut game = crate::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(VelocitySystem);
+    ctx.add_system(TriggerSystem);
+    let zone = crate::spawn!(
+        &mut ctx,
+        RigidBody::new().with_pos(V2::new(100.0, 0.0)),
+        TriggerCollider::new().size(V2::new(10.0, 100.0)),
+    );
+    // overlaps the zone for 2 updates, moving 6 px per update
+    let runner = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(90.0, 0.0))
+            .with_size(V2::new(4.0, 4.0))
+            .with_vel(V2::new(6.0 / crate::Game::FIXED_DELTA, 0.0)),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    let bystander = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(102.0, 50.0))
+            .with_size(V2::new(4.0, 4.0)),
+        SolidCollider::new(),
+    );
+
+    let mut events = Vec::new();
+    for _ in 0..6 {
+        game.step();
+        let ctx = game.context();
+        let entered = ctx.read_events::<TriggerEntered>();
+        let stayed = ctx.read_events::<TriggerStayed>();
+        let exited = ctx.read_events::<TriggerExited>();
+        assert!(entered.iter().all(|event| event.trigger == zone));
+        assert!(stayed.iter().all(|event| event.trigger == zone));
+        assert!(exited.iter().all(|event| event.trigger == zone));
+        let entered = entered.iter().map(|event| ("enter", event.other));
+        let stayed = stayed.iter().map(|event| ("stay", event.other));
+        let exited = exited.iter().map(|event| ("exit", event.other));
+        events.push(entered.chain(stayed).chain(exited).collect::<Vec<_>>());
+    }
+    assert_eq!(
+        events,
+        vec![
+            vec![("enter", bystander)],
+            vec![("enter", runner), ("stay", bystander)],
+            vec![("stay", runner), ("stay", bystander)],
+            vec![("stay", bystander), ("exit", runner)],
+            vec![("stay", bystander)],
+            vec![("stay", bystander)],
+        ]
+    );
+
+    let mut ctx = game.context();
+    ctx.despawn(bystander);
+    game.step();
+    let ctx = game.context();
+    let exited = ctx.read_events::<TriggerExited>();
+    assert_eq!(
+        exited,
+        vec![TriggerExited {
+            trigger: zone,
+            other: bystander
+        }]
+    );
+}
+
+#[test]
+fn ignored_bodies_drop_through_shallow_colliders() {
+    let mut game = crate::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(VelocitySystem);
+    ctx.add_system(GravitySystem);
+    let falling = |x| {
+        RigidBody::new()
+            .with_pos(V2::new(x, 100.0))
+            .with_size(V2::new(32.0, 32.0))
+            .with_gravity()
+    };
+    let dropping = crate::spawn!(
+        &mut ctx,
+        falling(0.0),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    let standing = crate::spawn!(
+        &mut ctx,
+        falling(100.0),
+        SolidCollider::new().resolving(DefaultResolver),
+    );
+    let platform = crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(-100.0, 200.0))
+            .with_size(V2::new(300.0, 10.0)),
+        ShallowCollider::new().with_direction(QuadDirection::Top),
+    );
+    crate::spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(-100.0, 400.0))
+            .with_size(V2::new(300.0, 50.0)),
+        SolidCollider::new(),
+    );
+
+    for _ in 0..120 {
+        game.step();
+    }
+    let mut ctx = game.context();
+    for id in [dropping, standing] {
+        let body = ctx.select::<RigidBody>(id).clone();
+        assert!(
+            (body.pos.y + body.size.y - 200.0).abs() < 1.0,
+            "expected body to rest on the platform, got {body:?}"
+        );
+    }
+    let collider = ctx.select::<ShallowCollider>(platform);
+    collider.ignore(dropping, 0.25);
+    collider.ignore(dropping, 0.1);
+    assert!(collider.ignores(dropping) && !collider.ignores(standing));
+
+    for _ in 0..300 {
+        game.step();
     }
+    let mut ctx = game.context();
+    let body = ctx.select::<RigidBody>(dropping).clone();
+    assert!(
+        (body.pos.y + body.size.y - 400.0).abs() < 1.0,
+        "expected body to drop through onto the floor, got {body:?}"
+    );
+    let body = ctx.select::<RigidBody>(standing).clone();
+    assert!(
+        (body.pos.y + body.size.y - 200.0).abs() < 1.0,
+        "expected body to stay on the platform, got {body:?}"
+    );
+    assert!(!ctx.select::<ShallowCollider>(platform).ignores(dropping));
 }
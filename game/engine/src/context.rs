@@ -1,29 +1,27 @@
-use std::{
-    any::TypeId,
-    collections::{HashMap, HashSet},
-    fs::File,
-    io::BufReader,
-    path::{Path, PathBuf},
-    rc::Rc,
-};
+use std::{any::TypeId, rc::Rc};
 
-use sdl2::{
-    controller::{Button as ControllerButton, GameController as SdlGameController},
-    image::LoadTexture,
-    keyboard::Keycode,
-    mouse::MouseButton,
-    pixels::Color,
-    rect::{Point, Rect},
-    render::{BlendMode, Canvas, Texture as SdlTexture, TextureCreator},
-    ttf::Sdl2TtfContext,
-    video::{Window, WindowContext},
-};
+use sdl2::{controller::Button as ControllerButton, keyboard::Keycode, mouse::MouseButton};
 
-use crate::{game::ControllerPosition, texture::TextTextureKey, Game, V2};
+use crate::{
+    camera::Camera,
+    capture::{FrameCaptured, FrameRecorder},
+    input::ControllerPosition,
+    text_cache::{TextCacheLimits, TextCacheStats},
+    texture::TextTextureKey,
+    DisplayMode, DrawCommand, Game, RenderLayer, V2,
+};
 
 use super::{
-    entity::Entity, font::Font, id::Id, system::System, text::Text, texture::Texture, Component,
-    Error,
+    entity::Entity,
+    events::Events,
+    id::Id,
+    schedule::{self, Schedule, ScheduledSystem},
+    state::Transition,
+    storage::{SparseSet, Storage},
+    system::System,
+    text::{layout_text, Text, TextAlign, TextStyle},
+    texture::Texture,
+    Component, Error,
 };
 
 pub struct ComponentQuery<T>(std::marker::PhantomData<T>);
@@ -34,6 +32,8 @@ impl<T> ComponentQuery<T> {
     }
 }
 
+/// Iterates `(Entity, &T0, &T1, ..)` for every entity with all of the components,
+/// borrowing the context. Loops which need the context mutably use [`query_entities!`].
 #[macro_export]
 macro_rules! query {
     ($ctx:expr, $t:ty) => {
@@ -52,23 +52,21 @@ macro_rules! query {
     };
 }
 
+/// Collects the entities [`query!`] would yield, leaving the context free to be used mutably.
 #[macro_export]
-macro_rules! query_one {
-    ($ctx:expr, $t:ty) => {
-        {
-            #[allow(unused_imports)]
-            use $crate::QueryRunner;
-            let mut iter = $crate::ComponentQuery::<$t>::new().run($ctx).into_iter();
-            let value = iter.next().expect(format!("query failed: could not query one, at {}:{}", file!(), line!()).as_ref());
-            assert!(iter.next().is_none(), "query failed: could not exactly query one, at {}:{}", file!(), line!());
-            value
-        }
+macro_rules! query_entities {
+    ($ctx:expr, $($ts:ty),+) => {
+        $crate::query!($ctx, $($ts),+)
+            .map(|(id, ..)| id)
+            .collect::<Vec<$crate::Entity>>()
     };
+}
+
+#[macro_export]
+macro_rules! query_one {
     ($ctx:expr, $($ts:ty),+) => {
         {
-            #[allow(unused_imports)]
-            use $crate::QueryRunner;
-            let mut iter = $crate::ComponentQuery::<($($ts),+)>::new().run($ctx).into_iter();
+            let mut iter = $crate::query_entities!($ctx, $($ts),+).into_iter();
             let value = iter.next().expect(format!("query failed: could not query one, at {}:{}", file!(), line!()).as_ref());
             assert!(iter.next().is_none(), "query failed: could not exactly query one, at {}:{}", file!(), line!());
             value
@@ -79,10 +77,15 @@ macro_rules! query_one {
 #[macro_export]
 macro_rules! spawn {
     ($ctx:expr, [$($ts:expr),+ $(,)?]) => {
-        $crate::Context::spawn($ctx, vec![$(Box::new($ts)),+])
+        $crate::spawn!($ctx, $($ts),+)
     };
     ($ctx:expr, $($ts:expr),+ $(,)?) => {
-        $crate::Context::spawn($ctx, vec![$(Box::new($ts)),+])
+        {
+            let ctx: &mut $crate::Context = $ctx;
+            let id = ctx.spawn_empty();
+            $(ctx.insert(id, $ts);)+
+            id
+        }
     };
 }
 
@@ -91,114 +94,172 @@ where
     'game: 'context,
 {
     game: &'context mut Game<'game>,
-    ttf_context: *const Sdl2TtfContext,
-    texture_creator: *const TextureCreator<WindowContext>,
 }
 
 impl<'context, 'game> Context<'context, 'game> {
     pub fn new(game: &'context mut Game<'game>) -> Self {
-        let sdl2_ttf_context: *const Sdl2TtfContext = &game.ttf_context;
-        let texture_creator: *const TextureCreator<WindowContext> = &game.texture_creator;
-        Self {
-            game,
-            ttf_context: sdl2_ttf_context,
-            texture_creator,
-        }
+        Self { game }
     }
 
     pub fn quit(&mut self) {
         self.game.should_quit = true;
     }
 
-    pub fn entities_with_component<T: 'static + Component>(&self) -> Vec<u64> {
-        let entity_type_id = TypeId::of::<T>();
-        self.game
-            .entities
+    /// Entities pending despawn are left out, as they are as good as gone.
+    pub fn entities_with_component<T: 'static + Component>(&self) -> Vec<Entity> {
+        crate::query_entities!(self, T)
+    }
+
+    /// Entities which may have every one of the given component types, for queries to look
+    /// their components up. Only the smallest storage is walked, and entities pending
+    /// despawn are left out. Empty if any of the types has no storage.
+    pub(crate) fn query_candidates(
+        &self,
+        type_ids: &[TypeId],
+    ) -> impl Iterator<Item = Entity> + '_ {
+        let smallest = type_ids
             .iter()
-            .filter_map(|opt| {
-                opt.as_ref().and_then(|Entity(id, components)| {
-                    let contains_component = components
-                        .iter()
-                        .any(|entity| (*entity).inner_type_id() == entity_type_id);
-                    if contains_component {
-                        Some(*id)
-                    } else {
-                        None
-                    }
-                })
-            })
-            .collect()
-    }
-
-    pub fn select<T: 'static + Component>(&mut self, entity_id: u64) -> &mut T {
-        let entity_type_id = TypeId::of::<T>();
-        let Entity(_id, components) = self.game
-            .entities
-            .iter_mut()
-            .find(|opt| opt.as_ref().is_some_and(|Entity(id, _)| *id == entity_id))
-            .and_then(|v| v.as_mut())
-            .expect("tried to get entity_component of removed id, are you removing it while looping over it?");
-
-        let component = components
-            .iter_mut()
-            .find_map(|entity| {
-                let is_id = (*entity).inner_type_id() == entity_type_id;
-                if is_id {
-                    Some(entity.as_any().downcast_mut::<T>().unwrap())
-                } else {
-                    None
-                }
+            .map(|type_id| self.game.components.get(type_id))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|storages| storages.into_iter().min_by_key(|storage| storage.len()));
+        smallest
+            .into_iter()
+            .flat_map(|storage| storage.entities().iter().copied())
+            .filter(|entity| !self.game.entities.is_despawning(*entity))
+    }
+
+    pub(crate) fn storage<T: 'static + Component>(&self) -> Option<&SparseSet<T>> {
+        self.game
+            .components
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref::<SparseSet<T>>())
+    }
+
+    fn storage_mut<T: 'static + Component>(&mut self) -> Option<&mut SparseSet<T>> {
+        self.game
+            .components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<SparseSet<T>>())
+    }
+
+    /// Iterates every component of type `T` alongside the entity owning it,
+    /// leaving out entities pending despawn like [`query!`].
+    pub fn components<T: 'static + Component>(&self) -> impl Iterator<Item = (Entity, &T)> + '_ {
+        let entities = &self.game.entities;
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(SparseSet::iter)
+            .filter(|(entity, _)| !entities.is_despawning(*entity))
+    }
+
+    pub fn components_mut<T: 'static + Component>(
+        &mut self,
+    ) -> impl Iterator<Item = (Entity, &mut T)> + '_ {
+        let entities = &self.game.entities;
+        self.game
+            .components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<SparseSet<T>>())
+            .into_iter()
+            .flat_map(SparseSet::iter_mut)
+            .filter(|(entity, _)| !entities.is_despawning(*entity))
+    }
+
+    /// Panics if the entity is dead or doesn't have the component, see [`Context::try_select`].
+    pub fn select<T: 'static + Component>(&mut self, entity: Entity) -> &mut T {
+        if !self.game.entities.is_alive(entity) {
+            panic!("tried to get component of despawned entity {entity}");
+        }
+        self.storage_mut::<T>()
+            .and_then(|storage| storage.get_mut(entity))
+            .unwrap_or_else(|| {
+                panic!(
+                    "entity {entity} has no component {}",
+                    std::any::type_name::<T>()
+                )
             })
-            .unwrap();
-        component
+    }
+
+    /// Entities despawned during this update can still be selected until it ends.
+    pub fn try_select<T: 'static + Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.game.entities.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()
+            .and_then(|storage| storage.get_mut(entity))
+    }
+
+    /// Like [`Context::try_select`], but borrowing the context immutably,
+    /// so components can be looked up while iterating a [`query!`].
+    pub fn get<T: 'static + Component>(&self, entity: Entity) -> Option<&T> {
+        if !self.game.entities.is_alive(entity) {
+            return None;
+        }
+        self.storage::<T>().and_then(|storage| storage.get(entity))
     }
 
     pub fn select_one<T: 'static + Component>(&mut self) -> &mut T {
         let entity_id = query_one!(self, T);
-        let entity_type_id = TypeId::of::<T>();
-        let Entity(_id, components) = self.game
-            .entities
-            .iter_mut()
-            .find(|opt| opt.as_ref().is_some_and(|Entity(id, _)| *id == entity_id))
-            .and_then(|v| v.as_mut())
-            .expect("tried to get entity_component of removed id, are you removing it while looping over it?");
-
-        let component = components
-            .iter_mut()
-            .find_map(|entity| {
-                let is_id = (*entity).inner_type_id() == entity_type_id;
-                if is_id {
-                    Some(entity.as_any().downcast_mut::<T>().unwrap())
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        component
+        self.select::<T>(entity_id)
     }
 
     pub fn clone_one<T: 'static + Component + Clone>(&mut self) -> T {
         let entity_id = query_one!(self, T);
-        let entity_type_id = TypeId::of::<T>();
-        let Entity(_id, components) = self.game
-            .entities
-            .iter_mut()
-            .find(|opt| opt.as_ref().is_some_and(|Entity(id, _)| *id == entity_id))
-            .and_then(|v| v.as_mut())
-            .expect("tried to get entity_component of removed id, are you removing it while looping over it?");
-
-        let component = components
-            .iter_mut()
-            .find_map(|entity| {
-                let is_id = (*entity).inner_type_id() == entity_type_id;
-                if is_id {
-                    Some(entity.as_any().downcast_mut::<T>().unwrap())
-                } else {
-                    None
-                }
-            })
-            .unwrap();
-        component.clone()
+        self.select::<T>(entity_id).clone()
+    }
+
+    /// Stores a value which isn't tied to any entity, replacing the previous value of the same type.
+    pub fn insert_resource<T: 'static>(&mut self, resource: T) {
+        self.game
+            .resources
+            .insert(TypeId::of::<T>(), Box::new(resource));
+    }
+
+    /// Panics if the resource hasn't been inserted, see [`Context::try_resource`].
+    pub fn resource<T: 'static>(&mut self) -> &mut T {
+        self.try_resource::<T>().unwrap_or_else(|| {
+            panic!(
+                "tried to get resource {} which has not been inserted",
+                std::any::type_name::<T>()
+            )
+        })
+    }
+
+    pub fn try_resource<T: 'static>(&mut self) -> Option<&mut T> {
+        self.game
+            .resources
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast_mut::<T>())
+    }
+
+    pub fn remove_resource<T: 'static>(&mut self) -> Option<T> {
+        self.game
+            .resources
+            .remove(&TypeId::of::<T>())
+            .and_then(|resource| resource.downcast::<T>().ok())
+            .map(|resource| *resource)
+    }
+
+    /// The event can be read by every system during the next update.
+    pub fn send_event<E: 'static>(&mut self, event: E) {
+        self.game
+            .events
+            .entry(TypeId::of::<E>())
+            .or_insert_with(|| Box::new(Events::<E>::new()))
+            .as_any_mut()
+            .downcast_mut::<Events<E>>()
+            .unwrap()
+            .send(event);
+    }
+
+    /// Events of type `E` sent during the previous update.
+    pub fn read_events<E: 'static + Clone>(&self) -> Vec<E> {
+        self.game
+            .events
+            .get(&TypeId::of::<E>())
+            .and_then(|events| events.as_any().downcast_ref::<Events<E>>())
+            .map(|events| events.readable().to_vec())
+            .unwrap_or_default()
     }
 
     pub fn load_font<P>(&mut self, path: P, size: u16) -> Result<Id, Error>
@@ -206,22 +267,29 @@ impl<'context, 'game> Context<'context, 'game> {
         P: AsRef<std::path::Path>,
     {
         let path = path.as_ref();
-        let existing_id = self.game.fonts.iter().find_map(|(id, s, p, _)| {
-            if path == p && size == *s {
-                Some(*id)
-            } else {
-                None
-            }
-        });
-        if let Some(id) = existing_id {
-            Ok(id)
-        } else {
-            let font = Font(unsafe { (*self.ttf_context).load_font(path, size)? });
-            let id = self.game.entity_id_counter;
-            self.game.entity_id_counter += 1;
-            self.game.fonts.push((id, size, path.to_path_buf(), font));
-            Ok(id)
+        let frame = self.game.frame;
+        if let Some(font) = self.game.text_cache.font(path, size) {
+            let id = font.id;
+            self.ensure_font_loaded(id)?;
+            return Ok(id);
         }
+        let id = self.game.id_counter;
+        self.game.backend.load_font(id, path, size)?;
+        self.game.id_counter += 1;
+        self.game.text_cache.insert_font(id, path, size, frame);
+        Ok(id)
+    }
+
+    /// Loads a font again if it was evicted from the text cache, keeping its id.
+    fn ensure_font_loaded(&mut self, font_id: Id) -> Result<(), Error> {
+        let Some(font) = self.game.text_cache.use_font(font_id, self.game.frame) else {
+            return Ok(());
+        };
+        self.game
+            .backend
+            .load_font(font.id, &font.path, font.size)?;
+        font.loaded = true;
+        Ok(())
     }
 
     pub fn load_texture<P>(&mut self, path: P) -> Result<Texture, Error>
@@ -231,127 +299,170 @@ impl<'context, 'game> Context<'context, 'game> {
         if let Some(id) = self.game.texture_path_to_id_map.get(path.as_ref()) {
             return Ok(Texture(*id));
         }
-        let texture: SdlTexture<'game> =
-            unsafe { (*self.texture_creator).load_texture(path.as_ref())? };
-        let id = self.game.entity_id_counter;
-        self.game.entity_id_counter += 1;
-        self.game.textures.push((id, texture));
+        let id = self.game.id_counter;
+        self.game.backend.load_texture(id, path.as_ref())?;
+        self.game.id_counter += 1;
         self.game
             .texture_path_to_id_map
             .insert(path.as_ref().into(), id);
         Ok(Texture(id))
     }
 
+    /// Loads a texture again from the path it was first loaded from, keeping its id,
+    /// so everything drawing it shows the new image. Returns `false` if no texture
+    /// has been loaded from `path`.
+    pub fn reload_texture<P>(&mut self, path: P) -> Result<bool, Error>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let Some(id) = self.game.texture_path_to_id_map.get(path.as_ref()).copied() else {
+            return Ok(false);
+        };
+        self.game.backend.load_texture(id, path.as_ref())?;
+        Ok(true)
+    }
+
     pub fn render_text<S: Into<String>>(
         &mut self,
         font_id: Id,
         text: S,
         rgb: (u8, u8, u8),
+    ) -> Result<Text, Error> {
+        self.render_text_styled(font_id, text, &TextStyle::new().color(rgb))
+    }
+
+    /// Renders text which may span several lines, wrap, be decorated or change color midway,
+    /// see [`TextStyle`]. Like [`Context::render_text`], the texture is reused for the same
+    /// text, font and style.
+    pub fn render_text_styled<S: Into<String>>(
+        &mut self,
+        font_id: Id,
+        text: S,
+        style: &TextStyle,
     ) -> Result<Text, Error> {
         let text = text.into();
-        let key = TextTextureKey(font_id, text.clone(), rgb);
-        if let Some(existing) = self.game.text_textures.get(&key) {
-            return Ok(existing.clone());
+        let key = TextTextureKey(font_id, text.clone(), style.clone());
+        if let Some(existing) = self.game.text_cache.get(&key, self.game.frame) {
+            return Ok(existing);
         };
-        let Font(font) = self
-            .game
-            .fonts
-            .iter()
-            .find_map(|(id, _, _, font)| if *id == font_id { Some(font) } else { None })
-            .ok_or("tried to render non-loaded text")?;
-        let (r, g, b) = rgb;
-        let surface = font.render(&text).blended(Color { r, g, b, a: 255 })?;
-        let texture = unsafe {
-            surface.as_texture(&*self.texture_creator as &TextureCreator<WindowContext>)
-        }?;
-        let id = self.game.entity_id_counter;
-        self.game.entity_id_counter += 1;
-
-        let texture_size = (texture.query().width, texture.query().height);
+        self.ensure_font_loaded(font_id)?;
+        let id = self.game.id_counter;
+        let texture_size = if style.is_plain(&text) {
+            self.game
+                .backend
+                .render_text(id, font_id, &text, style.color)?
+        } else {
+            let backend = &mut self.game.backend;
+            let (runs, size) = layout_text(&text, style, |text| backend.text_size(font_id, text))?;
+            backend.render_text_runs(id, font_id, &runs, size)?;
+            size
+        };
+        self.game.id_counter += 1;
+
         let text = Text {
             texture: Texture(id),
-            size: V2::new(
-                texture_size.0.try_into().unwrap(),
-                texture_size.1.try_into().unwrap(),
-            ),
+            size: V2::new(texture_size.0.into(), texture_size.1.into()),
         };
-        self.game.text_textures.insert(key, text.clone());
-        self.game.textures.push((id, texture));
+        self.game.text_cache.insert(key, text, self.game.frame);
         Ok(text)
     }
 
     pub fn text_size<S: AsRef<str>>(&mut self, font_id: Id, text: S) -> Result<(u32, u32), Error> {
-        let Font(font) = self
-            .game
-            .fonts
-            .iter()
-            .find_map(|(id, _, _, font)| if *id == font_id { Some(font) } else { None })
-            .ok_or("tried to render non-loaded text")?;
-        Ok(font.size_of(text.as_ref()).map_err(|e| e.to_string())?)
+        self.ensure_font_loaded(font_id)?;
+        self.game.backend.text_size(font_id, text.as_ref())
     }
 
     pub fn texture_size(&mut self, texture: Texture) -> Result<(u32, u32), Error> {
-        let texture = self
-            .game
-            .textures
-            .iter()
-            .find_map(|v| if v.0 == texture.0 { Some(&v.1) } else { None })
-            .ok_or("invalid sprite id")?;
-        Ok((texture.query().width, texture.query().height))
+        self.game.backend.texture_size(texture.0)
+    }
+
+    /// Queues a draw in screen space, drawn once every system has rendered.
+    /// Draws are ordered by layer, then by depth, then by the order they were made in.
+    pub fn draw(&mut self, layer: RenderLayer, depth: i32, command: DrawCommand) {
+        if let DrawCommand::Texture { texture, .. } = &command {
+            self.game
+                .text_cache
+                .touch_texture(texture.0, self.game.frame);
+        }
+        self.game.render_queue.push(layer, depth, command);
+    }
+
+    /// Like [`Context::draw`], but in world space,
+    /// as seen through the [`Camera`] resource if there is one.
+    pub fn draw_world(
+        &mut self,
+        layer: RenderLayer,
+        depth: i32,
+        command: DrawCommand,
+    ) -> Result<(), Error> {
+        let Some(camera) = self.try_resource::<Camera>().cloned() else {
+            self.draw(layer, depth, command);
+            return Ok(());
+        };
+        let command = match command {
+            DrawCommand::Texture { texture, pos, opts } => {
+                let size = match (opts.size, opts.source) {
+                    (Some(size), _) => size,
+                    (None, Some((_, source_size))) => source_size,
+                    (None, None) => {
+                        let (width, height) = self.texture_size(texture)?;
+                        V2::new(width.into(), height.into())
+                    }
+                };
+                DrawCommand::Texture {
+                    texture,
+                    pos: camera.world_to_screen(pos),
+                    opts: opts.size(camera.scale(size)),
+                }
+            }
+            DrawCommand::Rect { rgba, pos, size } => DrawCommand::Rect {
+                rgba,
+                pos: camera.world_to_screen(pos),
+                size: camera.scale(size),
+            },
+        };
+        self.draw(layer, depth, command);
+        Ok(())
     }
 
+    /// Draws on the layer of the rendering system, see [`Schedule::render_layer`].
     pub fn draw_texture(
         &mut self,
         texture: Texture,
         pos: V2,
         opts: DrawTextureOpts,
     ) -> Result<(), Error> {
-        let texture = self
-            .game
-            .textures
-            .iter_mut()
-            .find_map(|v| {
-                if v.0 == texture.0 {
-                    Some(&mut v.1)
-                } else {
-                    None
-                }
-            })
-            .ok_or("invalid sprite id")?;
-        let size = opts.size.unwrap_or(V2::new(
-            texture.query().width as f64,
-            texture.query().height as f64,
-        ));
-        if let Some(color) = opts.color_mod {
-            texture.set_color_mod(color.0, color.1, color.2);
-        } else {
-            texture.set_color_mod(255, 255, 255);
-        }
-        if let Some(alpha) = opts.opacity {
-            texture.set_alpha_mod((alpha * 255.0) as u8);
-        } else {
-            texture.set_alpha_mod(255);
-        }
-        if let Some(angle) = opts.angle {
-            self.game.canvas.copy_ex(
-                texture,
-                None,
-                Rect::new(pos.x as i32, pos.y as i32, size.x as u32, size.y as u32),
-                angle,
-                Point::new(0, 0),
-                false,
-                false,
-            )?;
-        } else {
-            self.game.canvas.copy(
-                texture,
-                None,
-                Rect::new(pos.x as i32, pos.y as i32, size.x as u32, size.y as u32),
-            )?;
-        }
+        let layer = self.game.render_layer;
+        self.draw(layer, 0, DrawCommand::Texture { texture, pos, opts });
         Ok(())
     }
 
+    /// Draws text with `pos` at its left edge, center or right edge, depending on `align`.
+    pub fn draw_text(&mut self, text: Text, pos: V2, align: TextAlign) -> Result<(), Error> {
+        if !self.game.text_cache.contains_texture(text.texture.0) {
+            return Err(format!(
+                "text texture {} was evicted from the text cache after going undrawn, \
+                 render the text again rather than keeping its handle",
+                text.texture.0
+            )
+            .into());
+        }
+        let x = pos.x + align.offset(0.0, text.size.x);
+        self.draw_texture(text.texture, V2::new(x, pos.y), DrawTextureOpts::new())
+    }
+
+    /// Like [`Context::draw_texture`], but in world space,
+    /// as seen through the [`Camera`] resource if there is one.
+    pub fn draw_texture_world(
+        &mut self,
+        texture: Texture,
+        pos: V2,
+        opts: DrawTextureOpts,
+    ) -> Result<(), Error> {
+        let layer = self.game.render_layer;
+        self.draw_world(layer, 0, DrawCommand::Texture { texture, pos, opts })
+    }
+
     pub fn draw_rect(
         &mut self,
         rgb: (u8, u8, u8),
@@ -361,9 +472,7 @@ impl<'context, 'game> Context<'context, 'game> {
         h: u32,
     ) -> Result<(), Error> {
         let (r, g, b) = rgb;
-        self.game.canvas.set_draw_color(Color { r, g, b, a: 255 });
-        self.game.canvas.fill_rect(Rect::new(x, y, w, h))?;
-        Ok(())
+        self.draw_rect_alpha((r, g, b), 255, x, y, w, h)
     }
 
     pub fn draw_rect_alpha(
@@ -376,9 +485,16 @@ impl<'context, 'game> Context<'context, 'game> {
         h: u32,
     ) -> Result<(), Error> {
         let (r, g, b) = rgb;
-        self.game.canvas.set_blend_mode(BlendMode::Blend);
-        self.game.canvas.set_draw_color(Color { r, g, b, a });
-        self.game.canvas.fill_rect(Rect::new(x, y, w, h))?;
+        let layer = self.game.render_layer;
+        self.draw(
+            layer,
+            0,
+            DrawCommand::Rect {
+                rgba: (r, g, b, a),
+                pos: V2::new(x.into(), y.into()),
+                size: V2::new(w.into(), h.into()),
+            },
+        );
         Ok(())
     }
 
@@ -391,51 +507,78 @@ impl<'context, 'game> Context<'context, 'game> {
         h: u32,
         alpha: u8,
     ) -> Result<(), Error> {
-        let (r, g, b) = rgb;
-        self.game.canvas.set_draw_color(Color { r, g, b, a: alpha });
-        self.game
-            .canvas
-            .set_blend_mode(sdl2::render::BlendMode::Blend);
-        self.game.canvas.fill_rect(Rect::new(x, y, w, h))?;
-        Ok(())
+        self.draw_rect_alpha(rgb, alpha, x, y, w, h)
     }
 
-    pub fn spawn(&mut self, components: Vec<Box<dyn Component>>) -> Id {
-        let id = self.game.entity_id_counter;
-        self.game.entity_id_counter += 1;
-        let mut entity = Some(Entity(id, components));
-        let first_none = self.game.entities.iter().position(Option::is_none);
-        let Some(index) = first_none else {
-            self.game.entities.push(entity);
-            return id;
-        };
-        std::mem::swap(&mut self.game.entities[index], &mut entity);
-        id
+    /// Fills a rect in world space, as seen through the [`Camera`] resource if there is one.
+    pub fn draw_rect_world(
+        &mut self,
+        rgba: (u8, u8, u8, u8),
+        pos: V2,
+        size: V2,
+    ) -> Result<(), Error> {
+        let layer = self.game.render_layer;
+        self.draw_world(layer, 0, DrawCommand::Rect { rgba, pos, size })
     }
 
-    pub fn despawn(&mut self, entity_id: Id) {
-        let Some(index) = self
-            .game
-            .entities
-            .iter()
-            .position(|v| v.as_ref().is_some_and(|v| v.0 == entity_id))
-        else {
-            println!("tried to despawn {entity_id}; entity not found");
+    /// Creates an entity without components, see [`spawn!`] for spawning with components.
+    pub fn spawn_empty(&mut self) -> Entity {
+        let entity = self.game.entities.spawn();
+        self.game.states.own(entity);
+        entity
+    }
+
+    /// Adds a component to an entity, replacing any existing component of the same type.
+    pub fn insert<T: 'static + Component>(&mut self, entity: Entity, component: T) {
+        if !self.game.entities.is_alive(entity) {
+            println!("tried to insert component into despawned entity {entity}");
             return;
-        };
+        }
+        self.game
+            .components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(SparseSet::<T>::new()))
+            .as_any_mut()
+            .downcast_mut::<SparseSet<T>>()
+            .unwrap()
+            .insert(entity, component);
+    }
 
-        self.game.entities[index].take();
+    /// The entity is removed at the end of the current update,
+    /// until then it is left out of queries but can still be selected.
+    pub fn despawn(&mut self, entity: Entity) {
+        if !self.game.entities.queue_despawn(entity) {
+            println!("tried to despawn {entity}; entity not found");
+        }
     }
 
+    /// The system is owned by the state on top of the stack, if any.
     pub fn add_system<S, CTor>(&mut self, system_ctor: CTor) -> Id
     where
         S: System + 'static,
         CTor: Fn(Id) -> S,
+    {
+        self.add_system_with(system_ctor, |schedule| schedule)
+    }
+
+    /// Adds a system with its [`System::schedule`] adjusted, e.g. to add a run condition.
+    pub fn add_system_with<S, CTor, F>(&mut self, system_ctor: CTor, adjust_schedule: F) -> Id
+    where
+        S: System + 'static,
+        CTor: Fn(Id) -> S,
+        F: FnOnce(Schedule) -> Schedule,
     {
         let id = self.game.system_id_counter;
         self.game.system_id_counter += 1;
         let system = Rc::new(system_ctor(id));
-        self.game.systems.push((id, system.clone()));
+        let schedule = adjust_schedule(system.schedule());
+        self.game.systems.push(ScheduledSystem {
+            id,
+            system: system.clone(),
+            schedule,
+            state: self.game.states.top(),
+        });
+        schedule::sort(&mut self.game.systems);
         system.on_add(self).unwrap();
         id
     }
@@ -444,42 +587,133 @@ impl<'context, 'game> Context<'context, 'game> {
         self.game.systems_to_remove.push(system_id);
     }
 
+    /// Pushes a state with the given system at its root, at the end of the update.
+    /// The current state stops updating and rendering until the new state is popped.
+    pub fn push_state<S, CTor>(&mut self, system_ctor: CTor)
+    where
+        S: System + 'static,
+        CTor: Fn(Id) -> S + 'static,
+    {
+        self.push(system_ctor, false);
+    }
+
+    /// Like [`Context::push_state`], but the states below keep rendering, e.g. for a pause menu.
+    pub fn push_overlay<S, CTor>(&mut self, system_ctor: CTor)
+    where
+        S: System + 'static,
+        CTor: Fn(Id) -> S + 'static,
+    {
+        self.push(system_ctor, true);
+    }
+
+    fn push<S, CTor>(&mut self, system_ctor: CTor, overlay: bool)
+    where
+        S: System + 'static,
+        CTor: Fn(Id) -> S + 'static,
+    {
+        self.game.states.transitions.push(Transition::Push {
+            add_root_system: Box::new(move |ctx| ctx.add_system(system_ctor)),
+            overlay,
+        });
+    }
+
+    /// Pops the top state at the end of the update,
+    /// removing the systems and despawning the entities it owns.
+    pub fn pop_state(&mut self) {
+        self.game.states.transitions.push(Transition::Pop);
+    }
+
+    /// Pops the top state and pushes a new one in its place.
+    pub fn replace_state<S, CTor>(&mut self, system_ctor: CTor)
+    where
+        S: System + 'static,
+        CTor: Fn(Id) -> S + 'static,
+    {
+        self.pop_state();
+        self.push_state(system_ctor);
+    }
+
     pub fn key_just_pressed(&self, keycode: Keycode) -> bool {
         *self
             .game
+            .input
             .currently_pressed_keys
             .get(&keycode)
             .unwrap_or(&false)
     }
 
     pub fn key_pressed(&self, keycode: Keycode) -> bool {
-        self.game.currently_pressed_keys.contains_key(&keycode)
+        self.game
+            .input
+            .currently_pressed_keys
+            .contains_key(&keycode)
     }
 
     pub fn mouse_button_just_pressed(&self, button: MouseButton) -> bool {
         *self
             .game
+            .input
             .currently_pressed_mouse_buttons
             .get(&button)
             .unwrap_or(&false)
     }
 
+    pub fn display_mode(&self) -> DisplayMode {
+        self.game.display_mode
+    }
+
+    pub fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), Error> {
+        self.game.backend.set_display_mode(mode)?;
+        self.game.display_mode = mode;
+        Ok(())
+    }
+
+    /// Captures the current frame once every system has drawn it,
+    /// sent as a [`FrameCaptured`] event.
+    pub fn capture_frame(&mut self) {
+        self.game.capture_requested = true;
+    }
+
+    /// Captures frames into `recorder` from now on, replacing any previous recording.
+    pub fn start_recording(&mut self, recorder: FrameRecorder) {
+        self.game.recorder = Some(recorder);
+    }
+
+    pub fn stop_recording(&mut self) -> Option<FrameRecorder> {
+        self.game.recorder.take()
+    }
+
+    pub fn recorder(&self) -> Option<&FrameRecorder> {
+        self.game.recorder.as_ref()
+    }
+
+    pub fn text_cache_stats(&self) -> TextCacheStats {
+        self.game.text_cache.stats()
+    }
+
+    /// Changes how long text textures and fonts are kept unused, taking effect after this frame.
+    pub fn set_text_cache_limits(&mut self, limits: TextCacheLimits) {
+        self.game.text_cache.limits = limits;
+    }
+
+    /// In logical coordinates, no matter how the window is scaled.
     pub fn mouse_position(&self) -> (i32, i32) {
-        self.game.mouse_position
+        self.game.input.mouse_position
     }
 
     pub fn joystick_position(&self, id: Id) -> &ControllerPosition {
-        &self
-            .game
+        self.game
+            .input
             .controllers
             .iter()
             .find(|v| v.0 == id)
-            .map(|v| &v.2)
+            .map(|v| &v.1)
             .unwrap()
     }
 
     pub fn controller_button_pressed(&self, id: Id, button: ControllerButton) -> bool {
         self.game
+            .input
             .currently_pressed_controller_buttons
             .contains_key(&(id, button))
     }
@@ -487,6 +721,7 @@ impl<'context, 'game> Context<'context, 'game> {
     pub fn controller_button_just_pressed(&self, id: Id, button: ControllerButton) -> bool {
         *self
             .game
+            .input
             .currently_pressed_controller_buttons
             .get(&(id, button))
             .unwrap_or(&false)
@@ -494,6 +729,7 @@ impl<'context, 'game> Context<'context, 'game> {
 
     pub fn active_controllers(&self) -> impl Iterator<Item = Id> {
         self.game
+            .input
             .controllers
             .iter()
             .map(|v| v.0)
@@ -502,11 +738,14 @@ impl<'context, 'game> Context<'context, 'game> {
     }
 }
 
+#[derive(Clone)]
 pub struct DrawTextureOpts {
     pub color_mod: Option<(u8, u8, u8)>,
     pub opacity: Option<f64>,
     pub size: Option<V2>,
     pub angle: Option<f64>,
+    /// Position and size of the part of the texture to draw, e.g. a frame of a sprite sheet.
+    pub source: Option<(V2, V2)>,
 }
 
 impl DrawTextureOpts {
@@ -516,6 +755,7 @@ impl DrawTextureOpts {
             opacity: None,
             size: None,
             angle: None,
+            source: None,
         }
     }
     pub fn size(self, size: V2) -> Self {
@@ -539,4 +779,7 @@ impl DrawTextureOpts {
     pub fn angle(self, angle: Option<f64>) -> Self {
         Self { angle, ..self }
     }
+    pub fn source(self, source: Option<(V2, V2)>) -> Self {
+        Self { source, ..self }
+    }
 }
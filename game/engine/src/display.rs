@@ -0,0 +1,24 @@
+use crate::V2;
+
+/// Width everything is laid out and drawn in, before being scaled to fit the window.
+pub const LOGICAL_WIDTH: u32 = 1280;
+/// Height everything is laid out and drawn in, before being scaled to fit the window.
+pub const LOGICAL_HEIGHT: u32 = 720;
+/// [`LOGICAL_WIDTH`] and [`LOGICAL_HEIGHT`] as a vector, for world and screen space math.
+pub const LOGICAL_SIZE: V2 = V2 {
+    x: LOGICAL_WIDTH as f64,
+    y: LOGICAL_HEIGHT as f64,
+};
+
+/// How the window is shown.
+/// The logical resolution is scaled to fit in every mode, with black bars
+/// if the aspect ratio doesn't match.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// Switches the monitor to the logical resolution.
+    Fullscreen,
+    /// Covers the monitor at its own resolution.
+    Borderless,
+}
@@ -1,3 +1,115 @@
-use super::{id::Id, Component};
+/// Handle to an entity.
+/// Indices are reused after despawning, the generation makes sure handles
+/// to the despawned entity don't refer to whatever is spawned in its place.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
 
-pub struct Entity(pub Id, pub Vec<Box<dyn Component>>);
+impl Entity {
+    pub(crate) fn index(self) -> usize {
+        self.index as usize
+    }
+}
+
+impl std::fmt::Display for Entity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}v{}", self.index, self.generation)
+    }
+}
+
+struct Slot {
+    generation: u32,
+    alive: bool,
+    /// Queued for despawn, so it can be left out of queries without searching the queue.
+    despawning: bool,
+}
+
+/// Allocates entity handles and keeps track of which are alive.
+#[derive(Default)]
+pub(crate) struct Entities {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    to_despawn: Vec<Entity>,
+}
+
+impl Entities {
+    pub(crate) fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            let slot = &mut self.slots[index as usize];
+            slot.alive = true;
+            slot.despawning = false;
+            return Entity {
+                index,
+                generation: slot.generation,
+            };
+        }
+        let index = self.slots.len() as u32;
+        self.slots.push(Slot {
+            generation: 0,
+            alive: true,
+            despawning: false,
+        });
+        Entity {
+            index,
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn is_alive(&self, entity: Entity) -> bool {
+        self.slots
+            .get(entity.index())
+            .is_some_and(|slot| slot.alive && slot.generation == entity.generation)
+    }
+
+    pub(crate) fn is_despawning(&self, entity: Entity) -> bool {
+        self.slots
+            .get(entity.index())
+            .is_some_and(|slot| slot.despawning && slot.generation == entity.generation)
+    }
+
+    /// Marks the entity to be despawned by [`Entities::despawn_queued`].
+    /// Returns false if the entity is already dead.
+    pub(crate) fn queue_despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let slot = &mut self.slots[entity.index()];
+        if !slot.despawning {
+            slot.despawning = true;
+            self.to_despawn.push(entity);
+        }
+        true
+    }
+
+    /// Frees every queued entity and returns them, so their components can be removed.
+    pub(crate) fn despawn_queued(&mut self) -> Vec<Entity> {
+        let despawned = std::mem::take(&mut self.to_despawn);
+        for entity in &despawned {
+            let slot = &mut self.slots[entity.index()];
+            slot.alive = false;
+            slot.despawning = false;
+            slot.generation += 1;
+            self.free.push(entity.index);
+        }
+        despawned
+    }
+}
+
+#[test]
+fn despawned_entity_handles_go_stale_when_index_is_reused() {
+    let mut entities = Entities::default();
+    let first = entities.spawn();
+    assert!(entities.queue_despawn(first));
+    assert!(entities.queue_despawn(first));
+    assert!(entities.is_alive(first) && entities.is_despawning(first));
+    assert_eq!(entities.despawn_queued(), vec![first]);
+
+    let second = entities.spawn();
+    assert_eq!(first.index(), second.index());
+    assert_ne!(first, second);
+    assert!(!entities.is_alive(first));
+    assert!(entities.is_alive(second) && !entities.is_despawning(second));
+    assert!(!entities.queue_despawn(first));
+}
@@ -1,4 +1,9 @@
-use sdl2::{render::TextureValueError, ttf::FontError, video::WindowBuildError, IntegerOrSdlError};
+use sdl2::{
+    render::{TextureValueError, UpdateTextureError},
+    ttf::FontError,
+    video::WindowBuildError,
+    IntegerOrSdlError,
+};
 
 #[derive(Debug, Clone)]
 pub struct Error(String);
@@ -33,5 +38,6 @@ impl_from_T_for_Error!(
     IntegerOrSdlError,
     &str,
     FontError,
-    TextureValueError
+    TextureValueError,
+    UpdateTextureError
 );
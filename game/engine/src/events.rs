@@ -0,0 +1,47 @@
+use std::any::Any;
+
+/// Type erased access to [`Events`], so every event type can be swapped at the end of an update.
+pub(crate) trait EventBuffer {
+    fn swap(&mut self);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Events of a single type, double buffered.
+/// Events sent during an update are read during the next one,
+/// so every system sees them exactly once, no matter the order systems run in.
+pub(crate) struct Events<E> {
+    readable: Vec<E>,
+    sent: Vec<E>,
+}
+
+impl<E> Events<E> {
+    pub(crate) fn new() -> Self {
+        Self {
+            readable: Vec::new(),
+            sent: Vec::new(),
+        }
+    }
+
+    pub(crate) fn send(&mut self, event: E) {
+        self.sent.push(event);
+    }
+
+    pub(crate) fn readable(&self) -> &[E] {
+        &self.readable
+    }
+}
+
+impl<E: 'static> EventBuffer for Events<E> {
+    fn swap(&mut self) {
+        self.readable = std::mem::take(&mut self.sent);
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
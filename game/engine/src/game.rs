@@ -1,232 +1,107 @@
-use std::collections::{HashMap, HashSet};
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::rc::Rc;
 use std::time::{Duration, Instant};
 
-use sdl2::controller::GameController as SdlGameController;
-use sdl2::keyboard::Keycode;
-use sdl2::mouse::MouseButton;
-use sdl2::ttf::{self, Sdl2TtfContext};
-use sdl2::GameControllerSubsystem;
-use sdl2::{
-    event::Event,
-    image::{self, Sdl2ImageContext},
-    pixels::Color,
-    render::{Canvas, Texture, TextureCreator},
-    video::{Window, WindowContext},
-    Sdl, VideoSubsystem,
-};
-
-use crate::texture::TextTextureKey;
-use crate::Text;
-use crate::{ControllerButton, V2};
+use crate::backend::{Backend, DrawCall};
+use crate::capture::{FrameCaptured, FrameRecorder, Image};
+use crate::events::EventBuffer;
+use crate::headless_backend::HeadlessBackend;
+use crate::input::{Input, InputEvent};
+use crate::render_queue::RenderQueue;
+use crate::sdl_backend::SdlBackend;
+use crate::state::{States, Transition};
+use crate::text_cache::TextCache;
+use crate::{DisplayMode, RenderLayer};
 
-use super::font::Font;
-use super::{context::Context, entity::Entity, id::Id, system::System};
-use super::{Component, Error};
+use super::Error;
+use super::{
+    context::Context, entity::Entities, id::Id, schedule::ScheduledSystem, storage::Storage,
+};
 
 pub struct Game<'game> {
-    #[allow(dead_code)]
-    pub(crate) sdl_context: Sdl,
-    #[allow(dead_code)]
-    pub(crate) video_subsystem: VideoSubsystem,
-    #[allow(dead_code)]
-    pub(crate) controller_subsystem: GameControllerSubsystem,
-    #[allow(dead_code)]
-    pub(crate) image_context: Sdl2ImageContext,
-    pub(crate) ttf_context: Sdl2TtfContext,
-    pub(crate) canvas: Canvas<Window>,
-    pub(crate) texture_creator: TextureCreator<WindowContext>,
-    pub(crate) event_pump: sdl2::EventPump,
-    pub(crate) entity_id_counter: Id,
-    pub(crate) entities: Vec<Option<Entity>>,
+    pub(crate) backend: Box<dyn Backend + 'game>,
+    pub(crate) input: Input,
+    pub(crate) scripted_input: Vec<InputEvent>,
+    pub(crate) id_counter: Id,
+    pub(crate) entities: Entities,
+    pub(crate) components: HashMap<TypeId, Box<dyn Storage>>,
+    pub(crate) resources: HashMap<TypeId, Box<dyn Any>>,
+    pub(crate) events: HashMap<TypeId, Box<dyn EventBuffer>>,
     pub(crate) system_id_counter: Id,
-    pub(crate) systems: Vec<(Id, Rc<dyn System>)>,
+    pub(crate) systems: Vec<ScheduledSystem>,
     pub(crate) systems_to_remove: Vec<Id>,
-    pub(crate) textures: Vec<(Id, Texture<'game>)>,
+    pub(crate) states: States,
     pub(crate) texture_path_to_id_map: HashMap<PathBuf, Id>,
-    pub(crate) text_textures: HashMap<TextTextureKey, Text>,
-    pub(crate) fonts: Vec<(Id, u16, PathBuf, Font<'game>)>,
-    pub(crate) currently_pressed_keys: HashMap<Keycode, bool>,
-    pub(crate) currently_pressed_mouse_buttons: HashMap<MouseButton, bool>,
-    pub(crate) currently_pressed_controller_buttons: HashMap<(Id, ControllerButton), bool>,
-    pub(crate) controllers: Vec<(Id, SdlGameController, ControllerPosition)>,
-    pub(crate) mouse_position: (i32, i32),
+    pub(crate) text_cache: TextCache,
+    pub(crate) display_mode: DisplayMode,
+    pub(crate) render_queue: RenderQueue,
+    /// Layer of the system currently rendering, see [`Schedule::render_layer`](crate::Schedule::render_layer).
+    pub(crate) render_layer: RenderLayer,
+    pub(crate) capture_requested: bool,
+    pub(crate) recorder: Option<FrameRecorder>,
     pub(crate) should_quit: bool,
-}
-
-#[derive(Default)]
-pub struct ControllerPosition {
-    pub left_stick: V2,
-    pub right_stick: V2,
-    pub left_trigger: f64,
-    pub right_trigger: f64,
+    pub(crate) accumulator: f64,
+    /// Frames rendered so far.
+    pub(crate) frame: u64,
 }
 
 impl<'game> Game<'game> {
     pub fn new() -> Result<Self, Error> {
-        let sdl_context = sdl2::init()?;
-        let video_subsystem = sdl_context.video()?;
-        let controller_subsystem = sdl_context.game_controller()?;
-        let image_context = image::init(image::InitFlag::PNG)?;
-        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
-
-        let window = video_subsystem
-            .window("Sky Clash", 1280, 720)
-            .position_centered()
-            // .fullscreen()
-            .build()?;
-
-        let mut canvas = window.into_canvas().build()?;
-        let texture_creator = canvas.texture_creator();
-
-        canvas.set_draw_color(Color::BLACK);
-        canvas.clear();
-        canvas.present();
-        let event_pump = sdl_context.event_pump()?;
-        let mouse_position = (event_pump.mouse_state().x(), event_pump.mouse_state().y());
-        Ok(Self {
-            sdl_context,
-            video_subsystem,
-            controller_subsystem,
-            image_context,
-            ttf_context,
-            canvas,
-            texture_creator,
-            event_pump,
-            entity_id_counter: 0,
+        Ok(Self::with_backend(Box::new(SdlBackend::new()?)))
+    }
+
+    /// Creates a game without SDL video, window or TTF.
+    /// Nothing is drawn and input only comes from [`Game::send_input`],
+    /// which allows systems to be stepped frame by frame in tests.
+    pub fn new_headless() -> Self {
+        Self::with_backend(Box::new(HeadlessBackend::new()))
+    }
+
+    fn with_backend(backend: Box<dyn Backend + 'game>) -> Self {
+        Self {
+            backend,
+            input: Default::default(),
+            scripted_input: Default::default(),
+            id_counter: 0,
             entities: Default::default(),
+            components: Default::default(),
+            resources: Default::default(),
+            events: Default::default(),
             system_id_counter: 0,
             systems: Default::default(),
             systems_to_remove: Default::default(),
-            textures: Default::default(),
+            states: Default::default(),
             texture_path_to_id_map: Default::default(),
-            text_textures: Default::default(),
-            fonts: Default::default(),
-            currently_pressed_keys: Default::default(),
-            currently_pressed_mouse_buttons: Default::default(),
-            currently_pressed_controller_buttons: Default::default(),
-            controllers: Default::default(),
-            mouse_position,
+            text_cache: Default::default(),
+            display_mode: DisplayMode::Windowed,
+            render_queue: Default::default(),
+            render_layer: RenderLayer::World,
+            capture_requested: false,
+            recorder: None,
             should_quit: false,
-        })
+            accumulator: 0.0,
+            frame: 0,
+        }
     }
 
+    /// Time simulated by a single call to [`System::on_update`](crate::System::on_update).
+    pub const FIXED_DELTA: f64 = 1.0 / 120.0;
+
+    /// Longest frame fed to the simulation, so a stall doesn't make the game
+    /// spend the following frames catching up.
+    const MAX_FRAME_DELTA: f64 = 0.25;
+
     pub fn run(&mut self) {
         let mut time_before = Instant::now();
         let time_per_frame = 1_000_000_000 / 144;
-        'running: loop {
-            self.currently_pressed_mouse_buttons
-                .values_mut()
-                .for_each(|value| {
-                    *value = false;
-                });
-            self.currently_pressed_controller_buttons
-                .values_mut()
-                .for_each(|value| {
-                    *value = false;
-                });
-            self.currently_pressed_keys.values_mut().for_each(|value| {
-                *value = false;
-            });
-            if self.should_quit {
-                break 'running;
-            }
-            for event in self.event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. } => break 'running,
-                    Event::KeyDown {
-                        keycode: Some(btn), ..
-                    } => {
-                        if !self.currently_pressed_keys.contains_key(&btn) {
-                            self.currently_pressed_keys.insert(btn, true);
-                        }
-                    }
-                    Event::KeyUp {
-                        keycode: Some(btn), ..
-                    } => {
-                        self.currently_pressed_keys.remove(&btn);
-                    }
-                    Event::MouseButtonDown { mouse_btn: btn, .. } => {
-                        self.currently_pressed_mouse_buttons.insert(btn, true);
-                    }
-                    Event::MouseButtonUp { mouse_btn: btn, .. } => {
-                        self.currently_pressed_mouse_buttons.remove(&btn);
-                    }
-                    Event::ControllerButtonDown {
-                        which, button: btn, ..
-                    } => {
-                        self.currently_pressed_controller_buttons
-                            .insert((which.into(), btn), true);
-                    }
-                    Event::ControllerButtonUp {
-                        which, button: btn, ..
-                    } => {
-                        self.currently_pressed_controller_buttons
-                            .remove(&(which.into(), btn));
-                    }
-                    Event::ControllerDeviceAdded { which, .. } => {
-                        let controller = self.controller_subsystem.open(which).unwrap();
-                        self.controllers
-                            .push((which.into(), controller, Default::default()));
-                    }
-                    Event::ControllerDeviceRemoved { which, .. } => {
-                        if let Some(pos) = self.controllers.iter().position(|v| v.0 == which.into())
-                        {
-                            self.controllers.remove(pos);
-                        };
-                    }
-                    Event::ControllerAxisMotion {
-                        value, which, axis, ..
-                    } => {
-                        let id = which.into();
-                        let value = value as f64 / i16::MAX as f64;
-                        let Some((_, _, pos)) = self.controllers.iter_mut().find(|v| v.0 == id)
-                        else {
-                            println!("tried to get controller positions of unregistered id {id}");
-                            continue;
-                        };
-                        match axis {
-                            sdl2::controller::Axis::LeftX => pos.left_stick.x = value,
-                            sdl2::controller::Axis::LeftY => pos.left_stick.y = value,
-                            sdl2::controller::Axis::RightX => pos.right_stick.x = value,
-                            sdl2::controller::Axis::RightY => pos.right_stick.y = value,
-                            sdl2::controller::Axis::TriggerLeft => pos.left_trigger = value,
-                            sdl2::controller::Axis::TriggerRight => pos.right_trigger = value,
-                        }
-                    }
-                    _ => {}
-                }
-            }
-            self.mouse_position = (
-                self.event_pump.mouse_state().x(),
-                self.event_pump.mouse_state().y(),
-            );
-            self.canvas.set_draw_color(Color::RGB(60, 180, 180));
-            self.canvas.clear();
+        while !self.should_quit {
             let now = Instant::now();
             let delta = (now - time_before).as_nanos() as f64 / 1_000_000_000.0;
             time_before = now;
 
-            for (_id, system) in self.systems.clone() {
-                let Err(err) = system.on_update(&mut self.context(), delta) else {
-                    continue;
-                };
-                println!("error occurred updating system: {err}");
-            }
-            let ids_to_remove: Vec<_> = self.systems_to_remove.drain(..).collect();
-            for removed_id in ids_to_remove {
-                let Some(position) = self.systems.iter().position(|(id, _)| *id == removed_id)
-                else {
-                    println!("tried to remove system with id {removed_id} but unable to");
-                    continue;
-                };
-                let (_, system) = self.systems.remove(position);
-                if let Err(err) = system.on_remove(&mut self.context()) {
-                    println!("error occurred removing system: {err}");
-                };
-            }
-            self.canvas.present();
+            self.frame(delta);
+
             let update_duration = Instant::now() - now;
             let update_duration = update_duration.as_nanos();
             if time_per_frame > update_duration {
@@ -235,6 +110,202 @@ impl<'game> Game<'game> {
         }
     }
 
+    /// Advances the game by exactly one fixed update, then renders.
+    pub fn step(&mut self) {
+        self.frame(Self::FIXED_DELTA);
+    }
+
+    /// Handles input, runs as many fixed updates as `delta` covers and renders once.
+    fn frame(&mut self, delta: f64) {
+        self.backend.poll_events(&mut self.input);
+        for event in std::mem::take(&mut self.scripted_input) {
+            self.input.apply(event);
+        }
+        if self.input.quit_requested {
+            self.should_quit = true;
+            return;
+        }
+
+        self.accumulator += delta.min(Self::MAX_FRAME_DELTA);
+        while self.accumulator >= Self::FIXED_DELTA {
+            self.update(Self::FIXED_DELTA);
+            // presses are only "just pressed" for the first update that sees them
+            self.input.clear_just_pressed();
+            self.accumulator -= Self::FIXED_DELTA;
+        }
+
+        self.render(delta, self.accumulator / Self::FIXED_DELTA);
+    }
+
+    fn update(&mut self, delta: f64) {
+        for ScheduledSystem {
+            system,
+            schedule,
+            state,
+            ..
+        } in self.systems.clone()
+        {
+            if !self.states.is_updating(state) || !schedule.should_run(&mut self.context()) {
+                continue;
+            }
+            let Err(err) = system.on_update(&mut self.context(), delta) else {
+                continue;
+            };
+            println!("error occurred updating system: {err}");
+        }
+        let ids_to_remove: Vec<_> = self.systems_to_remove.drain(..).collect();
+        for removed_id in ids_to_remove {
+            self.remove_system(removed_id);
+        }
+        self.apply_state_transitions();
+        for entity in self.entities.despawn_queued() {
+            for storage in self.components.values_mut() {
+                storage.remove(entity);
+            }
+            self.states.disown(entity);
+        }
+        for events in self.events.values_mut() {
+            events.swap();
+        }
+    }
+
+    fn remove_system(&mut self, removed_id: Id) {
+        let Some(position) = self
+            .systems
+            .iter()
+            .position(|system| system.id == removed_id)
+        else {
+            println!("tried to remove system with id {removed_id} but unable to");
+            return;
+        };
+        let ScheduledSystem { system, .. } = self.systems.remove(position);
+        if let Err(err) = system.on_remove(&mut self.context()) {
+            println!("error occurred removing system: {err}");
+        };
+    }
+
+    /// Applies transitions in the order they were requested,
+    /// including any requested while entering a pushed state.
+    fn apply_state_transitions(&mut self) {
+        while !self.states.transitions.is_empty() {
+            for transition in std::mem::take(&mut self.states.transitions) {
+                match transition {
+                    Transition::Push {
+                        add_root_system,
+                        overlay,
+                    } => {
+                        self.states.push(overlay);
+                        add_root_system(&mut self.context());
+                    }
+                    Transition::Pop => self.pop_state(),
+                }
+            }
+        }
+    }
+
+    fn pop_state(&mut self) {
+        let Some((state, entities)) = self.states.pop() else {
+            println!("tried to pop state but no state is active");
+            return;
+        };
+        let owned_systems: Vec<_> = self
+            .systems
+            .iter()
+            .filter(|system| system.state == Some(state))
+            .map(|system| system.id)
+            .collect();
+        for id in owned_systems.into_iter().rev() {
+            self.remove_system(id);
+        }
+        for entity in entities {
+            self.entities.queue_despawn(entity);
+        }
+    }
+
+    fn render(&mut self, delta: f64, interpolation: f64) {
+        self.backend.clear((60, 180, 180));
+        for ScheduledSystem {
+            system,
+            schedule,
+            state,
+            ..
+        } in self.systems.clone()
+        {
+            if !self.states.is_rendering(state) {
+                continue;
+            }
+            self.render_layer = schedule.default_render_layer();
+            let Err(err) = system.on_render(&mut self.context(), interpolation) else {
+                continue;
+            };
+            println!("error occurred rendering system: {err}");
+        }
+        if let Err(err) = self.render_queue.flush(self.backend.as_mut()) {
+            println!("error occurred drawing: {err}");
+        }
+        self.capture_rendered_frame(delta);
+        self.backend.present();
+        self.evict_text_cache();
+        self.frame += 1;
+    }
+
+    /// Frees text textures and fonts which haven't been used for a while.
+    fn evict_text_cache(&mut self) {
+        let (textures, fonts) = self.text_cache.evict(self.frame);
+        for texture in textures {
+            self.backend.unload_texture(texture);
+        }
+        for font in fonts {
+            self.backend.unload_font(font);
+        }
+    }
+
+    /// Reads back the frame before it's presented, if requested or due for the recorder.
+    fn capture_rendered_frame(&mut self, delta: f64) {
+        let requested = std::mem::take(&mut self.capture_requested);
+        let record = self
+            .recorder
+            .as_mut()
+            .is_some_and(|recorder| recorder.tick(delta));
+        if !requested && !record {
+            return;
+        }
+        let image = match self.backend.read_pixels() {
+            Ok(image) => image,
+            Err(err) => {
+                println!("error occurred capturing frame: {err}");
+                return;
+            }
+        };
+        if requested {
+            self.context().send_event(FrameCaptured(image.clone()));
+        }
+        if let Some(recorder) = self.recorder.as_mut().filter(|_| record) {
+            recorder.push(image);
+        }
+    }
+
+    /// Queues input to be handled at the start of the next frame.
+    pub fn send_input(&mut self, event: InputEvent) {
+        self.scripted_input.push(event);
+    }
+
+    pub fn should_quit(&self) -> bool {
+        self.should_quit
+    }
+
+    /// Draws made during the last frame. Only recorded by headless games.
+    pub fn draw_calls(&self) -> &[DrawCall] {
+        self.backend.draw_calls()
+    }
+
+    /// Reads back the last frame. Meant for headless games, e.g. to compare frames
+    /// to golden images in tests, as SDL doesn't keep a frame once it's presented.
+    /// Use [`Context::capture_frame`] otherwise.
+    pub fn capture(&mut self) -> Result<Image, Error> {
+        self.backend.read_pixels()
+    }
+
     pub fn context<'context>(&'context mut self) -> Context<'context, 'game>
     where
         'game: 'context,
@@ -242,3 +313,152 @@ impl<'game> Game<'game> {
         Context::new(self)
     }
 }
+
+#[test]
+fn long_frame_runs_several_updates_but_presses_are_just_pressed_once() {
+    use crate::{spawn, Component, Keycode, System};
+
+    #[derive(Component)]
+    struct Counter {
+        updates: u32,
+        presses: u32,
+    }
+
+    struct CountingSystem;
+    impl System for CountingSystem {
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            let pressed = ctx.key_just_pressed(Keycode::Space);
+            for (_, counter) in ctx.components_mut::<Counter>() {
+                counter.updates += 1;
+                if pressed {
+                    counter.presses += 1;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(|_| CountingSystem);
+    let id = spawn!(
+        &mut ctx,
+        Counter {
+            updates: 0,
+            presses: 0
+        }
+    );
+
+    game.send_input(InputEvent::KeyDown(Keycode::Space));
+    game.frame(Game::FIXED_DELTA * 4.5);
+    let mut ctx = game.context();
+    let counter = ctx.select::<Counter>(id);
+    assert_eq!((counter.updates, counter.presses), (4, 1));
+
+    // the leftover half update carries over into the next frame
+    game.frame(Game::FIXED_DELTA * 0.6);
+    let mut ctx = game.context();
+    let counter = ctx.select::<Counter>(id);
+    assert_eq!((counter.updates, counter.presses), (5, 1));
+}
+
+#[test]
+fn despawned_entities_leave_queries_at_once_and_storage_after_update() {
+    use crate::{query, spawn, Component, System};
+
+    #[derive(Component)]
+    struct Marker;
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    let entity = spawn!(&mut ctx, Marker);
+    ctx.despawn(entity);
+    assert_eq!(query!(&ctx, Marker).count(), 0);
+    assert_eq!(ctx.components::<Marker>().count(), 0);
+    assert!(ctx.try_select::<Marker>(entity).is_some());
+
+    game.step();
+    let mut ctx = game.context();
+    assert!(ctx.try_select::<Marker>(entity).is_none());
+    let respawned = spawn!(&mut ctx, Marker);
+    assert_ne!(entity, respawned);
+    assert!(ctx.try_select::<Marker>(entity).is_none());
+}
+
+#[test]
+fn resources_are_replaced_by_type_and_can_be_removed() {
+    #[derive(Debug, PartialEq)]
+    struct Score(u32);
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    assert!(ctx.try_resource::<Score>().is_none());
+    ctx.insert_resource(Score(1));
+    ctx.resource::<Score>().0 += 1;
+    assert_eq!(ctx.resource::<Score>(), &Score(2));
+    ctx.insert_resource(Score(10));
+    assert_eq!(ctx.remove_resource::<Score>(), Some(Score(10)));
+    assert!(ctx.try_resource::<Score>().is_none());
+}
+
+#[test]
+fn events_are_read_during_the_update_after_they_are_sent() {
+    use crate::{spawn, Component, System};
+
+    #[derive(Clone)]
+    struct Ping;
+
+    #[derive(Component)]
+    struct Received(Vec<usize>);
+
+    struct Reader;
+    impl System for Reader {
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            let count = ctx.read_events::<Ping>().len();
+            for (_, received) in ctx.components_mut::<Received>() {
+                received.0.push(count);
+            }
+            Ok(())
+        }
+    }
+
+    struct Sender;
+    impl System for Sender {
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            if ctx.key_just_pressed(crate::Keycode::Space) {
+                ctx.send_event(Ping);
+            }
+            Ok(())
+        }
+    }
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    // the reader runs before the sender, and still sees the event
+    ctx.add_system(|_| Reader);
+    ctx.add_system(|_| Sender);
+    let id = spawn!(&mut ctx, Received(Vec::new()));
+
+    game.send_input(InputEvent::KeyDown(crate::Keycode::Space));
+    game.step();
+    game.step();
+    game.step();
+    assert_eq!(game.context().select::<Received>(id).0, vec![0, 1, 0]);
+}
+
+#[test]
+fn reloaded_textures_keep_their_id() {
+    let path = std::env::temp_dir().join(format!("reload_{}.png", std::process::id()));
+    Image::new(16, 16).save_png(&path).unwrap();
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    let texture = ctx.load_texture(&path).unwrap();
+    assert_eq!(ctx.texture_size(texture).unwrap(), (16, 16));
+
+    Image::new(32, 8).save_png(&path).unwrap();
+    assert!(ctx.reload_texture(&path).unwrap());
+    assert_eq!(ctx.load_texture(&path).unwrap(), texture);
+    assert_eq!(ctx.texture_size(texture).unwrap(), (32, 8));
+    assert!(!ctx.reload_texture("never_loaded.png").unwrap());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, path::Path};
+
+use crate::{
+    backend::{Backend, DrawCall},
+    capture::Image,
+    input::Input,
+    text::TextRun,
+    DisplayMode, DrawTextureOpts, Error, Id, Texture, LOGICAL_HEIGHT, LOGICAL_WIDTH, V2,
+};
+
+/// Backend which never touches SDL.
+/// Draws are recorded, so they can be inspected after a frame, and rasterized in software
+/// at the logical size when the frame is read back. Text has no pixels and rotation is ignored.
+pub(crate) struct HeadlessBackend {
+    texture_sizes: HashMap<Id, (u32, u32)>,
+    texture_pixels: HashMap<Id, Image>,
+    font_sizes: HashMap<Id, u16>,
+    draw_calls: Vec<DrawCall>,
+    clear_rgb: (u8, u8, u8),
+    /// Options of each texture draw of the frame, as [`DrawCall`]s only keep the size.
+    texture_opts: Vec<DrawTextureOpts>,
+}
+
+impl HeadlessBackend {
+    pub(crate) fn new() -> Self {
+        Self {
+            texture_sizes: HashMap::new(),
+            texture_pixels: HashMap::new(),
+            font_sizes: HashMap::new(),
+            draw_calls: Vec::new(),
+            clear_rgb: (0, 0, 0),
+            texture_opts: Vec::new(),
+        }
+    }
+
+    /// Nearest neighbour, sampled at the center of each destination pixel.
+    fn rasterize_texture(
+        &self,
+        canvas: &mut Image,
+        texture: Id,
+        pos: V2,
+        size: V2,
+        opts: &DrawTextureOpts,
+    ) {
+        let Some(image) = self.texture_pixels.get(&texture) else {
+            return;
+        };
+        let (width, height) = (f64::from(image.width), f64::from(image.height));
+        let (source_pos, source_size) = opts
+            .source
+            .unwrap_or((V2::new(0.0, 0.0), V2::new(width, height)));
+        let (mod_r, mod_g, mod_b) = opts.color_mod.unwrap_or((255, 255, 255));
+        let opacity = opts.opacity.unwrap_or(1.0);
+        let modulate = |channel: u8, by: u8| (u16::from(channel) * u16::from(by) / 255) as u8;
+        let (left, top) = (pos.x as i64, pos.y as i64);
+        for dy in visible(top, size.y as i64, canvas.height) {
+            let sy = source_pos.y + (dy as f64 + 0.5) * source_size.y / size.y;
+            for dx in visible(left, size.x as i64, canvas.width) {
+                let sx = source_pos.x + (dx as f64 + 0.5) * source_size.x / size.x;
+                if sx < 0.0 || sy < 0.0 || sx >= width || sy >= height {
+                    continue;
+                }
+                let [r, g, b, a] = image.pixel(sx as u32, sy as u32);
+                let a = (f64::from(a) * opacity).round() as u8;
+                let rgba = [
+                    modulate(r, mod_r),
+                    modulate(g, mod_g),
+                    modulate(b, mod_b),
+                    a,
+                ];
+                blend(canvas, left + dx, top + dy, rgba);
+            }
+        }
+    }
+}
+
+/// Offsets into a span of `len` pixels starting at `start` which are on a canvas `bound` long.
+fn visible(start: i64, len: i64, bound: u32) -> std::ops::Range<i64> {
+    (-start).max(0)..len.min(i64::from(bound) - start)
+}
+
+/// Blends `rgba` over an opaque canvas pixel.
+fn blend(canvas: &mut Image, x: i64, y: i64, [r, g, b, a]: [u8; 4]) {
+    let (x, y) = (x as u32, y as u32);
+    let [dst_r, dst_g, dst_b, _] = canvas.pixel(x, y);
+    let mix = |src: u8, dst: u8| {
+        ((u32::from(src) * u32::from(a) + u32::from(dst) * (255 - u32::from(a)) + 127) / 255) as u8
+    };
+    canvas.set_pixel(x, y, [mix(r, dst_r), mix(g, dst_g), mix(b, dst_b), 255]);
+}
+
+fn fill(
+    canvas: &mut Image,
+    (r, g, b, a): (u8, u8, u8, u8),
+    (x, y): (i32, i32),
+    (w, h): (u32, u32),
+) {
+    let (left, top) = (i64::from(x), i64::from(y));
+    for y in visible(top, i64::from(h), canvas.height) {
+        for x in visible(left, i64::from(w), canvas.width) {
+            blend(canvas, left + x, top + y, [r, g, b, a]);
+        }
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn poll_events(&mut self, _input: &mut Input) {}
+
+    fn clear(&mut self, rgb: (u8, u8, u8)) {
+        self.draw_calls.clear();
+        self.texture_opts.clear();
+        self.clear_rgb = rgb;
+    }
+
+    fn present(&mut self) {}
+
+    fn set_display_mode(&mut self, _mode: DisplayMode) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn load_texture(&mut self, id: Id, path: &Path) -> Result<(), Error> {
+        let image = Image::load_png(path)?;
+        self.texture_sizes.insert(id, (image.width, image.height));
+        self.texture_pixels.insert(id, image);
+        Ok(())
+    }
+
+    fn load_font(&mut self, id: Id, path: &Path, size: u16) -> Result<(), Error> {
+        if !path.exists() {
+            return Err(format!("{}: font not found", path.display()).into());
+        }
+        self.font_sizes.insert(id, size);
+        Ok(())
+    }
+
+    fn unload_texture(&mut self, id: Id) {
+        self.texture_sizes.remove(&id);
+        self.texture_pixels.remove(&id);
+    }
+
+    fn unload_font(&mut self, id: Id) {
+        self.font_sizes.remove(&id);
+    }
+
+    fn render_text(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        text: &str,
+        _rgb: (u8, u8, u8),
+    ) -> Result<(u32, u32), Error> {
+        let size = self.text_size(font_id, text)?;
+        self.texture_sizes.insert(id, size);
+        self.texture_pixels.remove(&id);
+        Ok(size)
+    }
+
+    fn render_text_runs(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        _runs: &[TextRun],
+        size: (u32, u32),
+    ) -> Result<(), Error> {
+        self.font_sizes
+            .get(&font_id)
+            .ok_or("tried to render non-loaded text")?;
+        self.texture_sizes.insert(id, size);
+        self.texture_pixels.remove(&id);
+        Ok(())
+    }
+
+    /// Approximation, every character is assumed to be half as wide as the font is tall.
+    fn text_size(&mut self, font_id: Id, text: &str) -> Result<(u32, u32), Error> {
+        let size = *self
+            .font_sizes
+            .get(&font_id)
+            .ok_or("tried to render non-loaded text")?;
+        let width = text.chars().count() as u32 * u32::from(size) / 2;
+        Ok((width, u32::from(size)))
+    }
+
+    fn texture_size(&mut self, texture: Id) -> Result<(u32, u32), Error> {
+        Ok(*self
+            .texture_sizes
+            .get(&texture)
+            .ok_or("invalid sprite id")?)
+    }
+
+    fn draw_texture(&mut self, texture: Id, pos: V2, opts: &DrawTextureOpts) -> Result<(), Error> {
+        let (width, height) = self.texture_size(texture)?;
+        let size = opts
+            .size
+            .or(opts.source.map(|(_, size)| size))
+            .unwrap_or(V2::new(f64::from(width), f64::from(height)));
+        self.draw_calls.push(DrawCall::Texture {
+            texture: Texture(texture),
+            pos,
+            size,
+        });
+        self.texture_opts.push(opts.clone());
+        Ok(())
+    }
+
+    fn fill_rect(
+        &mut self,
+        rgba: (u8, u8, u8, u8),
+        pos: (i32, i32),
+        size: (u32, u32),
+    ) -> Result<(), Error> {
+        self.draw_calls.push(DrawCall::Rect { rgba, pos, size });
+        Ok(())
+    }
+
+    fn read_pixels(&mut self) -> Result<Image, Error> {
+        let mut canvas = Image::new(LOGICAL_WIDTH, LOGICAL_HEIGHT);
+        let (r, g, b) = self.clear_rgb;
+        fill(
+            &mut canvas,
+            (r, g, b, 255),
+            (0, 0),
+            (LOGICAL_WIDTH, LOGICAL_HEIGHT),
+        );
+        let mut texture_opts = self.texture_opts.iter();
+        for call in &self.draw_calls {
+            match call {
+                DrawCall::Texture { texture, pos, size } => {
+                    let opts = texture_opts
+                        .next()
+                        .expect("recorded with every texture draw");
+                    self.rasterize_texture(&mut canvas, texture.0, *pos, *size, opts);
+                }
+                DrawCall::Rect { rgba, pos, size } => fill(&mut canvas, *rgba, *pos, *size),
+            }
+        }
+        Ok(canvas)
+    }
+
+    fn draw_calls(&self) -> &[DrawCall] {
+        &self.draw_calls
+    }
+}
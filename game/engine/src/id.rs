@@ -2,19 +2,19 @@ use std::ops::AddAssign;
 
 pub type Id = u64;
 
-pub struct IdAccumulator(Vec<Id>);
+pub struct IdAccumulator<T = Id>(Vec<T>);
 
-impl IdAccumulator {
+impl<T> IdAccumulator<T> {
     pub fn new() -> Self {
         Self(Vec::new())
     }
-    pub fn finish(self) -> Vec<Id> {
+    pub fn finish(self) -> Vec<T> {
         self.0
     }
 }
 
-impl AddAssign<Id> for IdAccumulator {
-    fn add_assign(&mut self, rhs: Id) {
+impl<T> AddAssign<T> for IdAccumulator<T> {
+    fn add_assign(&mut self, rhs: T) {
         self.0.push(rhs);
     }
 }
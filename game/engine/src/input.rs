@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use crate::{ControllerButton, Id, Keycode, MouseButton, V2};
+
+#[derive(Default)]
+pub struct ControllerPosition {
+    pub left_stick: V2,
+    pub right_stick: V2,
+    pub left_trigger: f64,
+    pub right_trigger: f64,
+}
+
+/// Input which can be fed to a [`crate::Game`] without going through SDL,
+/// used to script input for headless games.
+#[derive(Clone, Debug)]
+pub enum InputEvent {
+    KeyDown(Keycode),
+    KeyUp(Keycode),
+    MouseButtonDown(MouseButton),
+    MouseButtonUp(MouseButton),
+    MouseMotion(i32, i32),
+    Quit,
+}
+
+#[derive(Default)]
+pub(crate) struct Input {
+    pub(crate) currently_pressed_keys: HashMap<Keycode, bool>,
+    pub(crate) currently_pressed_mouse_buttons: HashMap<MouseButton, bool>,
+    pub(crate) currently_pressed_controller_buttons: HashMap<(Id, ControllerButton), bool>,
+    pub(crate) controllers: Vec<(Id, ControllerPosition)>,
+    pub(crate) mouse_position: (i32, i32),
+    pub(crate) quit_requested: bool,
+}
+
+impl Input {
+    pub(crate) fn clear_just_pressed(&mut self) {
+        self.currently_pressed_mouse_buttons
+            .values_mut()
+            .for_each(|value| {
+                *value = false;
+            });
+        self.currently_pressed_controller_buttons
+            .values_mut()
+            .for_each(|value| {
+                *value = false;
+            });
+        self.currently_pressed_keys.values_mut().for_each(|value| {
+            *value = false;
+        });
+    }
+
+    pub(crate) fn apply(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::KeyDown(btn) => {
+                self.currently_pressed_keys.entry(btn).or_insert(true);
+            }
+            InputEvent::KeyUp(btn) => {
+                self.currently_pressed_keys.remove(&btn);
+            }
+            InputEvent::MouseButtonDown(btn) => {
+                self.currently_pressed_mouse_buttons.insert(btn, true);
+            }
+            InputEvent::MouseButtonUp(btn) => {
+                self.currently_pressed_mouse_buttons.remove(&btn);
+            }
+            InputEvent::MouseMotion(x, y) => {
+                self.mouse_position = (x, y);
+            }
+            InputEvent::Quit => {
+                self.quit_requested = true;
+            }
+        }
+    }
+}
@@ -1,41 +1,64 @@
 #![allow(unused_imports)]
 
+mod backend;
 mod component;
 mod context;
+mod display;
 mod entity;
 mod error;
+mod events;
 mod font;
 mod game;
+mod headless_backend;
 mod id;
+mod input;
 mod query_runner;
+mod render_queue;
+mod schedule;
+mod sdl_backend;
+mod state;
+mod storage;
 mod system;
 mod text;
+mod text_cache;
 mod texture;
 
+pub mod animation;
+pub mod broadphase;
+pub mod camera;
+pub mod capture;
 pub mod collision;
+pub mod particles;
 pub mod physics;
 pub mod rigid_body;
 pub mod shared_ptr;
 pub mod ui;
 
 pub use self::{
+    backend::DrawCall,
     collision::CollisionSystem,
     collision::SolidCollider,
     component::Component,
     context::ComponentQuery,
     context::Context,
     context::DrawTextureOpts,
+    display::{DisplayMode, LOGICAL_HEIGHT, LOGICAL_SIZE, LOGICAL_WIDTH},
+    entity::Entity,
     error::Error,
     game::Game,
     id::{Id, IdAccumulator},
+    input::InputEvent,
     physics::clamp,
     physics::max,
     physics::min,
     physics::V2,
     query_runner::QueryRunner,
+    render_queue::{DrawCommand, RenderLayer},
+    schedule::{Schedule, Stage},
     shared_ptr::SharedPtr,
     system::System,
-    text::Text,
+    text::{Text, TextAlign, TextOutline, TextShadow, TextStyle},
+    text_cache::{TextCacheLimits, TextCacheStats},
     texture::Texture,
 };
 pub use component_macro::Component;
@@ -0,0 +1,317 @@
+use rand::Rng;
+
+use crate::{
+    query_entities, rigid_body::RigidBody, Component, Context, Error, RenderLayer, Schedule, Stage,
+    System, Texture, V2,
+};
+
+/// A single particle of a [`ParticleEmitter`], positioned in world space.
+#[derive(Clone, Debug)]
+pub struct Particle {
+    pub pos: V2,
+    pub vel: V2,
+    /// Seconds since the particle was spawned.
+    pub age: f64,
+}
+
+/// How a particle is drawn at its current age, see [`ParticleEmitter::sprites`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticleSprite {
+    /// Top left corner, in world space.
+    pub pos: V2,
+    pub size: V2,
+    pub color: (u8, u8, u8),
+    pub alpha: f64,
+}
+
+/// Spawns particles from the center of the entity's [`RigidBody`], advanced by [`ParticleSystem`].
+/// Particles are drawn as squares of their color, or tinted by it if the emitter has a texture.
+#[derive(Component, Clone, Debug)]
+pub struct ParticleEmitter {
+    /// Particles spawned per second while emitting.
+    pub rate: f64,
+    /// Particles spawned at once when the emitter first updates.
+    pub burst: u32,
+    /// Seconds the emitter keeps spawning particles, `None` for as long as it exists.
+    pub duration: Option<f64>,
+    /// Seconds each particle lives.
+    pub lifetime: f64,
+    /// Angle particles are launched at, in radians, where 0 points right and PI/2 points down.
+    pub direction: f64,
+    /// Particles are launched up to half of this angle to either side of `direction`.
+    pub spread: f64,
+    /// Minimum and maximum launch speed.
+    pub speed: (f64, f64),
+    pub gravity: f64,
+    pub size: V2,
+    /// Color at the start and end of a particle's life, blended in between.
+    pub color: ((u8, u8, u8), (u8, u8, u8)),
+    /// Opacity at the start and end of a particle's life, blended in between.
+    pub alpha: (f64, f64),
+    pub texture: Option<Texture>,
+    pub layer: RenderLayer,
+    /// Order within the layer, higher is drawn on top.
+    pub depth: i32,
+    /// Despawns the entity once it has stopped emitting and every particle has died.
+    pub despawn_when_done: bool,
+    particles: Vec<Particle>,
+    elapsed: f64,
+    /// Fractions of particles owed by `rate`, spawned once they add up to whole particles.
+    pending: f64,
+    burst_spawned: bool,
+}
+
+impl ParticleEmitter {
+    pub fn new() -> Self {
+        Self {
+            rate: 0.0,
+            burst: 0,
+            duration: None,
+            lifetime: 1.0,
+            direction: 0.0,
+            spread: std::f64::consts::TAU,
+            speed: (0.0, 0.0),
+            gravity: 0.0,
+            size: V2::new(4.0, 4.0),
+            color: ((255, 255, 255), (255, 255, 255)),
+            alpha: (1.0, 1.0),
+            texture: None,
+            layer: RenderLayer::Effects,
+            depth: 0,
+            despawn_when_done: false,
+            particles: Vec::new(),
+            elapsed: 0.0,
+            pending: 0.0,
+            burst_spawned: false,
+        }
+    }
+
+    pub fn rate(self, rate: f64) -> Self {
+        Self { rate, ..self }
+    }
+
+    pub fn burst(self, burst: u32) -> Self {
+        Self { burst, ..self }
+    }
+
+    pub fn duration(self, duration: f64) -> Self {
+        Self {
+            duration: Some(duration),
+            ..self
+        }
+    }
+
+    pub fn lifetime(self, lifetime: f64) -> Self {
+        Self { lifetime, ..self }
+    }
+
+    pub fn direction(self, direction: f64, spread: f64) -> Self {
+        Self {
+            direction,
+            spread,
+            ..self
+        }
+    }
+
+    pub fn speed(self, min: f64, max: f64) -> Self {
+        Self {
+            speed: (min, max),
+            ..self
+        }
+    }
+
+    pub fn gravity(self, gravity: f64) -> Self {
+        Self { gravity, ..self }
+    }
+
+    pub fn size(self, size: V2) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn color(self, start: (u8, u8, u8), end: (u8, u8, u8)) -> Self {
+        Self {
+            color: (start, end),
+            ..self
+        }
+    }
+
+    pub fn alpha(self, start: f64, end: f64) -> Self {
+        Self {
+            alpha: (start, end),
+            ..self
+        }
+    }
+
+    pub fn texture(self, texture: Texture) -> Self {
+        Self {
+            texture: Some(texture),
+            ..self
+        }
+    }
+
+    pub fn layer(self, layer: RenderLayer) -> Self {
+        Self { layer, ..self }
+    }
+
+    pub fn depth(self, depth: i32) -> Self {
+        Self { depth, ..self }
+    }
+
+    pub fn despawn_when_done(self) -> Self {
+        Self {
+            despawn_when_done: true,
+            ..self
+        }
+    }
+
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    pub fn emitting(&self) -> bool {
+        self.duration.is_none_or(|duration| self.elapsed < duration)
+    }
+
+    /// Stops spawning particles, the ones already spawned live out their lifetime.
+    pub fn stop(&mut self) {
+        self.duration = Some(self.elapsed);
+    }
+
+    /// Whether the emitter has stopped emitting and every particle has died.
+    pub fn done(&self) -> bool {
+        self.burst_spawned && !self.emitting() && self.particles.is_empty()
+    }
+
+    /// Moves and ages the particles, and spawns new ones at `origin`.
+    pub fn update(&mut self, delta: f64, origin: V2, rng: &mut impl Rng) {
+        let lifetime = self.lifetime;
+        self.particles.retain_mut(|particle| {
+            particle.age += delta;
+            particle.vel.y += self.gravity * delta;
+            particle.pos += particle.vel.extend(delta);
+            particle.age < lifetime
+        });
+
+        let mut count = 0;
+        if !self.burst_spawned {
+            self.burst_spawned = true;
+            count += self.burst;
+        }
+        if self.emitting() {
+            self.pending += self.rate * delta;
+            count += self.pending as u32;
+            self.pending %= 1.0;
+        }
+        for _ in 0..count {
+            let particle = self.spawn_particle(origin, rng);
+            self.particles.push(particle);
+        }
+        self.elapsed += delta;
+    }
+
+    fn spawn_particle(&self, origin: V2, rng: &mut impl Rng) -> Particle {
+        let angle = self.direction + (rng.gen::<f64>() - 0.5) * self.spread;
+        let (min_speed, max_speed) = self.speed;
+        let speed = min_speed + rng.gen::<f64>() * (max_speed - min_speed);
+        Particle {
+            pos: origin,
+            vel: V2::new(angle.cos(), angle.sin()).extend(speed),
+            age: 0.0,
+        }
+    }
+
+    /// Every living particle, centered on its position, with its color and opacity for its age.
+    pub fn sprites(&self) -> Vec<ParticleSprite> {
+        let lerp = |start: f64, end: f64, t: f64| start + (end - start) * t;
+        let ((start_r, start_g, start_b), (end_r, end_g, end_b)) = self.color;
+        self.particles
+            .iter()
+            .map(|particle| {
+                let t = (particle.age / self.lifetime).clamp(0.0, 1.0);
+                let channel = |start: u8, end: u8| lerp(start.into(), end.into(), t).round() as u8;
+                ParticleSprite {
+                    pos: particle.pos - self.size.extend(0.5),
+                    size: self.size,
+                    color: (
+                        channel(start_r, end_r),
+                        channel(start_g, end_g),
+                        channel(start_b, end_b),
+                    ),
+                    alpha: lerp(self.alpha.0, self.alpha.1, t),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Advances every [`ParticleEmitter`] attached to a [`RigidBody`].
+pub struct ParticleSystem(pub u64);
+impl System for ParticleSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay)
+    }
+
+    fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
+        let mut rng = rand::thread_rng();
+        for id in query_entities!(ctx, ParticleEmitter, RigidBody) {
+            let body = ctx.select::<RigidBody>(id);
+            let origin = body.pos + body.size.extend(0.5);
+            let emitter = ctx.select::<ParticleEmitter>(id);
+            emitter.update(delta, origin, &mut rng);
+            if emitter.despawn_when_done && emitter.done() {
+                ctx.despawn(id);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn particle_emitters_burst_emit_at_their_rate_and_fade() {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let origin = V2::new(100.0, 100.0);
+    let mut emitter = ParticleEmitter::new()
+        .burst(3)
+        .rate(8.0)
+        .duration(0.25)
+        .lifetime(0.5)
+        .direction(0.0, 0.0)
+        .speed(100.0, 100.0)
+        .gravity(200.0)
+        .color((255, 0, 0), (0, 0, 255))
+        .alpha(1.0, 0.0);
+
+    emitter.update(0.125, origin, &mut rng);
+    assert_eq!(
+        emitter.particles().len(),
+        4,
+        "the burst and one from the rate"
+    );
+    emitter.update(0.125, origin, &mut rng);
+    assert_eq!(emitter.particles().len(), 5);
+    assert!(!emitter.emitting());
+    emitter.update(0.125, origin, &mut rng);
+    assert_eq!(emitter.particles().len(), 5);
+
+    let first = &emitter.particles()[0];
+    assert_eq!(first.pos.x, 125.0, "launched right");
+    assert!(first.pos.y > origin.y, "pulled down by gravity");
+    let sprite = &emitter.sprites()[0];
+    assert_eq!(sprite.color, (128, 0, 128));
+    assert_eq!(sprite.alpha, 0.5);
+    assert_eq!(sprite.pos, first.pos - V2::new(2.0, 2.0));
+
+    emitter.update(0.25, origin, &mut rng);
+    assert_eq!(emitter.particles().len(), 1, "the burst has died");
+    assert!(!emitter.done());
+    emitter.update(0.5, origin, &mut rng);
+    assert!(emitter.done());
+}
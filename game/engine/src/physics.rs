@@ -194,37 +194,6 @@ impl Moving<V2> {
         }
     }
 
-    /// Will a moving point (self) pass a static point (p), where p is on the same movement path?
-    pub fn crosses_point_debug(&self, p: V2) -> bool {
-        if eq_tol(self.delta_pos.x, 0.0, F64_TOL) && eq_tol(self.delta_pos.y, 0.0, F64_TOL) {
-            // no movement; it will never pass
-            return false;
-        }
-        // draw a line from the target position backwards to the point
-        // if the point is on the line, then it intends to pass the point
-
-        // intended_movement = origin->target = (target - origin)
-        // point_to_pass = intended_movement * distance_factor + origin
-        // distance_factor = (point_to_pass - origin) / intended_movement
-        let origin = self.inner;
-        let target = origin + self.delta_pos;
-        let point_to_pass = p;
-        let intended_movement = target - origin;
-        let distance_factor = (point_to_pass - origin).len() / intended_movement.len();
-
-        if distance_factor < -0.01 {
-            // has not yet reached point
-            dbg!(origin, intended_movement, point_to_pass, distance_factor);
-            return false;
-        }
-        if distance_factor > 1.0 {
-            // has already passed point
-            println!("passed T~T");
-            return false;
-        }
-        true
-    }
-
     /// Will a moving point (self) pass a static point (p), where p is on the same movement path?
     pub fn crosses_point(&self, p: V2) -> bool {
         if eq_tol(self.delta_pos.x, 0.0, F64_TOL) && eq_tol(self.delta_pos.y, 0.0, F64_TOL) {
@@ -263,14 +232,6 @@ impl Moving<V2> {
     /// The close intersection is to point, the closer factor is to zero,
     /// factor is zero, when intersection is at point + delta_pos.
     pub fn line_segment_intersect(&self, line: Line) -> Option<Intersection> {
-        // TODO: uhhhh keep debugging idk
-        let debug = false && line.p0.x == 306.0 && line.p0.y == 162.0 && self.inner.x < 600.0;
-        if debug {
-            println!("started new");
-            if self.inner.x < 306.0 {
-                // panic!();
-            }
-        }
         if eq_tol(self.delta_pos.len(), 0.0, F64_TOL) {
             // no movement, no collision
             return None;
@@ -279,18 +240,7 @@ impl Moving<V2> {
         if !line.point_within_segment(intersection) {
             return None;
         }
-
-        let cwosses = if debug {
-            self.crosses_point_debug(intersection)
-        } else {
-            self.crosses_point(intersection)
-        };
-
-        if !cwosses {
-            if debug {
-                println!("doth not cwoss point");
-                dbg!(intersection, self.inner.x, self.delta_pos.x);
-            }
+        if !self.crosses_point(intersection) {
             return None;
         }
         let distance_factor = self.distance_factor_to_point(intersection);
@@ -335,12 +285,34 @@ impl Rect {
         self.pos.add_y(self.size.y)
     }
 
+    pub fn center(&self) -> V2 {
+        self.pos + self.size.div_comps(2.0)
+    }
+
     pub fn radius(&self) -> f64 {
         self.size.div_comps(2.0).len()
     }
 
     pub fn radii_distance_to_rect(&self, other: Rect) -> f64 {
-        (other.pos - self.pos).len() - (self.radius() + other.radius())
+        (other.center() - self.center()).len() - (self.radius() + other.radius())
+    }
+
+    /// Smallest rect covering both.
+    pub fn union(&self, other: Rect) -> Rect {
+        let pos = V2::new(min(self.pos.x, other.pos.x), min(self.pos.y, other.pos.y));
+        let end = V2::new(
+            max(self.pos.x + self.size.x, other.pos.x + other.size.x),
+            max(self.pos.y + self.size.y, other.pos.y + other.size.y),
+        );
+        Rect::new(pos, end - pos)
+    }
+
+    /// Whether the rects share any area, just touching doesn't count.
+    pub fn overlaps(&self, other: Rect) -> bool {
+        self.pos.x < other.pos.x + other.size.x
+            && self.pos.x + self.size.x > other.pos.x
+            && self.pos.y < other.pos.y + other.size.y
+            && self.pos.y + self.size.y > other.pos.y
     }
 
     pub fn side_corners(&self, dir: QuadDirection) -> (V2, V2) {
@@ -355,12 +327,93 @@ impl Rect {
     }
 }
 
+/// Where and when a moving rect first touches a static one, see [`Moving::sweep`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Contact {
+    /// Fraction of the movement made before touching, between 0 and 1.
+    pub time: f64,
+    /// Side of the moving rect which touches.
+    pub side: QuadDirection,
+    /// Point on the side of the static rect which is touched.
+    pub pos: V2,
+}
+
+impl Contact {
+    /// Unit vector out of the touched side of the static rect.
+    pub fn normal(&self) -> V2 {
+        use QuadDirection::*;
+        match self.side {
+            Top => V2::new(0.0, 1.0),
+            Right => V2::new(-1.0, 0.0),
+            Bottom => V2::new(0.0, -1.0),
+            Left => V2::new(1.0, 0.0),
+        }
+    }
+}
+
+/// Times, as fractions of `delta`, at which a moving span starts and stops overlapping
+/// a static span. A span which doesn't move overlaps all along or never does.
+fn sweep_axis(
+    pos: f64,
+    size: f64,
+    delta: f64,
+    other_pos: f64,
+    other_size: f64,
+) -> Option<(f64, f64)> {
+    let (entry, exit) = if delta > 0.0 {
+        (other_pos - (pos + size), other_pos + other_size - pos)
+    } else {
+        (pos - (other_pos + other_size), pos + size - other_pos)
+    };
+    if delta == 0.0 {
+        return (entry < 0.0 && exit > 0.0).then_some((f64::NEG_INFINITY, f64::INFINITY));
+    }
+    Some((entry / delta.abs(), exit / delta.abs()))
+}
+
 impl Moving<Rect> {
     pub fn rect_within_reach(&self, other: Rect) -> bool {
         let radii = self.radius() + self.delta_pos.len() + other.radius();
         let length_between = self.radii_distance_to_rect(other);
         radii >= length_between
     }
+
+    /// Swept AABB test, finding the first time the rect touches `other` while moving,
+    /// however far it moves. A rect touching `other` and moving into it, e.g. resting on
+    /// top of it, touches at once. Rects already overlapping more than [`F64_TOL`],
+    /// moving away or only grazing `other` don't touch. Corners hitting exactly at once
+    /// count as hitting the top or bottom, so bodies land on the edges of platforms.
+    pub fn sweep(&self, other: Rect) -> Option<Contact> {
+        use QuadDirection::*;
+        let delta = self.delta_pos;
+        if delta.x == 0.0 && delta.y == 0.0 {
+            return None;
+        }
+        let (x_entry, x_exit) =
+            sweep_axis(self.pos.x, self.size.x, delta.x, other.pos.x, other.size.x)?;
+        let (y_entry, y_exit) =
+            sweep_axis(self.pos.y, self.size.y, delta.y, other.pos.y, other.size.y)?;
+
+        let vertical = y_entry >= x_entry;
+        let (entry, entry_distance) = if vertical {
+            (y_entry, y_entry * delta.y.abs())
+        } else {
+            (x_entry, x_entry * delta.x.abs())
+        };
+        if entry >= x_exit.min(y_exit) || entry > 1.0 || entry_distance < -F64_TOL {
+            return None;
+        }
+
+        let time = max(entry, 0.0);
+        let at = self.pos + delta.extend(time);
+        let (side, pos) = match (vertical, delta.x > 0.0, delta.y > 0.0) {
+            (true, _, true) => (Bottom, V2::new(at.x, other.pos.y)),
+            (true, _, false) => (Top, V2::new(at.x, other.pos.y + other.size.y)),
+            (false, true, _) => (Right, V2::new(other.pos.x, at.y)),
+            (false, false, _) => (Left, V2::new(other.pos.x + other.size.x, at.y)),
+        };
+        Some(Contact { time, side, pos })
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -487,10 +540,10 @@ impl OctoDirection {
         match (direction, self) {
             (Top, Top | TopLeft | TopRight)
             | (Right, Right | TopRight | BottomRight)
-            | (Bottom, Bottom | BottomLeft | BottomRight) => true,
-            (Left, Left | TopLeft | TopRight) => todo!(),
+            | (Bottom, Bottom | BottomLeft | BottomRight)
+            | (Left, Left | TopLeft | BottomLeft) => true,
             (Top | Right | Bottom | Left, _) => false,
-            _ => unreachable!(),
+            (direction, _) => direction == *self,
         }
     }
 }
@@ -521,3 +574,92 @@ impl From<QuadDirection> for OctoDirection {
         }
     }
 }
+
+#[test]
+fn swept_rects_touch_when_they_first_overlap_and_never_tunnel() {
+    // xorshift, so failures can be reproduced. Whole numbers and axis aligned movement
+    // make touching edges and corners common
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move |range: i64| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % (2 * range as u64 + 1)) as f64 - range as f64
+    };
+    let overlap = |a: Rect, b: Rect| {
+        let x = min(a.pos.x + a.size.x, b.pos.x + b.size.x) - max(a.pos.x, b.pos.x);
+        let y = min(a.pos.y + a.size.y, b.pos.y + b.size.y) - max(a.pos.y, b.pos.y);
+        min(x, y)
+    };
+
+    let mut contacts = 0;
+    for _ in 0..20_000 {
+        let rect = Rect::from_f64(random(50), random(50), random(20) + 21.0, random(20) + 21.0);
+        let other = Rect::from_f64(random(50), random(50), random(20) + 21.0, random(3) + 4.0);
+        let mut delta = V2::new(random(400), random(400));
+        match random(2) as i64 {
+            -2 => delta.x = 0.0,
+            -1 => delta.y = 0.0,
+            _ => {}
+        }
+        if overlap(rect, other) > 0.0 {
+            continue;
+        }
+        let at = |time: f64| Rect::new(rect.pos + delta.extend(time), rect.size);
+        let Some(contact) = rect.moving(delta).sweep(other) else {
+            for i in 0..=1000 {
+                let time = f64::from(i) / 1000.0;
+                assert!(
+                    overlap(at(time), other) <= 1e-9,
+                    "{rect:?} moving {delta:?} passed through {other:?} at {time}"
+                );
+            }
+            continue;
+        };
+        contacts += 1;
+        assert!((0.0..=1.0).contains(&contact.time), "{contact:?}");
+        assert!(
+            eq_tol(overlap(at(contact.time), other), 0.0, 1e-9),
+            "{contact:?}"
+        );
+        assert!(overlap(at(contact.time + 1e-9), other) > 0.0, "{contact:?}");
+        for i in 0..100 {
+            let time = contact.time * f64::from(i) / 100.0;
+            assert!(overlap(at(time), other) <= 1e-9, "{contact:?}");
+        }
+        let normal = contact.normal();
+        assert!(normal.x * delta.x + normal.y * delta.y < 0.0, "{contact:?}");
+    }
+    assert!(contacts > 1000, "only {contacts} contacts were tested");
+}
+
+#[test]
+fn resting_and_corner_contacts_are_found() {
+    use QuadDirection::*;
+    let platform = Rect::from_f64(0.0, 100.0, 200.0, 20.0);
+
+    let resting = Rect::from_f64(50.0, 68.0, 32.0, 32.0).moving(V2::new(5.0, 0.5));
+    let contact = resting.sweep(platform).unwrap();
+    assert_eq!((contact.time, contact.side), (0.0, Bottom));
+    assert_eq!(contact.normal(), V2::new(0.0, -1.0));
+
+    let leaving = Rect::from_f64(50.0, 68.0, 32.0, 32.0).moving(V2::new(5.0, -0.5));
+    assert_eq!(leaving.sweep(platform), None);
+
+    let corner = Rect::from_f64(-42.0, 58.0, 32.0, 32.0).moving(V2::new(20.0, 20.0));
+    let contact = corner.sweep(platform).unwrap();
+    assert_eq!((contact.time, contact.side), (0.5, Bottom));
+    assert_eq!(contact.pos, V2::new(-32.0, 100.0));
+
+    let grazing = Rect::from_f64(-32.0, 0.0, 32.0, 32.0).moving(V2::new(0.0, 200.0));
+    assert_eq!(grazing.sweep(platform), None);
+
+    let wall = Rect::from_f64(-40.0, 90.0, 32.0, 20.0).moving(V2::new(16.0, 0.0));
+    let contact = wall.sweep(platform).unwrap();
+    assert_eq!((contact.time, contact.side), (0.5, Right));
+
+    assert!(OctoDirection::Left.facing(OctoDirection::Left));
+    assert!(OctoDirection::BottomLeft.facing(OctoDirection::Left));
+    assert!(!OctoDirection::TopRight.facing(OctoDirection::Left));
+    assert!(OctoDirection::TopLeft.facing(OctoDirection::TopLeft));
+}
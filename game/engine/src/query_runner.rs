@@ -1,119 +1,85 @@
-use crate::{Component, ComponentQuery, Context};
+use std::any::TypeId;
 
+use crate::{Component, ComponentQuery, Context, Entity};
+
+/// Runs a [`ComponentQuery`], see [`query!`](crate::query).
 pub trait QueryRunner {
-    fn run(&self, context: &Context) -> Vec<u64>;
+    type Item<'a>;
+
+    fn run<'a>(&self, context: &'a Context) -> impl Iterator<Item = Self::Item<'a>> + 'a;
 }
 
 impl<T0> QueryRunner for ComponentQuery<T0>
 where
     T0: 'static + Component,
 {
-    fn run(&self, context: &Context) -> Vec<u64> {
-        context.entities_with_component::<T0>()
-    }
-}
+    type Item<'a> = (Entity, &'a T0);
 
-impl<T0, T1> QueryRunner for ComponentQuery<(T0, T1)>
-where
-    T0: 'static + Component,
-    T1: 'static + Component,
-{
-    fn run(&self, context: &Context) -> Vec<u64> {
-        let vs0 = context.entities_with_component::<T0>();
-        let vs1 = context.entities_with_component::<T1>();
-        vs0.into_iter()
-            .filter(|v0| vs1.iter().any(|v1| *v0 == *v1))
-            .collect()
+    fn run<'a>(&self, context: &'a Context) -> impl Iterator<Item = Self::Item<'a>> + 'a {
+        context.components::<T0>()
     }
 }
 
-impl<T0, T1, T2> QueryRunner for ComponentQuery<(T0, T1, T2)>
-where
-    T0: 'static + Component,
-    T1: 'static + Component,
-    T2: 'static + Component,
-{
-    fn run(&self, context: &Context) -> Vec<u64> {
-        let vs0 = context.entities_with_component::<T0>();
-        let vs1 = context.entities_with_component::<T1>();
-        let vs2 = context.entities_with_component::<T2>();
-        vs0.into_iter()
-            .filter(|v0| vs1.iter().any(|v1| *v0 == *v1) && vs2.iter().any(|v2| *v0 == *v2))
-            .collect()
-    }
-}
+/// Walks the entities of the smallest storage and looks the rest of the components up.
+macro_rules! impl_query_runner {
+    ($($t:ident),+) => {
+        impl<$($t),+> QueryRunner for ComponentQuery<($($t),+)>
+        where
+            $($t: 'static + Component),+
+        {
+            type Item<'a> = (Entity, $(&'a $t),+);
 
-impl<T0, T1, T2, T3> QueryRunner for ComponentQuery<(T0, T1, T2, T3)>
-where
-    T0: 'static + Component,
-    T1: 'static + Component,
-    T2: 'static + Component,
-    T3: 'static + Component,
-{
-    fn run(&self, context: &Context) -> Vec<u64> {
-        let vs0 = context.entities_with_component::<T0>();
-        let vs1 = context.entities_with_component::<T1>();
-        let vs2 = context.entities_with_component::<T2>();
-        let vs3 = context.entities_with_component::<T3>();
-        vs0.into_iter()
-            .filter(|v0| {
-                vs1.iter().any(|v1| *v0 == *v1)
-                    && vs2.iter().any(|v2| *v0 == *v2)
-                    && vs3.iter().any(|v3| *v0 == *v3)
-            })
-            .collect()
-    }
+            #[allow(non_snake_case)]
+            fn run<'a>(&self, context: &'a Context) -> impl Iterator<Item = Self::Item<'a>> + 'a {
+                let ($($t,)+) = ($(context.storage::<$t>(),)+);
+                context
+                    .query_candidates(&[$(TypeId::of::<$t>()),+])
+                    .filter_map(move |entity| Some((entity, $($t?.get(entity)?),+)))
+            }
+        }
+    };
 }
 
-impl<T0, T1, T2, T3, T4> QueryRunner for ComponentQuery<(T0, T1, T2, T3, T4)>
-where
-    T0: 'static + Component,
-    T1: 'static + Component,
-    T2: 'static + Component,
-    T3: 'static + Component,
-    T4: 'static + Component,
-{
-    fn run(&self, context: &Context) -> Vec<u64> {
-        let vs0 = context.entities_with_component::<T0>();
-        let vs1 = context.entities_with_component::<T1>();
-        let vs2 = context.entities_with_component::<T2>();
-        let vs3 = context.entities_with_component::<T3>();
-        let vs4 = context.entities_with_component::<T4>();
-        vs0.into_iter()
-            .filter(|v0| {
-                vs1.iter().any(|v| *v0 == *v)
-                    && vs2.iter().any(|v| *v0 == *v)
-                    && vs3.iter().any(|v| *v0 == *v)
-                    && vs4.iter().any(|v| *v0 == *v)
-            })
-            .collect()
-    }
-}
+impl_query_runner!(T0, T1);
+impl_query_runner!(T0, T1, T2);
+impl_query_runner!(T0, T1, T2, T3);
+impl_query_runner!(T0, T1, T2, T3, T4);
+impl_query_runner!(T0, T1, T2, T3, T4, T5);
 
-impl<T0, T1, T2, T3, T4, T5> QueryRunner for ComponentQuery<(T0, T1, T2, T3, T4, T5)>
-where
-    T0: 'static + Component,
-    T1: 'static + Component,
-    T2: 'static + Component,
-    T3: 'static + Component,
-    T4: 'static + Component,
-    T5: 'static + Component,
-{
-    fn run(&self, context: &Context) -> Vec<u64> {
-        let vs0 = context.entities_with_component::<T0>();
-        let vs1 = context.entities_with_component::<T1>();
-        let vs2 = context.entities_with_component::<T2>();
-        let vs3 = context.entities_with_component::<T3>();
-        let vs4 = context.entities_with_component::<T4>();
-        let vs5 = context.entities_with_component::<T5>();
-        vs0.into_iter()
-            .filter(|v0| {
-                vs1.iter().any(|v| *v0 == *v)
-                    && vs2.iter().any(|v| *v0 == *v)
-                    && vs3.iter().any(|v| *v0 == *v)
-                    && vs4.iter().any(|v| *v0 == *v)
-                    && vs5.iter().any(|v| *v0 == *v)
-            })
-            .collect()
-    }
+#[test]
+fn queries_borrow_components_of_entities_with_all_of_them() {
+    use crate::{query, query_entities, spawn, Component, Game};
+
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Health(u32);
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Name(&'static str);
+    #[derive(Component, Clone, Debug, PartialEq)]
+    struct Boss;
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    let knight = spawn!(&mut ctx, Health(10), Name("knight"));
+    spawn!(&mut ctx, Health(3));
+    let dragon = spawn!(&mut ctx, Health(99), Name("dragon"), Boss);
+    let ghost = spawn!(&mut ctx, Name("ghost"), Boss);
+
+    let named = query!(&ctx, Name, Health)
+        .map(|(id, name, health)| (id, name.0, health.0))
+        .collect::<Vec<_>>();
+    assert_eq!(named, vec![(knight, "knight", 10), (dragon, "dragon", 99)]);
+    assert_eq!(
+        query!(&ctx, Health, Name, Boss).collect::<Vec<_>>(),
+        vec![(dragon, &Health(99), &Name("dragon"), &Boss)]
+    );
+
+    ctx.despawn(dragon);
+    assert_eq!(query_entities!(&ctx, Name, Boss), vec![ghost]);
+    assert_eq!(
+        ctx.components::<Boss>()
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>(),
+        vec![ghost]
+    );
+    assert_eq!(ctx.components_mut::<Health>().count(), 2);
 }
@@ -0,0 +1,203 @@
+use crate::{backend::Backend, DrawTextureOpts, Error, Texture, V2};
+
+/// Layers are drawn back to front, in the order they are declared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderLayer {
+    Background,
+    World,
+    Effects,
+    Hud,
+    Ui,
+}
+
+/// Something to draw, in screen space.
+pub enum DrawCommand {
+    Texture {
+        texture: Texture,
+        pos: V2,
+        opts: DrawTextureOpts,
+    },
+    Rect {
+        rgba: (u8, u8, u8, u8),
+        pos: V2,
+        size: V2,
+    },
+}
+
+struct QueuedDraw {
+    layer: RenderLayer,
+    depth: i32,
+    command: DrawCommand,
+}
+
+/// Draws sent to the backend in one call.
+enum Batch<'a> {
+    Textures(Texture, Vec<(V2, &'a DrawTextureOpts)>),
+    Rect {
+        rgba: (u8, u8, u8, u8),
+        pos: (i32, i32),
+        size: (u32, u32),
+    },
+}
+
+/// Draws submitted during a frame, sent to the backend once every system has rendered.
+#[derive(Default)]
+pub(crate) struct RenderQueue {
+    draws: Vec<QueuedDraw>,
+}
+
+impl RenderQueue {
+    pub(crate) fn push(&mut self, layer: RenderLayer, depth: i32, command: DrawCommand) {
+        self.draws.push(QueuedDraw {
+            layer,
+            depth,
+            command,
+        });
+    }
+
+    /// Sorts the draws by layer, then by depth within a layer, keeping the order they were
+    /// submitted in otherwise, and groups neighbouring draws of the same texture.
+    fn batches(&mut self) -> Vec<Batch<'_>> {
+        self.draws.sort_by_key(|draw| (draw.layer, draw.depth));
+        let mut batches = Vec::<Batch>::new();
+        for draw in &self.draws {
+            match (&draw.command, batches.last_mut()) {
+                (
+                    DrawCommand::Texture { texture, pos, opts },
+                    Some(Batch::Textures(last, draws)),
+                ) if last == texture => {
+                    draws.push((*pos, opts));
+                }
+                (DrawCommand::Texture { texture, pos, opts }, _) => {
+                    batches.push(Batch::Textures(*texture, vec![(*pos, opts)]));
+                }
+                (DrawCommand::Rect { rgba, pos, size }, _) => batches.push(Batch::Rect {
+                    rgba: *rgba,
+                    pos: (pos.x as i32, pos.y as i32),
+                    size: (size.x.ceil() as u32, size.y.ceil() as u32),
+                }),
+            }
+        }
+        batches
+    }
+
+    /// Draws and clears the queue. A failing draw doesn't keep the rest from being drawn,
+    /// the first error is returned once everything else is.
+    pub(crate) fn flush(&mut self, backend: &mut dyn Backend) -> Result<(), Error> {
+        let mut result = Ok(());
+        for batch in self.batches() {
+            let drawn = match batch {
+                Batch::Textures(texture, draws) => backend.draw_textures(texture.0, &draws),
+                Batch::Rect { rgba, pos, size } => backend.fill_rect(rgba, pos, size),
+            };
+            result = result.and(drawn);
+        }
+        self.draws.clear();
+        result
+    }
+}
+
+#[test]
+fn render_queue_sorts_by_layer_and_depth_and_batches_textures() {
+    let texture = |id, x| DrawCommand::Texture {
+        texture: Texture(id),
+        pos: V2::new(x, 0.0),
+        opts: DrawTextureOpts::new(),
+    };
+    let rect = DrawCommand::Rect {
+        rgba: (0, 0, 0, 255),
+        pos: V2::new(0.0, 0.0),
+        size: V2::new(1.0, 1.0),
+    };
+
+    let mut queue = RenderQueue::default();
+    queue.push(RenderLayer::Ui, 0, texture(1, 0.0));
+    queue.push(RenderLayer::World, 0, texture(2, 1.0));
+    queue.push(RenderLayer::World, -1, texture(2, 2.0));
+    queue.push(RenderLayer::World, 0, texture(2, 3.0));
+    queue.push(RenderLayer::Background, 0, rect);
+    queue.push(RenderLayer::World, 0, texture(3, 4.0));
+    queue.push(RenderLayer::Ui, 0, texture(1, 5.0));
+
+    let batches = queue
+        .batches()
+        .into_iter()
+        .map(|batch| match batch {
+            Batch::Textures(texture, draws) => {
+                (Some(texture), draws.iter().map(|(pos, _)| pos.x).collect())
+            }
+            Batch::Rect { .. } => (None, vec![]),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        batches,
+        vec![
+            (None, vec![]),
+            (Some(Texture(2)), vec![2.0, 1.0, 3.0]),
+            (Some(Texture(3)), vec![4.0]),
+            (Some(Texture(1)), vec![0.0, 5.0]),
+        ]
+    );
+}
+
+#[test]
+fn systems_draw_on_the_layer_of_their_schedule() {
+    use crate::{backend::DrawCall, Context, Game, Schedule, Stage, System};
+
+    struct Menu;
+    impl System for Menu {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Ui)
+        }
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.draw_rect((1, 1, 1), 0, 0, 1, 1)
+        }
+    }
+
+    struct Hud;
+    impl System for Hud {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Ui).render_layer(RenderLayer::Hud)
+        }
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.draw_rect((2, 2, 2), 0, 0, 1, 1)
+        }
+    }
+
+    struct World;
+    impl System for World {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Render)
+        }
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.draw_rect((3, 3, 3), 0, 0, 1, 1)?;
+            ctx.draw(
+                RenderLayer::Background,
+                0,
+                DrawCommand::Rect {
+                    rgba: (4, 4, 4, 255),
+                    pos: V2::new(0.0, 0.0),
+                    size: V2::new(1.0, 1.0),
+                },
+            );
+            Ok(())
+        }
+    }
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(|_| Menu);
+    ctx.add_system(|_| Hud);
+    ctx.add_system(|_| World);
+    game.step();
+
+    let colors = game
+        .draw_calls()
+        .iter()
+        .filter_map(|call| match call {
+            DrawCall::Rect { rgba, .. } => Some(rgba.0),
+            DrawCall::Texture { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(colors, vec![4, 3, 2, 1]);
+}
@@ -1,9 +1,11 @@
 use super::{Context, Error, System};
-use crate::{query, rigid_body, Component, V2};
+use crate::{rigid_body, Component, Schedule, Stage, V2};
 
 #[derive(Component, Clone, Debug)]
 pub struct RigidBody {
     pub pos: V2,
+    /// Position before the latest fixed update, used to interpolate rendering.
+    pub prev_pos: V2,
     pub vel: V2,
     pub size: V2,
     pub gravity: bool,
@@ -14,6 +16,7 @@ impl RigidBody {
     pub fn new() -> Self {
         Self {
             pos: V2::new(0.0, 0.0),
+            prev_pos: V2::new(0.0, 0.0),
             vel: V2::new(0.0, 0.0),
             size: V2::new(0.0, 0.0),
             gravity: false,
@@ -22,7 +25,11 @@ impl RigidBody {
     }
 
     pub fn with_pos(self, pos: V2) -> Self {
-        Self { pos, ..self }
+        Self {
+            pos,
+            prev_pos: pos,
+            ..self
+        }
     }
 
     pub fn with_vel(self, vel: V2) -> Self {
@@ -43,13 +50,29 @@ impl RigidBody {
     pub fn with_drag(self) -> Self {
         Self { drag: true, ..self }
     }
+
+    /// Moves the body without it being interpolated from its old position.
+    pub fn teleport(&mut self, pos: V2) {
+        self.pos = pos;
+        self.prev_pos = pos;
+    }
+
+    pub fn interpolated_pos(&self, interpolation: f64) -> V2 {
+        self.prev_pos + (self.pos - self.prev_pos).extend(interpolation)
+    }
 }
 
 pub struct VelocitySystem(pub u64);
 impl System for VelocitySystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Physics)
+            .after::<GravitySystem>()
+            .after::<DragSystem>()
+    }
+
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
-        for id in query!(ctx, RigidBody) {
-            let body = ctx.select::<RigidBody>(id);
+        for (_, body) in ctx.components_mut::<RigidBody>() {
+            body.prev_pos = body.pos;
             body.pos += body.vel.extend(delta);
         }
         Ok(())
@@ -58,9 +81,12 @@ impl System for VelocitySystem {
 
 pub struct GravitySystem(pub u64);
 impl System for GravitySystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Physics)
+    }
+
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
-        for id in query!(ctx, RigidBody) {
-            let body = ctx.select::<RigidBody>(id);
+        for (_, body) in ctx.components_mut::<RigidBody>() {
             if !body.gravity {
                 continue;
             }
@@ -76,9 +102,12 @@ impl System for GravitySystem {
 
 pub struct DragSystem(pub u64);
 impl System for DragSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Physics).after::<GravitySystem>()
+    }
+
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
-        for id in query!(ctx, RigidBody) {
-            let body = ctx.select::<RigidBody>(id);
+        for (_, body) in ctx.components_mut::<RigidBody>() {
             if !body.drag {
                 continue;
             }
@@ -0,0 +1,215 @@
+use std::{any::TypeId, rc::Rc};
+
+use crate::{Context, Id, RenderLayer, System};
+
+/// Stages run in the order they are declared, every update and every render.
+/// Within a stage, systems run in the order they were added,
+/// unless reordered by [`Schedule::after`] and [`Schedule::before`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    /// Turns input into intent, e.g. movement and attacks.
+    Input,
+    /// Forces, collision and integration of rigid bodies.
+    Physics,
+    /// Rules reacting to where things ended up, e.g. hits and knockouts.
+    Gameplay,
+    /// Drawing the world.
+    Render,
+    /// Drawing and handling menus and the hud, on top of the world.
+    Ui,
+}
+
+type RunCondition = Rc<dyn Fn(&mut Context) -> bool>;
+
+/// Where and when a system runs.
+#[derive(Clone)]
+pub struct Schedule {
+    stage: Stage,
+    render_layer: Option<RenderLayer>,
+    after: Vec<TypeId>,
+    before: Vec<TypeId>,
+    run_conditions: Vec<RunCondition>,
+}
+
+impl Schedule {
+    pub fn new(stage: Stage) -> Self {
+        Self {
+            stage,
+            render_layer: None,
+            after: Vec::new(),
+            before: Vec::new(),
+            run_conditions: Vec::new(),
+        }
+    }
+
+    pub fn stage(self, stage: Stage) -> Self {
+        Self { stage, ..self }
+    }
+
+    /// Layer draws made while rendering end up in, unless they name one.
+    /// Defaults to [`RenderLayer::Ui`] for systems in [`Stage::Ui`], else [`RenderLayer::World`].
+    pub fn render_layer(self, render_layer: RenderLayer) -> Self {
+        Self {
+            render_layer: Some(render_layer),
+            ..self
+        }
+    }
+
+    pub(crate) fn default_render_layer(&self) -> RenderLayer {
+        self.render_layer.unwrap_or(match self.stage {
+            Stage::Ui => RenderLayer::Ui,
+            _ => RenderLayer::World,
+        })
+    }
+
+    /// Runs after every system of type `S` in the same stage.
+    pub fn after<S: System>(mut self) -> Self {
+        self.after.push(TypeId::of::<S>());
+        self
+    }
+
+    /// Runs before every system of type `S` in the same stage.
+    pub fn before<S: System>(mut self) -> Self {
+        self.before.push(TypeId::of::<S>());
+        self
+    }
+
+    /// Skips [`System::on_update`] unless every condition holds.
+    /// Rendering is not affected, so e.g. a paused game is still drawn.
+    pub fn run_if<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&mut Context) -> bool + 'static,
+    {
+        self.run_conditions.push(Rc::new(condition));
+        self
+    }
+
+    pub(crate) fn should_run(&self, ctx: &mut Context) -> bool {
+        self.run_conditions.iter().all(|condition| condition(ctx))
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ScheduledSystem {
+    pub(crate) id: Id,
+    pub(crate) system: Rc<dyn System>,
+    pub(crate) schedule: Schedule,
+    /// The state owning the system, if any.
+    pub(crate) state: Option<Id>,
+}
+
+impl ScheduledSystem {
+    fn runs_before(&self, other: &ScheduledSystem) -> bool {
+        self.schedule.before.contains(&other.system.inner_type_id())
+            || other.schedule.after.contains(&self.system.inner_type_id())
+    }
+}
+
+/// Orders systems by stage, then by their constraints.
+/// Ties are broken by the order systems were added in, so the result is deterministic.
+pub(crate) fn sort(systems: &mut Vec<ScheduledSystem>) {
+    systems.sort_by_key(|system| system.schedule.stage);
+
+    let mut sorted = Vec::with_capacity(systems.len());
+    let mut remaining = std::mem::take(systems);
+    while !remaining.is_empty() {
+        let stage = remaining[0].schedule.stage;
+        let stage_len = remaining
+            .iter()
+            .take_while(|system| system.schedule.stage == stage)
+            .count();
+        let mut stage_systems: Vec<_> = remaining.drain(..stage_len).collect();
+        while !stage_systems.is_empty() {
+            let next = (0..stage_systems.len()).find(|candidate| {
+                !stage_systems
+                    .iter()
+                    .any(|other| other.runs_before(&stage_systems[*candidate]))
+            });
+            let Some(next) = next else {
+                println!("system ordering in stage {stage:?} has a cycle, falling back to insertion order");
+                sorted.append(&mut stage_systems);
+                break;
+            };
+            sorted.push(stage_systems.remove(next));
+        }
+    }
+    *systems = sorted;
+}
+
+#[test]
+fn systems_run_by_stage_then_constraints_and_skip_when_conditions_fail() {
+    use crate::{Error, Game};
+
+    struct Log(Vec<&'static str>);
+    struct Enabled(bool);
+
+    struct Ui;
+    impl System for Ui {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Ui)
+        }
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("ui");
+            Ok(())
+        }
+    }
+
+    struct Move;
+    impl System for Move {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Physics)
+        }
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("move");
+            Ok(())
+        }
+    }
+
+    struct Collide;
+    impl System for Collide {
+        fn schedule(&self) -> Schedule {
+            Schedule::new(Stage::Physics).before::<Move>()
+        }
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("collide");
+            Ok(())
+        }
+    }
+
+    struct Gravity;
+    impl System for Gravity {
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("gravity");
+            Ok(())
+        }
+    }
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.insert_resource(Log(Vec::new()));
+    ctx.insert_resource(Enabled(true));
+    ctx.add_system(|_| Ui);
+    ctx.add_system(|_| Move);
+    ctx.add_system(|_| Collide);
+    ctx.add_system_with(
+        |_| Gravity,
+        |schedule| {
+            schedule
+                .stage(Stage::Physics)
+                .before::<Collide>()
+                .run_if(|ctx| ctx.resource::<Enabled>().0)
+        },
+    );
+
+    game.step();
+    let mut ctx = game.context();
+    assert_eq!(
+        std::mem::take(&mut ctx.resource::<Log>().0),
+        vec!["gravity", "collide", "move", "ui"]
+    );
+
+    ctx.resource::<Enabled>().0 = false;
+    game.step();
+    let mut ctx = game.context();
+    assert_eq!(ctx.resource::<Log>().0, vec!["collide", "move", "ui"]);
+}
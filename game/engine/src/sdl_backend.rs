@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use sdl2::{
+    controller::GameController as SdlGameController,
+    event::Event,
+    image::{self, LoadTexture, Sdl2ImageContext},
+    pixels::{Color, PixelFormatEnum},
+    rect::{Point, Rect},
+    render::{BlendMode, Canvas, Texture as SdlTexture, TextureCreator},
+    surface::Surface,
+    ttf::{self, Sdl2TtfContext},
+    video::{FullscreenType, Window, WindowContext},
+    GameControllerSubsystem, Sdl, VideoSubsystem,
+};
+
+use crate::{
+    backend::Backend,
+    capture::Image,
+    font::Font,
+    input::{Input, InputEvent},
+    text::TextRun,
+    DisplayMode, DrawTextureOpts, Error, Id, LOGICAL_HEIGHT, LOGICAL_WIDTH, V2,
+};
+
+pub(crate) struct SdlBackend<'game> {
+    // textures and fonts borrow from the contexts below, so they are declared
+    // first to make sure they are dropped first
+    textures: Vec<(Id, SdlTexture<'game>)>,
+    fonts: Vec<(Id, Font<'game>)>,
+    controllers: Vec<(Id, SdlGameController)>,
+    canvas: Canvas<Window>,
+    texture_creator: TextureCreator<WindowContext>,
+    event_pump: sdl2::EventPump,
+    ttf_context: Sdl2TtfContext,
+    #[allow(dead_code)]
+    image_context: Sdl2ImageContext,
+    controller_subsystem: GameControllerSubsystem,
+    #[allow(dead_code)]
+    video_subsystem: VideoSubsystem,
+    #[allow(dead_code)]
+    sdl_context: Sdl,
+}
+
+impl<'game> SdlBackend<'game> {
+    pub(crate) fn new() -> Result<Self, Error> {
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let controller_subsystem = sdl_context.game_controller()?;
+        let image_context = image::init(image::InitFlag::PNG)?;
+        let ttf_context = ttf::init().map_err(|e| e.to_string())?;
+
+        let window = video_subsystem
+            .window("Sky Clash", LOGICAL_WIDTH, LOGICAL_HEIGHT)
+            .position_centered()
+            .resizable()
+            .build()?;
+
+        let mut canvas = window.into_canvas().build()?;
+        // scales and letterboxes every draw, and maps mouse events back to logical coordinates
+        canvas.set_logical_size(LOGICAL_WIDTH, LOGICAL_HEIGHT)?;
+        let texture_creator = canvas.texture_creator();
+
+        canvas.set_draw_color(Color::BLACK);
+        canvas.clear();
+        canvas.present();
+        let event_pump = sdl_context.event_pump()?;
+        Ok(Self {
+            textures: Default::default(),
+            fonts: Default::default(),
+            controllers: Default::default(),
+            canvas,
+            texture_creator,
+            event_pump,
+            ttf_context,
+            image_context,
+            controller_subsystem,
+            video_subsystem,
+            sdl_context,
+        })
+    }
+
+    fn font(&self, font_id: Id) -> Result<&Font<'_>, Error> {
+        self.fonts
+            .iter()
+            .find_map(|(id, font)| if *id == font_id { Some(font) } else { None })
+            .ok_or("tried to render non-loaded text".into())
+    }
+
+    fn font_mut(&mut self, font_id: Id) -> Result<&mut Font<'game>, Error> {
+        self.fonts
+            .iter_mut()
+            .find_map(|(id, font)| if *id == font_id { Some(font) } else { None })
+            .ok_or("tried to render non-loaded text".into())
+    }
+}
+
+/// Copies the pixels of a surface, which may have padding at the end of each row.
+fn surface_to_image(surface: &Surface) -> Result<Image, Error> {
+    let surface = surface.convert_format(PixelFormatEnum::RGBA32)?;
+    let (width, height) = (surface.width(), surface.height());
+    let pitch = surface.pitch() as usize;
+    let pixels = surface.with_lock(|pixels| {
+        pixels
+            .chunks(pitch)
+            .take(height as usize)
+            .flat_map(|row| &row[..width as usize * 4])
+            .copied()
+            .collect()
+    });
+    Image::from_rgba(width, height, pixels)
+}
+
+impl<'game> Backend for SdlBackend<'game> {
+    fn poll_events(&mut self, input: &mut Input) {
+        for event in self.event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. } => input.apply(InputEvent::Quit),
+                Event::KeyDown {
+                    keycode: Some(btn), ..
+                } => input.apply(InputEvent::KeyDown(btn)),
+                Event::KeyUp {
+                    keycode: Some(btn), ..
+                } => input.apply(InputEvent::KeyUp(btn)),
+                Event::MouseMotion { x, y, .. } => input.apply(InputEvent::MouseMotion(x, y)),
+                Event::MouseButtonDown { mouse_btn: btn, .. } => {
+                    input.apply(InputEvent::MouseButtonDown(btn))
+                }
+                Event::MouseButtonUp { mouse_btn: btn, .. } => {
+                    input.apply(InputEvent::MouseButtonUp(btn))
+                }
+                Event::ControllerButtonDown {
+                    which, button: btn, ..
+                } => {
+                    input
+                        .currently_pressed_controller_buttons
+                        .insert((which.into(), btn), true);
+                }
+                Event::ControllerButtonUp {
+                    which, button: btn, ..
+                } => {
+                    input
+                        .currently_pressed_controller_buttons
+                        .remove(&(which.into(), btn));
+                }
+                Event::ControllerDeviceAdded { which, .. } => {
+                    let controller = self.controller_subsystem.open(which).unwrap();
+                    self.controllers.push((which.into(), controller));
+                    input.controllers.push((which.into(), Default::default()));
+                }
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    let id: Id = which.into();
+                    self.controllers.retain(|v| v.0 != id);
+                    input.controllers.retain(|v| v.0 != id);
+                }
+                Event::ControllerAxisMotion {
+                    value, which, axis, ..
+                } => {
+                    let id = which.into();
+                    let value = value as f64 / i16::MAX as f64;
+                    let Some((_, pos)) = input.controllers.iter_mut().find(|v| v.0 == id) else {
+                        println!("tried to get controller positions of unregistered id {id}");
+                        continue;
+                    };
+                    match axis {
+                        sdl2::controller::Axis::LeftX => pos.left_stick.x = value,
+                        sdl2::controller::Axis::LeftY => pos.left_stick.y = value,
+                        sdl2::controller::Axis::RightX => pos.right_stick.x = value,
+                        sdl2::controller::Axis::RightY => pos.right_stick.y = value,
+                        sdl2::controller::Axis::TriggerLeft => pos.left_trigger = value,
+                        sdl2::controller::Axis::TriggerRight => pos.right_trigger = value,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn clear(&mut self, (r, g, b): (u8, u8, u8)) {
+        self.canvas.set_draw_color(Color::RGB(r, g, b));
+        self.canvas.clear();
+    }
+
+    fn present(&mut self) {
+        self.canvas.present();
+    }
+
+    fn set_display_mode(&mut self, mode: DisplayMode) -> Result<(), Error> {
+        let fullscreen_type = match mode {
+            DisplayMode::Windowed => FullscreenType::Off,
+            DisplayMode::Fullscreen => FullscreenType::True,
+            DisplayMode::Borderless => FullscreenType::Desktop,
+        };
+        self.canvas.window_mut().set_fullscreen(fullscreen_type)?;
+        Ok(())
+    }
+
+    fn load_texture(&mut self, id: Id, path: &Path) -> Result<(), Error> {
+        let texture_creator: *const TextureCreator<WindowContext> = &self.texture_creator;
+        let texture: SdlTexture<'game> = unsafe { (*texture_creator).load_texture(path)? };
+        // reloading replaces the texture, keeping its id
+        self.textures.retain(|(texture_id, _)| *texture_id != id);
+        self.textures.push((id, texture));
+        Ok(())
+    }
+
+    fn load_font(&mut self, id: Id, path: &Path, size: u16) -> Result<(), Error> {
+        let ttf_context: *const Sdl2TtfContext = &self.ttf_context;
+        let font = Font(unsafe { (*ttf_context).load_font(path, size)? });
+        self.fonts.push((id, font));
+        Ok(())
+    }
+
+    fn unload_texture(&mut self, id: Id) {
+        self.textures.retain(|(texture_id, _)| *texture_id != id);
+    }
+
+    fn unload_font(&mut self, id: Id) {
+        self.fonts.retain(|(font_id, _)| *font_id != id);
+    }
+
+    fn render_text(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        text: &str,
+        (r, g, b): (u8, u8, u8),
+    ) -> Result<(u32, u32), Error> {
+        let Font(font) = self.font(font_id)?;
+        let surface = font.render(text).blended(Color { r, g, b, a: 255 })?;
+        let texture_creator: *const TextureCreator<WindowContext> = &self.texture_creator;
+        let texture: SdlTexture<'game> = unsafe { surface.as_texture(&*texture_creator) }?;
+        let size = (texture.query().width, texture.query().height);
+        self.textures.push((id, texture));
+        Ok(size)
+    }
+
+    /// Runs are composited in software, as SDL_ttf renders each to a surface of its own.
+    fn render_text_runs(
+        &mut self,
+        id: Id,
+        font_id: Id,
+        runs: &[TextRun],
+        (width, height): (u32, u32),
+    ) -> Result<(), Error> {
+        let mut image = Image::new(width.max(1), height.max(1));
+        for run in runs.iter().filter(|run| !run.text.is_empty()) {
+            let Font(font) = self.font_mut(font_id)?;
+            let (r, g, b) = run.rgb;
+            font.set_outline_width(run.outline);
+            let surface = font.render(&run.text).blended(Color { r, g, b, a: 255 });
+            font.set_outline_width(0);
+            image.draw_over(&surface_to_image(&surface?)?, run.pos);
+        }
+        let texture_creator: *const TextureCreator<WindowContext> = &self.texture_creator;
+        let mut texture: SdlTexture<'game> = unsafe {
+            (*texture_creator).create_texture_static(
+                PixelFormatEnum::RGBA32,
+                image.width,
+                image.height,
+            )
+        }?;
+        texture.update(None, &image.pixels, image.width as usize * 4)?;
+        texture.set_blend_mode(BlendMode::Blend);
+        self.textures.push((id, texture));
+        Ok(())
+    }
+
+    fn text_size(&mut self, font_id: Id, text: &str) -> Result<(u32, u32), Error> {
+        let Font(font) = self.font(font_id)?;
+        Ok(font.size_of(text).map_err(|e| e.to_string())?)
+    }
+
+    fn texture_size(&mut self, texture: Id) -> Result<(u32, u32), Error> {
+        let texture = self
+            .textures
+            .iter()
+            .find_map(|v| if v.0 == texture { Some(&v.1) } else { None })
+            .ok_or("invalid sprite id")?;
+        Ok((texture.query().width, texture.query().height))
+    }
+
+    fn draw_texture(&mut self, texture: Id, pos: V2, opts: &DrawTextureOpts) -> Result<(), Error> {
+        self.draw_textures(texture, &[(pos, opts)])
+    }
+
+    fn draw_textures(
+        &mut self,
+        texture: Id,
+        draws: &[(V2, &DrawTextureOpts)],
+    ) -> Result<(), Error> {
+        let texture = self
+            .textures
+            .iter_mut()
+            .find_map(|v| if v.0 == texture { Some(&mut v.1) } else { None })
+            .ok_or("invalid sprite id")?;
+        for (pos, opts) in draws {
+            copy_texture(&mut self.canvas, texture, *pos, opts)?;
+        }
+        Ok(())
+    }
+
+    fn fill_rect(
+        &mut self,
+        (r, g, b, a): (u8, u8, u8, u8),
+        (x, y): (i32, i32),
+        (w, h): (u32, u32),
+    ) -> Result<(), Error> {
+        if a < 255 {
+            self.canvas.set_blend_mode(BlendMode::Blend);
+        }
+        self.canvas.set_draw_color(Color { r, g, b, a });
+        self.canvas.fill_rect(Rect::new(x, y, w, h))?;
+        Ok(())
+    }
+
+    /// Reads at the resolution of the window, without the letterboxing around the logical size.
+    fn read_pixels(&mut self) -> Result<Image, Error> {
+        let (output_width, _) = self.canvas.output_size()?;
+        let (scale_x, scale_y) = self.canvas.scale();
+        let viewport = self.canvas.viewport();
+        let width = (viewport.width() as f32 * scale_x).round() as u32;
+        let height = (viewport.height() as f32 * scale_y).round() as u32;
+        // rows are as long as the output, but only the viewport is read
+        let pixels = self.canvas.read_pixels(None, PixelFormatEnum::RGBA32)?;
+        let pitch = output_width as usize * 4;
+        let pixels = pixels
+            .chunks(pitch)
+            .take(height as usize)
+            .flat_map(|row| &row[..width as usize * 4])
+            .copied()
+            .collect();
+        Image::from_rgba(width, height, pixels)
+    }
+}
+
+fn copy_texture(
+    canvas: &mut Canvas<Window>,
+    texture: &mut SdlTexture,
+    pos: V2,
+    opts: &DrawTextureOpts,
+) -> Result<(), Error> {
+    let size = opts
+        .size
+        .or(opts.source.map(|(_, size)| size))
+        .unwrap_or(V2::new(
+            texture.query().width as f64,
+            texture.query().height as f64,
+        ));
+    let source = opts
+        .source
+        .map(|(pos, size)| Rect::new(pos.x as i32, pos.y as i32, size.x as u32, size.y as u32));
+    if let Some(color) = opts.color_mod {
+        texture.set_color_mod(color.0, color.1, color.2);
+    } else {
+        texture.set_color_mod(255, 255, 255);
+    }
+    if let Some(alpha) = opts.opacity {
+        texture.set_alpha_mod((alpha * 255.0) as u8);
+    } else {
+        texture.set_alpha_mod(255);
+    }
+    if let Some(angle) = opts.angle {
+        canvas.copy_ex(
+            texture,
+            source,
+            Rect::new(pos.x as i32, pos.y as i32, size.x as u32, size.y as u32),
+            angle,
+            Point::new(0, 0),
+            false,
+            false,
+        )?;
+    } else {
+        canvas.copy(
+            texture,
+            source,
+            Rect::new(pos.x as i32, pos.y as i32, size.x as u32, size.y as u32),
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,161 @@
+use std::collections::HashSet;
+
+use crate::{Context, Entity, Id};
+
+pub(crate) enum Transition {
+    Push {
+        add_root_system: Box<dyn FnOnce(&mut Context) -> Id>,
+        overlay: bool,
+    },
+    Pop,
+}
+
+/// A state on the stack, e.g. a menu or a match.
+/// Systems and entities added while a state is on top are owned by it,
+/// and are removed along with it.
+pub(crate) struct State {
+    id: Id,
+    overlay: bool,
+    entities: HashSet<Entity>,
+}
+
+#[derive(Default)]
+pub(crate) struct States {
+    stack: Vec<State>,
+    pub(crate) transitions: Vec<Transition>,
+    id_counter: Id,
+}
+
+impl States {
+    pub(crate) fn push(&mut self, overlay: bool) {
+        let id = self.id_counter;
+        self.id_counter += 1;
+        self.stack.push(State {
+            id,
+            overlay,
+            entities: HashSet::new(),
+        });
+    }
+
+    /// Returns the popped state and the entities it owned.
+    pub(crate) fn pop(&mut self) -> Option<(Id, HashSet<Entity>)> {
+        self.stack.pop().map(|state| (state.id, state.entities))
+    }
+
+    pub(crate) fn top(&self) -> Option<Id> {
+        self.stack.last().map(|state| state.id)
+    }
+
+    /// Systems without a state always update, other systems only update while their state is on top.
+    pub(crate) fn is_updating(&self, state: Option<Id>) -> bool {
+        state.is_none() || state == self.top()
+    }
+
+    /// The topmost state which isn't an overlay is rendered, along with every overlay above it.
+    pub(crate) fn is_rendering(&self, state: Option<Id>) -> bool {
+        let Some(state) = state else {
+            return true;
+        };
+        self.stack
+            .iter()
+            .rev()
+            .scan(false, |below_opaque, frame| {
+                if *below_opaque {
+                    return None;
+                }
+                *below_opaque = !frame.overlay;
+                Some(frame.id)
+            })
+            .any(|id| id == state)
+    }
+
+    pub(crate) fn own(&mut self, entity: Entity) {
+        if let Some(state) = self.stack.last_mut() {
+            state.entities.insert(entity);
+        }
+    }
+
+    pub(crate) fn disown(&mut self, entity: Entity) {
+        for state in &mut self.stack {
+            state.entities.remove(&entity);
+        }
+    }
+}
+
+#[test]
+fn states_own_systems_and_entities_and_overlays_render_over_lower_states() {
+    use crate::{query, spawn, Component, Error, Game, System};
+
+    #[derive(Default)]
+    struct Log(Vec<&'static str>);
+
+    #[derive(Component)]
+    struct Owned;
+
+    struct Menu;
+    impl System for Menu {
+        fn on_add(&self, ctx: &mut Context) -> Result<(), Error> {
+            spawn!(ctx, Owned);
+            Ok(())
+        }
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("menu update");
+            Ok(())
+        }
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("menu render");
+            Ok(())
+        }
+    }
+
+    struct Pause;
+    impl System for Pause {
+        fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("pause update");
+            Ok(())
+        }
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            ctx.resource::<Log>().0.push("pause render");
+            Ok(())
+        }
+    }
+
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.insert_resource(Log::default());
+    ctx.push_state(|_| Menu);
+    assert_eq!(query!(&ctx, Owned).count(), 0, "transitions are deferred");
+
+    game.step();
+    let mut ctx = game.context();
+    assert_eq!(query!(&ctx, Owned).count(), 1);
+    assert_eq!(ctx.resource::<Log>().0, vec!["menu render"]);
+
+    ctx.resource::<Log>().0.clear();
+    ctx.push_overlay(|_| Pause);
+    game.step();
+    game.step();
+    let mut ctx = game.context();
+    assert_eq!(
+        ctx.resource::<Log>().0,
+        vec![
+            "menu update",
+            "menu render",
+            "pause render",
+            "pause update",
+            "menu render",
+            "pause render"
+        ]
+    );
+
+    ctx.resource::<Log>().0.clear();
+    ctx.pop_state();
+    ctx.replace_state(|_| Pause);
+    game.step();
+    let mut ctx = game.context();
+    assert_eq!(query!(&ctx, Owned).count(), 0);
+    assert_eq!(
+        ctx.resource::<Log>().0,
+        vec!["pause update", "pause render"]
+    );
+}
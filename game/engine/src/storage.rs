@@ -0,0 +1,130 @@
+use std::any::Any;
+
+use crate::{Component, Entity};
+
+/// Type erased access to a [`SparseSet`], so every storage can be held in one map
+/// and entities can be despawned without knowing their component types.
+pub(crate) trait Storage {
+    fn remove(&mut self, entity: Entity);
+    fn len(&self) -> usize;
+    fn entities(&self) -> &[Entity];
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Components of a single type, packed densely so they can be iterated without
+/// touching entities which don't have one.
+/// The sparse half is indexed by entity index and points into the dense half.
+pub(crate) struct SparseSet<T> {
+    sparse: Vec<Option<usize>>,
+    entities: Vec<Entity>,
+    components: Vec<T>,
+}
+
+impl<T> SparseSet<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            entities: Vec::new(),
+            components: Vec::new(),
+        }
+    }
+
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        let index = (*self.sparse.get(entity.index())?)?;
+        if self.entities[index] == entity {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn insert(&mut self, entity: Entity, component: T) {
+        if let Some(index) = self.dense_index(entity) {
+            self.components[index] = component;
+            return;
+        }
+        if self.sparse.len() <= entity.index() {
+            self.sparse.resize(entity.index() + 1, None);
+        }
+        self.sparse[entity.index()] = Some(self.components.len());
+        self.entities.push(entity);
+        self.components.push(component);
+    }
+
+    pub(crate) fn get(&self, entity: Entity) -> Option<&T> {
+        self.dense_index(entity)
+            .map(|index| &self.components[index])
+    }
+
+    pub(crate) fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.dense_index(entity)
+            .map(|index| &mut self.components[index])
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.components.iter())
+    }
+
+    pub(crate) fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.entities
+            .iter()
+            .copied()
+            .zip(self.components.iter_mut())
+    }
+}
+
+impl<T: Component> Storage for SparseSet<T> {
+    fn remove(&mut self, entity: Entity) {
+        let Some(index) = self.dense_index(entity) else {
+            return;
+        };
+        self.sparse[entity.index()] = None;
+        self.entities.swap_remove(index);
+        self.components.swap_remove(index);
+        if let Some(moved) = self.entities.get(index) {
+            self.sparse[moved.index()] = Some(index);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+#[test]
+fn sparse_set_keeps_lookups_valid_after_remove() {
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+    impl Component for Health {
+        fn as_any(&mut self) -> &mut dyn Any {
+            self
+        }
+    }
+
+    let mut entities = crate::entity::Entities::default();
+    let [a, b, c] = [entities.spawn(), entities.spawn(), entities.spawn()];
+    let mut set = SparseSet::new();
+    set.insert(a, Health(1));
+    set.insert(b, Health(2));
+    set.insert(c, Health(3));
+    Storage::remove(&mut set, a);
+
+    assert_eq!(set.get(a), None);
+    assert_eq!(set.len(), 2);
+    assert_eq!(set.get_mut(b), Some(&mut Health(2)));
+    assert_eq!(set.get_mut(c), Some(&mut Health(3)));
+    assert_eq!(set.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![c, b]);
+}
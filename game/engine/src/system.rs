@@ -1,4 +1,8 @@
-use super::{context::Context, Error};
+use super::{
+    context::Context,
+    schedule::{Schedule, Stage},
+    Error,
+};
 
 pub trait System
 where
@@ -10,9 +14,19 @@ where
     fn on_update(&self, _ctx: &mut Context, _delta: f64) -> Result<(), Error> {
         Ok(())
     }
+    /// Called once per rendered frame, after any fixed updates.
+    /// `interpolation` is how far, from 0 to 1, the frame is between the
+    /// previous and the current fixed update.
+    fn on_render(&self, _ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+        Ok(())
+    }
     fn on_remove(&self, _ctx: &mut Context) -> Result<(), Error> {
         Ok(())
     }
+    /// Where and when the system runs, unless overridden when it is added.
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay)
+    }
     fn inner_type_id(&self) -> std::any::TypeId {
         std::any::TypeId::of::<Self>()
     }
@@ -1,10 +1,429 @@
-use crate::V2;
+use crate::{Error, V2};
 
 use super::Texture;
 
+/// Handle to rendered text. It stays valid while it's drawn every frame or so, as text
+/// textures going undrawn for a while are evicted, see [`TextCacheLimits`]. Rendering the
+/// same text again is cheap while it's cached, so prefer it to keeping handles around.
+///
+/// [`TextCacheLimits`]: crate::TextCacheLimits
 #[derive(Clone, Copy)]
 #[must_use]
 pub struct Text {
     pub texture: Texture,
     pub size: V2,
 }
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+impl TextAlign {
+    /// Where something `width` wide starts when aligned within `space`.
+    pub fn offset(self, space: f64, width: f64) -> f64 {
+        match self {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (space - width) / 2.0,
+            TextAlign::Right => space - width,
+        }
+    }
+}
+
+/// Drawn around the glyphs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextOutline {
+    pub width: u16,
+    pub color: (u8, u8, u8),
+}
+
+/// A copy of the text drawn behind it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TextShadow {
+    pub offset: (i32, i32),
+    pub color: (u8, u8, u8),
+}
+
+/// How [`Context::render_text_styled`](crate::Context::render_text_styled) lays out and decorates text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TextStyle {
+    pub color: (u8, u8, u8),
+    /// Lines are wrapped at spaces to fit within this many pixels.
+    /// A word wider than a line gets a line of its own.
+    pub max_width: Option<u32>,
+    /// How lines of different widths line up.
+    pub align: TextAlign,
+    /// Pixels added between lines.
+    pub line_spacing: u32,
+    pub outline: Option<TextOutline>,
+    pub shadow: Option<TextShadow>,
+    /// Whether `[color=#rrggbb]` and `[/color]` tags change the color of the text between them.
+    pub markup: bool,
+}
+
+impl TextStyle {
+    pub fn new() -> Self {
+        Self {
+            color: (255, 255, 255),
+            max_width: None,
+            align: TextAlign::Left,
+            line_spacing: 0,
+            outline: None,
+            shadow: None,
+            markup: false,
+        }
+    }
+
+    pub fn color(self, color: (u8, u8, u8)) -> Self {
+        Self { color, ..self }
+    }
+
+    pub fn max_width(self, max_width: u32) -> Self {
+        Self {
+            max_width: Some(max_width),
+            ..self
+        }
+    }
+
+    pub fn align(self, align: TextAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn line_spacing(self, line_spacing: u32) -> Self {
+        Self {
+            line_spacing,
+            ..self
+        }
+    }
+
+    pub fn outline(self, width: u16, color: (u8, u8, u8)) -> Self {
+        Self {
+            outline: Some(TextOutline { width, color }),
+            ..self
+        }
+    }
+
+    pub fn shadow(self, offset: (i32, i32), color: (u8, u8, u8)) -> Self {
+        Self {
+            shadow: Some(TextShadow { offset, color }),
+            ..self
+        }
+    }
+
+    pub fn markup(self) -> Self {
+        Self {
+            markup: true,
+            ..self
+        }
+    }
+
+    /// Whether `text` is a single line of one color, which backends render directly.
+    pub(crate) fn is_plain(&self, text: &str) -> bool {
+        self.max_width.is_none()
+            && self.outline.is_none()
+            && self.shadow.is_none()
+            && !self.markup
+            && !text.contains('\n')
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Text of one color, positioned within the texture it's rendered into.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TextRun {
+    pub text: String,
+    pub rgb: (u8, u8, u8),
+    pub pos: (i32, i32),
+    /// Rendered as an outline of this width, which makes the glyphs this much larger on every side.
+    pub outline: u16,
+}
+
+fn parse_color_tag(tag: &str) -> Option<(u8, u8, u8)> {
+    let hex = tag.strip_prefix("color=#")?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Splits text into pieces of a color. Tags nest, and anything which isn't a valid tag is kept as text.
+pub(crate) fn parse_markup(text: &str, color: (u8, u8, u8)) -> Vec<(String, (u8, u8, u8))> {
+    let mut colors = vec![color];
+    let mut pieces = Vec::<(String, (u8, u8, u8))>::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let tag = rest
+            .strip_prefix('[')
+            .and_then(|after| after.split_once(']'))
+            .and_then(|(tag, after)| {
+                if tag == "/color" && colors.len() > 1 {
+                    colors.pop();
+                    Some(after)
+                } else {
+                    let color = parse_color_tag(tag)?;
+                    colors.push(color);
+                    Some(after)
+                }
+            });
+        if let Some(after) = tag {
+            rest = after;
+            continue;
+        }
+        // the first character is text even if it's a bracket which didn't start a tag
+        let first = rest.chars().next().map_or(0, char::len_utf8);
+        let end = rest[first..].find('[').map_or(rest.len(), |i| i + first);
+        let color = *colors.last().unwrap();
+        match pieces.last_mut() {
+            Some((piece, last_color)) if *last_color == color => piece.push_str(&rest[..end]),
+            _ => pieces.push((rest[..end].to_string(), color)),
+        }
+        rest = &rest[end..];
+    }
+    pieces
+}
+
+/// Pieces of a word, which may change color midway.
+type Word = Vec<(String, (u8, u8, u8))>;
+
+/// Breaks text into lines of words, wrapping at `max_width` if given.
+/// Every space is a word break, so runs of spaces collapse into one.
+fn wrap(
+    pieces: Vec<(String, (u8, u8, u8))>,
+    max_width: Option<u32>,
+    space_width: u32,
+    measure: &mut impl FnMut(&str) -> Result<(u32, u32), Error>,
+) -> Result<Vec<Vec<Word>>, Error> {
+    let mut paragraphs = vec![vec![Word::new()]];
+    for (text, color) in pieces {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                paragraphs.push(vec![Word::new()]);
+            }
+            let words = paragraphs.last_mut().unwrap();
+            for (j, part) in line.split(' ').enumerate() {
+                if j > 0 {
+                    words.push(Word::new());
+                }
+                if !part.is_empty() {
+                    words.last_mut().unwrap().push((part.to_string(), color));
+                }
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for words in paragraphs {
+        let mut line = Vec::<Word>::new();
+        let mut line_width = 0;
+        for word in words.into_iter().filter(|word| !word.is_empty()) {
+            let mut width = 0;
+            for (text, _) in &word {
+                width += measure(text)?.0;
+            }
+            let fits = max_width.is_none_or(|max| line_width + space_width + width <= max);
+            if line.is_empty() {
+                line_width = width;
+            } else if fits {
+                line_width += space_width + width;
+            } else {
+                lines.push(std::mem::take(&mut line));
+                line_width = width;
+            }
+            line.push(word);
+        }
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Lays styled text out as runs to render into one texture of the returned size.
+/// Shadows are listed first and outlines before the text they surround, so runs are drawn in order.
+pub(crate) fn layout_text(
+    text: &str,
+    style: &TextStyle,
+    mut measure: impl FnMut(&str) -> Result<(u32, u32), Error>,
+) -> Result<(Vec<TextRun>, (u32, u32)), Error> {
+    let pieces = if style.markup {
+        parse_markup(text, style.color)
+    } else {
+        vec![(text.to_string(), style.color)]
+    };
+    let (space_width, line_height) = measure(" ")?;
+    let lines = wrap(pieces, style.max_width, space_width, &mut measure)?;
+
+    // runs of each line, positioned relative to the start of the line
+    let mut laid_out = Vec::new();
+    for line in lines {
+        let mut runs = Vec::<(String, (u8, u8, u8))>::new();
+        for (i, word) in line.into_iter().enumerate() {
+            if i > 0 {
+                // spaces take the color of the text before them
+                runs.last_mut().unwrap().0.push(' ');
+            }
+            for (text, color) in word {
+                match runs.last_mut() {
+                    Some((run, last_color)) if *last_color == color => run.push_str(&text),
+                    _ => runs.push((text, color)),
+                }
+            }
+        }
+        let mut line_text = String::new();
+        let mut positioned = Vec::new();
+        for (text, color) in runs {
+            // measured as a prefix of the line, so kerning between runs is kept
+            let x = if line_text.is_empty() {
+                0
+            } else {
+                measure(&line_text)?.0
+            };
+            line_text.push_str(&text);
+            positioned.push((text, color, x));
+        }
+        let width = if line_text.is_empty() {
+            0
+        } else {
+            measure(&line_text)?.0
+        };
+        laid_out.push((positioned, width));
+    }
+
+    let text_width = laid_out.iter().map(|(_, width)| *width).max().unwrap_or(0);
+    let line_count = laid_out.len() as u32;
+    let text_height = line_count * line_height + (line_count - 1) * style.line_spacing;
+
+    let outline = style.outline.map_or(0, |outline| outline.width);
+    let (shadow_x, shadow_y) = style.shadow.map_or((0, 0), |shadow| shadow.offset);
+    let left = i32::from(outline) + (-shadow_x).max(0);
+    let top = i32::from(outline) + (-shadow_y).max(0);
+    let size = (
+        text_width + 2 * u32::from(outline) + shadow_x.unsigned_abs(),
+        text_height + 2 * u32::from(outline) + shadow_y.unsigned_abs(),
+    );
+
+    let mut text_runs = Vec::new();
+    for (i, (runs, width)) in laid_out.into_iter().enumerate() {
+        let line_x = style
+            .align
+            .offset(f64::from(text_width), f64::from(width))
+            .round() as i32;
+        let y = top + (i as u32 * (line_height + style.line_spacing)) as i32;
+        for (text, rgb, x) in runs {
+            text_runs.push(TextRun {
+                text,
+                rgb,
+                pos: (left + line_x + x as i32, y),
+                outline: 0,
+            });
+        }
+    }
+
+    let mut layers = Vec::new();
+    let outlined = |runs: &[TextRun], rgb| {
+        runs.iter()
+            .map(move |run| TextRun {
+                text: run.text.clone(),
+                rgb,
+                pos: (
+                    run.pos.0 - i32::from(outline),
+                    run.pos.1 - i32::from(outline),
+                ),
+                outline,
+            })
+            .collect::<Vec<_>>()
+    };
+    if let Some(TextShadow {
+        offset: (x, y),
+        color: rgb,
+    }) = style.shadow
+    {
+        let mut shadow = text_runs
+            .iter()
+            .map(|run| TextRun { rgb, ..run.clone() })
+            .collect::<Vec<_>>();
+        if outline > 0 {
+            shadow.extend(outlined(&text_runs, rgb));
+        }
+        for run in &mut shadow {
+            run.pos = (run.pos.0 + x, run.pos.1 + y);
+        }
+        layers.extend(shadow);
+    }
+    if let Some(TextOutline { color, .. }) = style.outline {
+        layers.extend(outlined(&text_runs, color));
+    }
+    layers.extend(text_runs);
+    Ok((layers, size))
+}
+
+#[test]
+fn styled_text_wraps_aligns_and_colors_runs() {
+    // every character is 10 pixels wide and lines are 20 pixels tall
+    let measure = |text: &str| Ok((text.chars().count() as u32 * 10, 20));
+    let red = (255, 0, 0);
+    let white = (255, 255, 255);
+
+    assert_eq!(
+        parse_markup("a [color=#ff0000]b [x][/color] é[/color]", white),
+        vec![
+            ("a ".to_string(), white),
+            ("b [x]".to_string(), red),
+            (" é[/color]".to_string(), white),
+        ]
+    );
+
+    let style = TextStyle::new()
+        .max_width(90)
+        .align(TextAlign::Right)
+        .line_spacing(5)
+        .markup();
+    let (runs, size) = layout_text(
+        "one [color=#ff0000]two[/color]\nthree four",
+        &style,
+        measure,
+    )
+    .unwrap();
+    let runs = runs
+        .iter()
+        .map(|run| (run.text.as_str(), run.rgb, run.pos))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        runs,
+        vec![
+            ("one ", white, (0, 0)),
+            ("two", red, (40, 0)),
+            ("three", white, (20, 25)),
+            ("four", white, (30, 50)),
+        ],
+        "wrapped after 'three', which would make a line of 100 pixels"
+    );
+    assert_eq!(size, (70, 70));
+
+    let style = TextStyle::new()
+        .outline(2, (0, 0, 0))
+        .shadow((3, -4), (1, 1, 1));
+    let (runs, size) = layout_text("hi", &style, measure).unwrap();
+    let runs = runs
+        .iter()
+        .map(|run| (run.rgb, run.pos, run.outline))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        runs,
+        vec![
+            ((1, 1, 1), (5, 2), 0),
+            ((1, 1, 1), (3, 0), 2),
+            ((0, 0, 0), (0, 4), 2),
+            (white, (2, 6), 0),
+        ],
+        "shadow, then outline, then text"
+    );
+    assert_eq!(size, (20 + 4 + 3, 20 + 4 + 4));
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{id::Id, texture::TextTextureKey, Text};
+
+/// Bounds on how much the text cache keeps, set with
+/// [`Context::set_text_cache_limits`](crate::Context::set_text_cache_limits).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextCacheLimits {
+    /// Frames a text texture is kept without being rendered or drawn.
+    pub max_text_age: u64,
+    /// Most text textures kept, the least recently used are evicted beyond it.
+    pub max_texts: usize,
+    /// Frames a font stays loaded without being used.
+    /// Evicted fonts keep their id and are loaded again when used.
+    pub max_font_age: u64,
+}
+
+impl Default for TextCacheLimits {
+    fn default() -> Self {
+        Self {
+            max_text_age: 600,
+            max_texts: 512,
+            max_font_age: 3600,
+        }
+    }
+}
+
+/// Counters of the text and font caches, for debugging memory use.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextCacheStats {
+    pub cached_texts: usize,
+    /// Pixels of every cached text texture, a quarter of the memory they take up.
+    pub cached_text_pixels: u64,
+    pub loaded_fonts: usize,
+    /// Lookups which found a cached texture, since the game started.
+    pub hits: u64,
+    /// Lookups which had to render a new texture, since the game started.
+    pub misses: u64,
+    pub evicted_texts: u64,
+    pub evicted_fonts: u64,
+}
+
+struct CachedText {
+    text: Text,
+    last_used: u64,
+}
+
+pub(crate) struct CachedFont {
+    pub id: Id,
+    pub size: u16,
+    pub path: PathBuf,
+    pub loaded: bool,
+    last_used: u64,
+}
+
+/// Rendered text textures and loaded fonts, evicted once unused for a while.
+#[derive(Default)]
+pub(crate) struct TextCache {
+    texts: HashMap<TextTextureKey, CachedText>,
+    keys_by_texture: HashMap<Id, TextTextureKey>,
+    fonts: Vec<CachedFont>,
+    pub limits: TextCacheLimits,
+    stats: TextCacheStats,
+}
+
+impl TextCache {
+    pub(crate) fn get(&mut self, key: &TextTextureKey, frame: u64) -> Option<Text> {
+        let Some(cached) = self.texts.get_mut(key) else {
+            self.stats.misses += 1;
+            return None;
+        };
+        self.stats.hits += 1;
+        cached.last_used = frame;
+        Some(cached.text)
+    }
+
+    pub(crate) fn insert(&mut self, key: TextTextureKey, text: Text, frame: u64) {
+        self.keys_by_texture.insert(text.texture.0, key.clone());
+        self.texts.insert(
+            key,
+            CachedText {
+                text,
+                last_used: frame,
+            },
+        );
+    }
+
+    /// Keeps a text texture which is drawn, e.g. from a handle kept between frames.
+    pub(crate) fn touch_texture(&mut self, texture: Id, frame: u64) {
+        if let Some(key) = self.keys_by_texture.get(&texture) {
+            if let Some(cached) = self.texts.get_mut(key) {
+                cached.last_used = frame;
+            }
+        }
+    }
+
+    /// Whether a text texture is still cached, every text texture is until evicted.
+    pub(crate) fn contains_texture(&self, texture: Id) -> bool {
+        self.keys_by_texture.contains_key(&texture)
+    }
+
+    pub(crate) fn font(&mut self, path: &Path, size: u16) -> Option<&mut CachedFont> {
+        self.fonts
+            .iter_mut()
+            .find(|font| font.path == path && font.size == size)
+    }
+
+    /// Marks the font as used, returning it if it has to be loaded again.
+    pub(crate) fn use_font(&mut self, id: Id, frame: u64) -> Option<&mut CachedFont> {
+        let font = self.fonts.iter_mut().find(|font| font.id == id)?;
+        font.last_used = frame;
+        (!font.loaded).then_some(font)
+    }
+
+    pub(crate) fn insert_font(&mut self, id: Id, path: &Path, size: u16, frame: u64) {
+        self.fonts.push(CachedFont {
+            id,
+            size,
+            path: path.to_path_buf(),
+            loaded: true,
+            last_used: frame,
+        });
+    }
+
+    /// Forgets texts and unloads fonts which went unused for longer than the limits allow,
+    /// and the least recently used texts beyond the most kept. Returns the ids of the
+    /// textures and fonts to free.
+    pub(crate) fn evict(&mut self, frame: u64) -> (Vec<Id>, Vec<Id>) {
+        let limits = self.limits;
+        let mut by_age = self
+            .texts
+            .iter()
+            .map(|(key, cached)| (cached.last_used, key.clone()))
+            .collect::<Vec<_>>();
+        by_age.sort_by_key(|(last_used, _)| *last_used);
+        let over_limit = by_age.len().saturating_sub(limits.max_texts);
+        let textures = by_age
+            .into_iter()
+            .enumerate()
+            .filter(|(i, (last_used, _))| {
+                *i < over_limit || frame.saturating_sub(*last_used) > limits.max_text_age
+            })
+            .filter_map(|(_, (_, key))| {
+                let texture = self.texts.remove(&key)?.text.texture.0;
+                self.keys_by_texture.remove(&texture);
+                Some(texture)
+            })
+            .collect::<Vec<_>>();
+
+        let fonts = self
+            .fonts
+            .iter_mut()
+            .filter(|font| {
+                font.loaded && frame.saturating_sub(font.last_used) > limits.max_font_age
+            })
+            .map(|font| {
+                font.loaded = false;
+                font.id
+            })
+            .collect::<Vec<_>>();
+
+        self.stats.evicted_texts += textures.len() as u64;
+        self.stats.evicted_fonts += fonts.len() as u64;
+        (textures, fonts)
+    }
+
+    pub(crate) fn stats(&self) -> TextCacheStats {
+        TextCacheStats {
+            cached_texts: self.texts.len(),
+            cached_text_pixels: self
+                .texts
+                .values()
+                .map(|cached| (cached.text.size.x * cached.text.size.y) as u64)
+                .sum(),
+            loaded_fonts: self.fonts.iter().filter(|font| font.loaded).count(),
+            ..self.stats
+        }
+    }
+}
+
+#[test]
+fn unused_text_textures_and_fonts_are_evicted() {
+    use crate::{Context, Error, Game, System, TextAlign, V2};
+
+    struct Hud {
+        font: Id,
+        /// Rendered once, drawn every frame.
+        title: Text,
+        /// Counts up while the timer is shown.
+        timer: Option<u32>,
+    }
+
+    struct HudSystem;
+    impl System for HudSystem {
+        fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+            let Hud { font, title, timer } = *ctx.resource::<Hud>();
+            ctx.draw_text(title, V2::new(0.0, 0.0), TextAlign::Left)?;
+            let Some(timer) = timer else {
+                return Ok(());
+            };
+            ctx.resource::<Hud>().timer = Some(timer + 1);
+            let label = ctx.render_text(font, "time", (255, 255, 255))?;
+            let time = ctx.render_text(font, timer.to_string(), (255, 255, 255))?;
+            ctx.draw_text(label, V2::new(0.0, 40.0), TextAlign::Left)?;
+            ctx.draw_text(time, V2::new(100.0, 40.0), TextAlign::Left)
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!("font_{}.ttf", std::process::id()));
+    std::fs::write(&path, []).unwrap();
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.set_text_cache_limits(TextCacheLimits {
+        max_text_age: 10,
+        max_texts: 5,
+        max_font_age: 20,
+    });
+    let font = ctx.load_font(&path, 16).unwrap();
+    let title = ctx.render_text(font, "title", (255, 255, 255)).unwrap();
+    ctx.insert_resource(Hud {
+        font,
+        title,
+        timer: Some(0),
+    });
+    ctx.add_system(|_| HudSystem);
+
+    for _ in 0..30 {
+        game.step();
+    }
+    let mut ctx = game.context();
+    let stats = ctx.text_cache_stats();
+    assert_eq!(stats.cached_texts, 5);
+    assert_eq!(stats.misses, 32);
+    assert_eq!(stats.hits, 29);
+    assert_eq!(stats.evicted_texts, 27);
+    assert_eq!(stats.loaded_fonts, 1);
+    assert_eq!(ctx.texture_size(title.texture).unwrap(), (40, 16));
+    let kept = ctx.render_text(font, "kept", (255, 255, 255)).unwrap();
+
+    ctx.resource::<Hud>().timer = None;
+    for _ in 0..30 {
+        game.step();
+    }
+    let mut ctx = game.context();
+    let stats = ctx.text_cache_stats();
+    assert_eq!(stats.cached_texts, 1);
+    let err = ctx
+        .draw_text(kept, V2::new(0.0, 0.0), TextAlign::Left)
+        .unwrap_err();
+    assert!(err.to_string().contains("evicted"), "{err}");
+    assert_eq!((stats.loaded_fonts, stats.evicted_fonts), (0, 1));
+    assert!(ctx.text_size(font, "x").is_ok());
+    assert_eq!(ctx.load_font(&path, 16).unwrap(), font);
+    assert_eq!(ctx.text_cache_stats().loaded_fonts, 1);
+    std::fs::remove_file(path).unwrap();
+}
@@ -1,7 +1,8 @@
 use super::id::Id;
+use crate::TextStyle;
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Texture(pub Id);
 
 #[derive(Clone, Hash, PartialEq, Eq)]
-pub struct TextTextureKey(pub Id, pub String, pub (u8, u8, u8));
+pub struct TextTextureKey(pub Id, pub String, pub TextStyle);
@@ -1,4 +1,5 @@
 use super::{Dom, EventId, InternalNodeId, NodeId};
+use crate::TextAlign;
 use std::{
     boxed::Box as InnerBox,
     ops::{Deref, DerefMut},
@@ -90,6 +91,8 @@ pub struct Node {
     border_color: Option<(u8, u8, u8)>,
     padding: Option<i32>,
     font_size: Option<u16>,
+    text_align: Option<TextAlign>,
+    wrap: bool,
     visible: bool,
     gap: Option<i32>,
 }
@@ -108,6 +111,8 @@ impl Node {
             border_color: None,
             padding: None,
             font_size: None,
+            text_align: None,
+            wrap: false,
             gap: None,
             visible: true,
         })
@@ -180,6 +185,8 @@ impl Node {
                 border_thickness: self.border_thickness.map(f64::from),
                 padding: self.padding.map(f64::from),
                 font_size: self.font_size.or(derived_props.font_size),
+                text_align: self.text_align.unwrap_or_default(),
+                wrap: self.wrap,
                 visible: self.visible,
                 focused: false,
                 focus_color: (50, 160, 190),
@@ -209,6 +216,13 @@ impl Box<Node> {
     make_with_function!(padding, padding, i32);
     make_with_function!(gap, gap, i32);
     make_with_function!(font_size, font_size, u16);
+    make_with_function!(text_align, text_align, TextAlign);
+
+    /// Wraps text to the width of the node, if it has one.
+    pub fn wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
 
     pub fn visible(mut self, visible: bool) -> Self {
         self.visible = visible;
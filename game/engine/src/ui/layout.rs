@@ -123,12 +123,17 @@ impl LayoutTreeLeaf<'_> {
                 let font_size = self.inner.font_size.unwrap_or(15);
                 let font_id = ctx.load_font(font, font_size).unwrap();
                 let text = ctx
-                    .render_text(font_id, text, self.inner.color.unwrap_or((255, 255, 255)))
+                    .render_text_styled(font_id, text, &self.inner.text_style())
                     .unwrap();
                 let offset =
                     self.inner.padding.unwrap_or(0.0) + self.inner.border_thickness.unwrap_or(0.0);
-                ctx.draw_texture(text.texture, self.pos + V2::new(offset, offset))
-                    .unwrap();
+                let content_width = self.size.x - offset * 2.0;
+                let align_offset = self.inner.text_align.offset(content_width, text.size.x);
+                ctx.draw_texture(
+                    text.texture,
+                    self.pos + V2::new(offset + align_offset, offset),
+                )
+                .unwrap();
             }
             Kind::Image(src) => {
                 let texture = ctx.load_texture(src).unwrap();
@@ -287,8 +292,10 @@ impl CanCreateLayoutTree for Node {
             Kind::Text { text, font } => {
                 let font_size = self.font_size.unwrap_or(15);
                 let font_id = ctx.load_font(font, font_size).unwrap();
-                let size = ctx.text_size(font_id, text).unwrap();
-                let size = V2::new(f64::from(size.0), f64::from(size.1));
+                let size = ctx
+                    .render_text_styled(font_id, text, &self.text_style())
+                    .unwrap()
+                    .size;
                 let leaf = build_leaf(self, node_id, pos_transformer, parent_pos, size);
                 LayoutTree::single(leaf)
             }
@@ -419,6 +426,8 @@ fn troller_no_trolling_min() {
                     gap: None,
                     border_color: None,
                     font_size: None,
+                    text_align: crate::TextAlign::Left,
+                    wrap: false,
                     visible: true,
                     focused: false,
                     focus_color,
@@ -444,6 +453,8 @@ fn troller_no_trolling_min() {
                         gap: None,
                         border_color: None,
                         font_size: None,
+                        text_align: crate::TextAlign::Left,
+                        wrap: false,
                         visible: true,
                         focused: false,
                         focus_color,
@@ -468,6 +479,8 @@ fn troller_no_trolling_min() {
                         gap: None,
                         border_color: None,
                         font_size: None,
+                        text_align: crate::TextAlign::Left,
+                        wrap: false,
                         visible: true,
                         focused: false,
                         focus_color,
@@ -6,7 +6,7 @@ pub mod focus;
 pub mod id_offset;
 pub mod utils;
 
-use crate::{Context, V2};
+use crate::{Context, TextAlign, TextStyle, V2};
 pub use builder::constructors;
 
 pub type BoxedNode = builder::Box<builder::Node>;
@@ -70,6 +70,8 @@ pub struct Node {
     gap: Option<f64>,
     border_color: Option<(u8, u8, u8)>,
     font_size: Option<u16>,
+    text_align: TextAlign,
+    wrap: bool,
     visible: bool,
     focused: bool,
     focus_thickness: f64,
@@ -103,6 +105,16 @@ impl Node {
         self.visible = visible;
     }
 
+    fn text_style(&self) -> TextStyle {
+        let style = TextStyle::new()
+            .color(self.color.unwrap_or((255, 255, 255)))
+            .align(self.text_align);
+        match self.width.filter(|_| self.wrap) {
+            Some(width) => style.max_width(width as u32),
+            None => style,
+        }
+    }
+
     pub fn children<'dom>(&self, dom: &'dom Dom) -> Option<Vec<&'dom Node>> {
         match &self.kind {
             Kind::Vert(children) | Kind::Hori(children) | Kind::Stack(children) => {
@@ -265,7 +277,6 @@ impl Dom {
 
     pub fn update(&mut self, ctx: &mut Context) {
         let tree = self.build_layout_tree(ctx);
-        tree.draw(ctx);
         if ctx.mouse_button_just_pressed(crate::MouseButton::Left) {
             let mouse_position = ctx.mouse_position();
             let mouse_position = V2::new(f64::from(mouse_position.0), f64::from(mouse_position.1));
@@ -275,4 +286,8 @@ impl Dom {
         }
         self.handle_events(ctx);
     }
+
+    pub fn draw(&self, ctx: &mut Context) {
+        self.build_layout_tree(ctx).draw(ctx);
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{DrawTextureOpts, V2};
+use crate::{DrawTextureOpts, TextStyle, V2};
 
 pub trait UiContext {
     fn draw_rect(&mut self, rgb: (u8, u8, u8), pos: V2, size: V2) -> Result<(), crate::Error>;
@@ -9,11 +9,11 @@ pub trait UiContext {
     where
         P: AsRef<std::path::Path>;
 
-    fn render_text<S: Into<String>>(
+    fn render_text_styled<S: Into<String>>(
         &mut self,
         font_id: crate::Id,
         text: S,
-        rgb: (u8, u8, u8),
+        style: &TextStyle,
     ) -> Result<crate::Text, crate::Error>;
 
     fn load_texture<P>(&mut self, path: P) -> Result<crate::Texture, crate::Error>
@@ -28,12 +28,6 @@ pub trait UiContext {
         pos: V2,
         size: V2,
     ) -> Result<(), crate::Error>;
-
-    fn text_size<S: AsRef<str>>(
-        &mut self,
-        font_id: crate::Id,
-        text: S,
-    ) -> Result<(u32, u32), crate::Error>;
 }
 
 impl UiContext for crate::Context<'_, '_> {
@@ -58,13 +52,13 @@ impl UiContext for crate::Context<'_, '_> {
         self.load_font(path, size)
     }
 
-    fn render_text<S: Into<String>>(
+    fn render_text_styled<S: Into<String>>(
         &mut self,
         font_id: crate::Id,
         text: S,
-        rgb: (u8, u8, u8),
+        style: &TextStyle,
     ) -> Result<crate::Text, crate::Error> {
-        self.render_text(font_id, text, rgb)
+        self.render_text_styled(font_id, text, style)
     }
 
     fn load_texture<P>(&mut self, path: P) -> Result<crate::Texture, crate::Error>
@@ -86,14 +80,6 @@ impl UiContext for crate::Context<'_, '_> {
     ) -> Result<(), crate::Error> {
         self.draw_texture(texture, pos, DrawTextureOpts::new().size(size))
     }
-
-    fn text_size<S: AsRef<str>>(
-        &mut self,
-        font_id: crate::Id,
-        text: S,
-    ) -> Result<(u32, u32), crate::Error> {
-        self.text_size(font_id, text)
-    }
 }
 
 pub struct MockContext;
@@ -115,11 +101,11 @@ impl UiContext for MockContext {
         unreachable!()
     }
 
-    fn render_text<S: Into<String>>(
+    fn render_text_styled<S: Into<String>>(
         &mut self,
         font_id: crate::Id,
         text: S,
-        rgb: (u8, u8, u8),
+        style: &TextStyle,
     ) -> Result<crate::Text, crate::Error> {
         unreachable!()
     }
@@ -143,12 +129,4 @@ impl UiContext for MockContext {
     ) -> Result<(), crate::Error> {
         unreachable!()
     }
-
-    fn text_size<S: AsRef<str>>(
-        &mut self,
-        font_id: crate::Id,
-        text: S,
-    ) -> Result<(u32, u32), crate::Error> {
-        unreachable!()
-    }
 }
@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    hash::Hash,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+
+use engine::{Context, Error, Id, Texture, V2};
+use serde::Deserialize;
+
+use crate::stage::{StageData, STAGES_DIR};
+
+pub const MANIFEST_PATH: &str = "assets/manifest.json";
+
+/// Single images, by the name they have in the manifest.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureAsset {
+    MainMenu,
+    Placeholder,
+    StatsLeft,
+    StatsLeftOutline,
+    StatsRight,
+    StatsRightOutline,
+}
+
+impl TextureAsset {
+    const ALL: [Self; 6] = [
+        Self::MainMenu,
+        Self::Placeholder,
+        Self::StatsLeft,
+        Self::StatsLeftOutline,
+        Self::StatsRight,
+        Self::StatsRightOutline,
+    ];
+}
+
+/// Animations stored as one image per frame.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SequenceAsset {
+    AttackUp,
+    AttackDown,
+    AttackLeft,
+    AttackRight,
+    Death,
+}
+
+impl SequenceAsset {
+    const ALL: [Self; 5] = [
+        Self::AttackUp,
+        Self::AttackDown,
+        Self::AttackLeft,
+        Self::AttackRight,
+        Self::Death,
+    ];
+}
+
+/// Sprite sheets of equally sized frames.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AtlasAsset {
+    Centrist,
+    Strong,
+    Speed,
+    Tankie,
+}
+
+impl AtlasAsset {
+    const ALL: [Self; 4] = [Self::Centrist, Self::Strong, Self::Speed, Self::Tankie];
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FontAsset {
+    Jaro,
+}
+
+impl FontAsset {
+    const ALL: [Self; 1] = [Self::Jaro];
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SoundAsset {
+    Click,
+    Explosion,
+    Swoosh,
+    MenuTheme,
+    HeroTheme,
+}
+
+impl SoundAsset {
+    const ALL: [Self; 5] = [
+        Self::Click,
+        Self::Explosion,
+        Self::Swoosh,
+        Self::MenuTheme,
+        Self::HeroTheme,
+    ];
+}
+
+#[derive(Deserialize, Clone)]
+pub struct AtlasEntry {
+    pub texture: String,
+    pub frame_width: u32,
+    pub frame_height: u32,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct FontEntry {
+    pub path: String,
+    /// Every size the font is drawn at, so each can be rendered ahead of time.
+    pub sizes: Vec<u16>,
+}
+
+/// Every asset the game uses, by logical name, as listed in [`MANIFEST_PATH`].
+#[derive(Deserialize, Clone)]
+pub struct Manifest {
+    pub textures: HashMap<TextureAsset, String>,
+    pub sequences: HashMap<SequenceAsset, Vec<String>>,
+    pub atlases: HashMap<AtlasAsset, AtlasEntry>,
+    pub fonts: HashMap<FontAsset, FontEntry>,
+    pub sounds: HashMap<SoundAsset, String>,
+}
+
+fn check_listed<K: Eq + Hash + std::fmt::Debug, V>(
+    kind: &str,
+    all: &[K],
+    listed: &HashMap<K, V>,
+) -> Result<(), Error> {
+    match all.iter().find(|asset| !listed.contains_key(asset)) {
+        Some(asset) => Err(format!("no {kind} named {asset:?}").into()),
+        None => Ok(()),
+    }
+}
+
+impl Manifest {
+    /// Unknown names fail to parse, and every name the game uses has to be listed.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let manifest: Self =
+            serde_json::from_str(&json).map_err(|err| format!("{}: {err}", path.display()))?;
+        check_listed("texture", &TextureAsset::ALL, &manifest.textures)
+            .and_then(|_| check_listed("sequence", &SequenceAsset::ALL, &manifest.sequences))
+            .and_then(|_| check_listed("atlas", &AtlasAsset::ALL, &manifest.atlases))
+            .and_then(|_| check_listed("font", &FontAsset::ALL, &manifest.fonts))
+            .and_then(|_| check_listed("sound", &SoundAsset::ALL, &manifest.sounds))
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(manifest)
+    }
+
+    /// Loading split into small steps, so a loading screen can be drawn in between.
+    pub fn steps(&self) -> Vec<LoadStep> {
+        let textures = self
+            .textures
+            .iter()
+            .map(|(asset, path)| LoadStep::Texture(*asset, path.clone()));
+        let sequences = self
+            .sequences
+            .iter()
+            .map(|(asset, paths)| LoadStep::Sequence(*asset, paths.clone()));
+        let atlases = self
+            .atlases
+            .iter()
+            .map(|(asset, entry)| LoadStep::Atlas(*asset, entry.clone()));
+        let fonts = self
+            .fonts
+            .iter()
+            .map(|(asset, entry)| LoadStep::Font(*asset, entry.clone()));
+        let sounds = self
+            .sounds
+            .iter()
+            .map(|(asset, path)| LoadStep::Sound(*asset, path.clone()));
+        textures
+            .chain(sequences)
+            .chain(atlases)
+            .chain(fonts)
+            .chain(sounds)
+            .chain([LoadStep::Stages])
+            .collect()
+    }
+}
+
+#[derive(Clone)]
+pub enum LoadStep {
+    Texture(TextureAsset, String),
+    Sequence(SequenceAsset, Vec<String>),
+    Atlas(AtlasAsset, AtlasEntry),
+    Font(FontAsset, FontEntry),
+    Sound(SoundAsset, String),
+    /// Stages aren't in the manifest, but their textures and music are checked all the same.
+    Stages,
+}
+
+fn load_texture(ctx: &mut Context, path: &str) -> Result<Texture, Error> {
+    ctx.load_texture(path)
+        .map_err(|err| format!("{path}: {err}").into())
+}
+
+/// Sounds are played on the sound thread, so they are only decoded here to check them.
+fn check_sound(path: &str) -> Result<(), Error> {
+    let file = File::open(path).map_err(|err| format!("{path}: {err}"))?;
+    rodio::Decoder::new(BufReader::new(file)).map_err(|err| format!("{path}: {err}"))?;
+    Ok(())
+}
+
+impl LoadStep {
+    pub fn run(self, ctx: &mut Context, assets: &mut Assets) -> Result<(), Error> {
+        match self {
+            LoadStep::Texture(asset, path) => {
+                let texture = load_texture(ctx, &path)?;
+                assets.textures.insert(asset, (texture, path));
+            }
+            LoadStep::Sequence(asset, paths) => {
+                if paths.is_empty() {
+                    return Err(format!("sequence {asset:?} has no frames").into());
+                }
+                let textures = paths
+                    .iter()
+                    .map(|path| load_texture(ctx, path))
+                    .collect::<Result<_, _>>()?;
+                assets.sequences.insert(asset, textures);
+            }
+            LoadStep::Atlas(asset, entry) => {
+                let texture = load_texture(ctx, &entry.texture)?;
+                assets.atlases.insert(
+                    asset,
+                    Atlas {
+                        texture,
+                        frame_size: V2::new(entry.frame_width.into(), entry.frame_height.into()),
+                        path: entry.texture,
+                    },
+                );
+            }
+            LoadStep::Font(asset, entry) => {
+                for size in entry.sizes {
+                    let font = ctx
+                        .load_font(&entry.path, size)
+                        .map_err(|err| format!("{}: {err}", entry.path))?;
+                    assets.fonts.insert((asset, size), font);
+                }
+            }
+            LoadStep::Sound(asset, path) => {
+                check_sound(&path)?;
+                assets.sounds.insert(asset, Sound(PathBuf::from(path)));
+            }
+            LoadStep::Stages => {
+                for stage in StageData::load_all(STAGES_DIR)? {
+                    for background in &stage.backgrounds {
+                        load_texture(ctx, &background.texture)?;
+                    }
+                    check_sound(&stage.music)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A sprite sheet of equally sized frames.
+#[derive(Clone)]
+pub struct Atlas {
+    pub texture: Texture,
+    pub frame_size: V2,
+    pub path: String,
+}
+
+/// A sound file which has been checked to exist and decode, played through the sound player.
+#[derive(Clone, Debug)]
+pub struct Sound(PathBuf);
+
+impl AsRef<Path> for Sound {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+/// Every asset in the manifest, loaded by the loading screen and inserted as a resource.
+/// Looking up an asset can't fail, as loading makes sure every one of them is there.
+#[derive(Default, Clone)]
+pub struct Assets {
+    textures: HashMap<TextureAsset, (Texture, String)>,
+    sequences: HashMap<SequenceAsset, Vec<Texture>>,
+    atlases: HashMap<AtlasAsset, Atlas>,
+    fonts: HashMap<(FontAsset, u16), Id>,
+    sounds: HashMap<SoundAsset, Sound>,
+}
+
+impl Assets {
+    pub fn texture(&self, asset: TextureAsset) -> Texture {
+        self.textures[&asset].0
+    }
+
+    /// For ui images, which are given by path.
+    pub fn texture_path(&self, asset: TextureAsset) -> &str {
+        &self.textures[&asset].1
+    }
+
+    pub fn sequence(&self, asset: SequenceAsset) -> Vec<Texture> {
+        self.sequences[&asset].clone()
+    }
+
+    pub fn atlas(&self, asset: AtlasAsset) -> &Atlas {
+        &self.atlases[&asset]
+    }
+
+    /// Panics if the size isn't listed for the font in the manifest.
+    pub fn font(&self, asset: FontAsset, size: u16) -> Id {
+        *self
+            .fonts
+            .get(&(asset, size))
+            .unwrap_or_else(|| panic!("font {asset:?} isn't loaded at size {size}"))
+    }
+
+    pub fn sound(&self, asset: SoundAsset) -> &Sound {
+        &self.sounds[&asset]
+    }
+}
+
+#[test]
+fn shipped_manifest_loads() {
+    let manifest = Manifest::load(MANIFEST_PATH).unwrap();
+    let mut game = engine::Game::new_headless();
+    let mut ctx = game.context();
+    let mut assets = Assets::default();
+    for step in manifest.steps() {
+        step.run(&mut ctx, &mut assets).unwrap();
+    }
+    assert_eq!(assets.sequence(SequenceAsset::Death).len(), 7);
+    assert_eq!(
+        assets.atlas(AtlasAsset::Tankie).frame_size,
+        V2::new(32.0, 32.0)
+    );
+
+    let unknown = r#"{"textures": {"main_manu": "assets/main_menu.png"}}"#;
+    assert!(serde_json::from_str::<Manifest>(unknown).is_err());
+}
@@ -0,0 +1,105 @@
+use engine::{
+    camera::{Camera, CameraSystem},
+    clamp, query,
+    rigid_body::RigidBody,
+    Context, Error, Schedule, Stage, System, V2,
+};
+
+#[cfg(test)]
+use crate::stage::BlastZone;
+use crate::{
+    events::{HitLanded, PlayerKnockedOut},
+    player::Player,
+};
+#[cfg(test)]
+use engine::LOGICAL_SIZE;
+
+/// Space kept around the fighters, so they aren't drawn at the very edge of the screen.
+const FRAMING_MARGIN: V2 = V2 { x: 500.0, y: 350.0 };
+const MAX_ZOOM: f64 = 1.6;
+/// Hits dealing at least this much damage shake the screen.
+const HEAVY_HIT_DAMAGE: f64 = 10.0;
+
+/// Frames every player, and shakes the camera on heavy hits and knockouts.
+pub struct CameraFollowSystem(pub u64);
+impl System for CameraFollowSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay).before::<CameraSystem>()
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        let hits = ctx.read_events::<HitLanded>();
+        let knockouts = ctx.read_events::<PlayerKnockedOut>();
+
+        let bodies = query!(ctx, RigidBody, Player)
+            .map(|(_, body, _)| body.clone())
+            .collect::<Vec<_>>();
+
+        let camera = ctx.resource::<Camera>();
+        if let Some((target, zoom)) = framing(&bodies, camera) {
+            camera.follow(target, zoom);
+        }
+        for hit in hits {
+            if hit.damage >= HEAVY_HIT_DAMAGE {
+                camera.shake(hit.damage * 0.8, 0.2);
+            }
+        }
+        if !knockouts.is_empty() {
+            camera.shake(16.0, 0.4);
+        }
+        Ok(())
+    }
+}
+
+/// The center and zoom which fit every body on screen.
+fn framing(bodies: &[RigidBody], camera: &Camera) -> Option<(V2, f64)> {
+    let first = bodies.first()?;
+    let (min, max) = bodies
+        .iter()
+        .fold((first.pos, first.pos + first.size), |(min, max), body| {
+            let end = body.pos + body.size;
+            (
+                V2::new(min.x.min(body.pos.x), min.y.min(body.pos.y)),
+                V2::new(max.x.max(end.x), max.y.max(end.y)),
+            )
+        });
+    let center = (min + max).extend(0.5);
+    let framed = max - min + FRAMING_MARGIN;
+    let screen = camera.screen_size();
+    let zoom = clamp(
+        (screen.x / framed.x).min(screen.y / framed.y),
+        0.0,
+        MAX_ZOOM,
+    );
+    Some((center, zoom))
+}
+
+#[test]
+fn fighters_further_apart_than_the_screen_zoom_the_camera_out() {
+    let blast_zone = BlastZone {
+        left: -250.0,
+        top: -250.0,
+        right: 1530.0,
+        bottom: 920.0,
+    };
+    let mut camera = Camera::new(LOGICAL_SIZE).with_bounds(blast_zone.pos(), blast_zone.size());
+    let bodies = [
+        RigidBody::new().with_pos(V2::new(-200.0, 300.0)),
+        RigidBody::new().with_pos(V2::new(1450.0, 300.0)),
+    ];
+    assert!(bodies[1].pos.x - bodies[0].pos.x > LOGICAL_SIZE.x);
+
+    let (target, zoom) = framing(&bodies, &camera).unwrap();
+    assert!(zoom < 1.0, "zoom {zoom}");
+    camera.follow(target, zoom);
+    camera.snap();
+    assert!(camera.zoom() < 1.0, "zoom {}", camera.zoom());
+    for body in &bodies {
+        let on_screen = camera.world_to_screen(body.pos);
+        assert!(
+            (0.0..=LOGICAL_SIZE.x).contains(&on_screen.x)
+                && (0.0..=LOGICAL_SIZE.y).contains(&on_screen.y),
+            "{on_screen:?} is off screen"
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use engine::{
+    capture::{self, FrameCaptured, FrameRecorder},
+    Context, Error, Keycode, Schedule, Stage, System,
+};
+
+use crate::settings::{HighlightFormat, Settings};
+
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+
+/// Seconds of play kept in memory for highlights.
+const HIGHLIGHT_SECONDS: f64 = 8.0;
+const HIGHLIGHT_FPS: f64 = 15.0;
+/// Highlights are kept at a quarter of the window's resolution, which is plenty for a gif.
+const HIGHLIGHT_DOWNSCALE: u32 = 4;
+
+pub fn highlight_recorder() -> FrameRecorder {
+    FrameRecorder::new(HIGHLIGHT_SECONDS, HIGHLIGHT_FPS).downscale(HIGHLIGHT_DOWNSCALE)
+}
+
+/// Milliseconds since the epoch, so captures never overwrite each other.
+fn timestamp() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis())
+        .unwrap_or_default()
+}
+
+/// Encodes on another thread, as encoding a few seconds of frames would stall the game.
+fn save_in_background<F>(save: F)
+where
+    F: FnOnce() -> Result<PathBuf, Error> + Send + 'static,
+{
+    std::thread::spawn(move || match save() {
+        Ok(path) => println!("saved {}", path.display()),
+        Err(err) => println!("could not save capture: {err}"),
+    });
+}
+
+fn create_screenshots_dir() -> Result<&'static Path, Error> {
+    let dir = Path::new(SCREENSHOTS_DIR);
+    std::fs::create_dir_all(dir).map_err(|err| format!("{}: {err}", dir.display()))?;
+    Ok(dir)
+}
+
+/// Saves a png screenshot with F12 and, if highlights are recorded,
+/// exports the last seconds of play with F10 in the format picked in [`Settings`].
+/// Added outside of any state, so it works in menus and during matches alike.
+pub struct CaptureSystem(pub u64);
+impl System for CaptureSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Input)
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        if ctx.key_just_pressed(Keycode::F12) {
+            ctx.capture_frame();
+        }
+        for FrameCaptured(image) in ctx.read_events::<FrameCaptured>() {
+            save_in_background(move || {
+                let path =
+                    create_screenshots_dir()?.join(format!("screenshot_{}.png", timestamp()));
+                image.save_png(&path)?;
+                Ok(path)
+            });
+        }
+
+        if !ctx.key_just_pressed(Keycode::F10) {
+            return Ok(());
+        }
+        let Some(recorder) = ctx.recorder() else {
+            return Ok(());
+        };
+        let frames = recorder.frames().cloned().collect::<Vec<_>>();
+        let fps = recorder.fps();
+        let format = ctx.resource::<Settings>().highlight_format;
+        save_in_background(move || {
+            let name = format!("highlight_{}", timestamp());
+            let path = create_screenshots_dir()?.join(name);
+            match format {
+                HighlightFormat::Gif => {
+                    let path = path.with_extension("gif");
+                    capture::save_gif(&frames, fps, &path)?;
+                    Ok(path)
+                }
+                HighlightFormat::PngSequence => {
+                    capture::save_png_sequence(&frames, &path)?;
+                    Ok(path)
+                }
+            }
+        });
+        Ok(())
+    }
+}
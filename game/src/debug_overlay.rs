@@ -0,0 +1,51 @@
+use engine::{Context, Error, Keycode, Schedule, Stage, System, TextAlign, TextStyle, V2};
+
+use crate::assets::{Assets, FontAsset};
+
+/// Whether the debug overlay is shown, toggled with F3.
+#[derive(Default)]
+pub struct DebugOverlay {
+    shown: bool,
+}
+
+/// Shows the text cache counters in development mode, to spot text textures piling up
+/// during long sessions. Added outside of any state, like [`crate::hot_reload::HotReloadSystem`].
+pub struct DebugOverlaySystem(pub u64);
+impl System for DebugOverlaySystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        if ctx.key_just_pressed(Keycode::F3) {
+            let overlay = ctx.resource::<DebugOverlay>();
+            overlay.shown = !overlay.shown;
+        }
+        Ok(())
+    }
+
+    fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+        if !ctx.resource::<DebugOverlay>().shown {
+            return Ok(());
+        }
+        // fonts are loaded along with everything else on the loading screen
+        let Some(assets) = ctx.try_resource::<Assets>() else {
+            return Ok(());
+        };
+        let font = assets.font(FontAsset::Jaro, 24);
+        let stats = ctx.text_cache_stats();
+        let lines = format!(
+            "texts: {} ({} kpx)\nfonts: {}\nhits: {} misses: {}\nevicted texts: {} fonts: {}",
+            stats.cached_texts,
+            stats.cached_text_pixels / 1000,
+            stats.loaded_fonts,
+            stats.hits,
+            stats.misses,
+            stats.evicted_texts,
+            stats.evicted_fonts,
+        );
+        let style = TextStyle::new().color((255, 255, 0)).line_spacing(2);
+        let text = ctx.render_text_styled(font, lines, &style)?;
+        ctx.draw_text(text, V2::new(8.0, 8.0), TextAlign::Left)
+    }
+}
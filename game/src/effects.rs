@@ -0,0 +1,55 @@
+use std::f64::consts::{PI, TAU};
+
+use engine::{particles::ParticleEmitter, rigid_body::RigidBody, spawn, Context, RenderLayer, V2};
+
+/// Launches faster than this leave a trail behind the victim.
+pub const TRAIL_KNOCKBACK: f64 = 800.0;
+
+/// Sparks flying every which way from where a hit landed.
+pub fn hit_sparks() -> ParticleEmitter {
+    ParticleEmitter::new()
+        .burst(14)
+        .lifetime(0.3)
+        .speed(150.0, 450.0)
+        .gravity(900.0)
+        .size(V2::new(6.0, 6.0))
+        .color((255, 240, 150), (255, 110, 30))
+        .alpha(1.0, 0.0)
+        .despawn_when_done()
+}
+
+/// Dust kicked up to both sides when a player lands.
+pub fn landing_dust() -> ParticleEmitter {
+    ParticleEmitter::new()
+        .burst(10)
+        .lifetime(0.4)
+        .direction(-PI / 2.0, PI)
+        .speed(40.0, 140.0)
+        .gravity(200.0)
+        .size(V2::new(8.0, 8.0))
+        .color((210, 200, 180), (150, 140, 120))
+        .alpha(0.8, 0.0)
+        .despawn_when_done()
+}
+
+/// A streak left behind a player launched by a heavy hit, attached to the player.
+pub fn launch_trail() -> ParticleEmitter {
+    ParticleEmitter::new()
+        .rate(80.0)
+        .duration(0.6)
+        .lifetime(0.35)
+        .direction(0.0, TAU)
+        .speed(0.0, 30.0)
+        .size(V2::new(10.0, 10.0))
+        .color((255, 255, 255), (120, 160, 255))
+        .alpha(0.7, 0.0)
+        // behind the player it follows
+        .layer(RenderLayer::World)
+        .depth(-1)
+}
+
+/// Spawns an emitter centered on `pos`, which despawns once its particles die
+/// if made with [`ParticleEmitter::despawn_when_done`].
+pub fn spawn_effect(ctx: &mut Context, pos: V2, emitter: ParticleEmitter) {
+    spawn!(ctx, RigidBody::new().with_pos(pos), emitter);
+}
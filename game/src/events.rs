@@ -0,0 +1,33 @@
+use engine::{Entity, V2};
+
+use crate::player::PlayerKind;
+
+/// A player left the stage and lost a life.
+#[derive(Clone)]
+pub struct PlayerKnockedOut {
+    pub player: Entity,
+    pub kind: PlayerKind,
+    pub pos: V2,
+    pub size: V2,
+}
+
+/// A hurtbox hit a player.
+#[derive(Clone)]
+pub struct HitLanded {
+    pub attacker: Option<Entity>,
+    pub victim: Entity,
+    pub damage: f64,
+}
+
+/// A player swung an attack.
+#[derive(Clone)]
+pub struct AttackStarted {
+    pub player: Entity,
+}
+
+/// A player lost their last life.
+#[derive(Clone)]
+pub struct MatchEnded {
+    pub winner: shared::Hero,
+    pub loser: shared::Hero,
+}
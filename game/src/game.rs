@@ -1,51 +1,38 @@
 use engine::{
-    collision::{resolve_position_default, CollisionResolver, DefaultResolver, ShallowCollider},
+    animation::{Animation, AnimationSystem},
+    camera::{Camera, CameraSystem},
+    collision::{
+        resolve_position_default, CollisionResolver, DefaultResolver, ShallowCollider,
+        TriggerSystem,
+    },
+    particles::ParticleSystem,
     physics::QuadDirection,
-    query, query_one,
+    query,
     rigid_body::{DragSystem, GravitySystem, RigidBody, VelocitySystem},
-    spawn, CollisionSystem, Component, IdAccumulator, SharedPtr, SolidCollider, System, V2,
+    spawn, CollisionSystem, Schedule, SolidCollider, Stage, System, LOGICAL_SIZE, V2,
 };
 
 use crate::{
-    hud::{player_damage_color, HudSystem},
+    assets::{Assets, Atlas},
+    camera::CameraFollowSystem,
+    hero_info::HeroInfo,
+    hot_reload::{HotReload, StageReloadSystem},
+    hud::HudSystem,
     hurtbox::{Hitbox, Hurtbox, HurtboxSystem, Victim},
     keyset::Keyset,
     knockoff::{DeathAnimationSystem, KnockoffSystem},
+    layers::{PLAYERS, PLAYER_MASK},
     pause::PauseSystem,
     player::{Player, PlayerKind},
+    player_animation::{PlayerAnimation, PlayerAnimationState, PlayerAnimationSystem},
     player_interaction::{PlayerInteraction, PlayerInteractionSystem},
-    server::Server,
-    sound_player::SoundPlayer,
+    server::{notify_server_about_player_colors, ServerReportSystem},
+    sound_player::SoundEffectSystem,
     sprite_renderer::{Sprite, SpriteRenderer},
-    timer::Timer,
+    stage::StageData,
 };
 
-#[derive(Component, Clone)]
-pub struct Game {
-    pub system_id: engine::Id,
-    pub child_systems: Vec<engine::Id>,
-    pub child_components: Vec<engine::Id>,
-    pub board_colors_timer: SharedPtr<Timer>,
-    pub paused: bool,
-}
-
-impl Game {
-    pub fn new(
-        system_id: engine::Id,
-        child_systems: Vec<engine::Id>,
-        child_components: Vec<engine::Id>,
-    ) -> Self {
-        Self {
-            system_id,
-            child_systems,
-            child_components,
-            board_colors_timer: Timer::new(1.0).into(),
-            paused: false,
-        }
-    }
-}
-
-#[derive(Component, Clone)]
+#[derive(Clone)]
 pub struct HeroesOnBoard {
     pub hero_1: shared::Hero,
     pub hero_2: shared::Hero,
@@ -54,134 +41,66 @@ pub struct HeroesOnBoard {
 pub struct GameSystem(pub u64);
 impl System for GameSystem {
     fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        let mut systems = IdAccumulator::new();
-        systems += ctx.add_system(CollisionSystem);
-        systems += ctx.add_system(VelocitySystem);
-        systems += ctx.add_system(SpriteRenderer);
-        systems += ctx.add_system(GravitySystem);
-        systems += ctx.add_system(DragSystem);
-        systems += ctx.add_system(HurtboxSystem);
-        systems += ctx.add_system(KnockoffSystem);
-        systems += ctx.add_system(PlayerInteractionSystem);
-        systems += ctx.add_system(HudSystem);
-        systems += ctx.add_system(DeathAnimationSystem);
-        // systems += ctx.add_system(DebugDrawer);
+        ctx.add_system(CollisionSystem);
+        ctx.add_system(VelocitySystem);
+        ctx.add_system(TriggerSystem);
+        ctx.add_system(SpriteRenderer);
+        ctx.add_system(GravitySystem);
+        ctx.add_system(DragSystem);
+        ctx.add_system(HurtboxSystem);
+        ctx.add_system(KnockoffSystem);
+        ctx.add_system(PlayerInteractionSystem);
+        ctx.add_system(HudSystem);
+        ctx.add_system(DeathAnimationSystem);
+        ctx.add_system(SoundEffectSystem);
+        ctx.add_system(ServerReportSystem);
+        ctx.add_system(CameraSystem);
+        ctx.add_system(CameraFollowSystem);
+        ctx.add_system(AnimationSystem);
+        ctx.add_system(PlayerAnimationSystem);
+        ctx.add_system(ParticleSystem);
+        if ctx.try_resource::<HotReload>().is_some() {
+            ctx.add_system(StageReloadSystem);
+        }
+        // ctx.add_system(DebugDrawer);
 
-        let background = ctx.load_texture("assets/map_1.png").unwrap();
+        let stage = ctx.resource::<StageData>().clone();
+        // fighters knocked off the stage stay in view until they leave the blast zone
+        ctx.insert_resource(
+            Camera::new(LOGICAL_SIZE).with_bounds(stage.blast_zone.pos(), stage.blast_zone.size()),
+        );
+        stage.spawn(ctx)?;
 
         notify_server_about_player_colors(ctx);
 
-        let mut children = IdAccumulator::new();
-        children += spawn!(
-            ctx,
-            Sprite::new(background).layer(2),
-            RigidBody::new().with_size(V2::new(1280.0, 720.0)),
-        );
-        children += self.spawn_player(ctx, V2::new(400.0, 350.0), Keyset::Wasd, PlayerKind::Left);
-        children += self.spawn_player(
+        let [left_spawn, right_spawn] = stage.spawn_points;
+        self.spawn_player(ctx, left_spawn.into(), Keyset::Wasd, PlayerKind::Left);
+        self.spawn_player(
             ctx,
-            V2::new(600.0, 350.0),
+            right_spawn.into(),
             Keyset::ArrowKeys,
             PlayerKind::Right,
         );
 
-        children += spawn!(
-            ctx,
-            RigidBody::new()
-                .with_pos(V2::new(350.0, 525.0))
-                .with_size(V2::new(676.0, 110.0)),
-            SolidCollider::new(),
-        );
-
-        children += spawn!(
-            ctx,
-            RigidBody::new()
-                .with_pos(V2::new(126.0, 162.0))
-                .with_size(V2::new(180.0, 204.0)),
-            SolidCollider::new(),
-        );
-
-        children += spawn!(
-            ctx,
-            RigidBody::new()
-                .with_pos(V2::new(720.0, 214.0))
-                .with_size(V2::new(248.0, 10.0)),
-            ShallowCollider::new().with_direction(QuadDirection::Top),
-        );
-        children += spawn!(
-            ctx,
-            RigidBody::new()
-                .with_pos(V2::new(924.0, 378.0))
-                .with_size(V2::new(280.0, 10.0)),
-            ShallowCollider::new().with_direction(QuadDirection::Top),
-        );
-
-        let sound_player = ctx.select_one::<SoundPlayer>();
-        sound_player.play_music("assets/sounds/theme_2.ogg");
-
-        spawn!(ctx, Game::new(self.0, systems.finish(), children.finish()));
-
         Ok(())
     }
 
-    fn on_update(&self, ctx: &mut engine::Context, delta: f64) -> Result<(), engine::Error> {
-        let game = ctx.clone_one::<Game>();
-
-        if game.paused {
-            return Ok(());
-        }
-
-        game.board_colors_timer.lock().update(delta);
-        if game.board_colors_timer.lock().done() {
-            notify_server_about_player_colors(ctx);
-            game.board_colors_timer.lock().reset()
-        }
-
+    fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
         if ctx.key_just_pressed(engine::Keycode::Escape) {
-            // Open pause menu
-            ctx.add_system(PauseSystem);
-
-            let game = ctx.select_one::<Game>();
-            game.paused = true;
+            ctx.push_overlay(PauseSystem);
         }
 
         Ok(())
     }
 
     fn on_remove(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        let game_id = query_one!(ctx, Game);
-        let game = ctx.clone_one::<Game>();
-        ctx.despawn(game_id);
-        for id in game.child_systems {
-            ctx.remove_system(id);
-        }
-        for id in game.child_components {
-            ctx.despawn(id);
-        }
-        let heroes_on_board = query_one!(ctx, HeroesOnBoard);
-        ctx.despawn(heroes_on_board);
+        ctx.remove_resource::<HeroesOnBoard>();
+        ctx.remove_resource::<Camera>();
+        ctx.remove_resource::<StageData>();
         Ok(())
     }
 }
 
-fn notify_server_about_player_colors(ctx: &mut engine::Context) {
-    let mut hero_1_color = (255, 255, 255);
-    let mut hero_2_color = (255, 255, 255);
-    for player_id in query!(ctx, Player).clone() {
-        let player = ctx.select::<Player>(player_id).clone();
-        match player.kind {
-            PlayerKind::Left => hero_1_color = player_damage_color(player.damage_taken),
-            PlayerKind::Right => hero_2_color = player_damage_color(player.damage_taken),
-        }
-    }
-    let board_colors = shared::UpdateBoardColorsParams {
-        hero_1_color,
-        hero_2_color,
-    };
-    let server = ctx.select_one::<Server>();
-    server.update_board_colors(board_colors);
-}
-
 struct BouncingCollider;
 impl CollisionResolver for BouncingCollider {
     fn resolve(&self, body: &mut RigidBody, pos: V2, size: V2, dir: QuadDirection) {
@@ -216,17 +135,21 @@ impl GameSystem {
         pos: V2,
         keyset: Keyset,
         kind: PlayerKind,
-    ) -> engine::Id {
+    ) -> engine::Entity {
         let scale = 1.0;
         let pixel_ratio = 4.0;
 
         let hero = self.player_hero(ctx, &kind);
-        let texture = self.hero_texture(ctx, &hero.kind);
+        let atlas = self.hero_atlas(ctx, &hero.kind);
+        let animation = PlayerAnimation::from_sheet(ctx, atlas.texture, atlas.frame_size).unwrap();
+        let idle = animation.clip(PlayerAnimationState::Idle);
 
         let factor = scale * pixel_ratio;
         spawn!(
             ctx,
-            Sprite::new(texture).layer(1),
+            Sprite::new(atlas.texture),
+            Animation::new(idle),
+            animation,
             Hitbox {
                 size: V2::new(24.0 * factor, 28.0 * factor),
                 offset: V2::new(4.0 * factor, 2.0 * factor)
@@ -236,7 +159,10 @@ impl GameSystem {
                 .with_size(V2::new(32.0 * factor, 32.0 * factor))
                 .with_gravity()
                 .with_drag(),
-            SolidCollider::new().resolving(BouncingCollider),
+            SolidCollider::new()
+                .resolving(BouncingCollider)
+                .layers(PLAYERS)
+                .mask(PLAYER_MASK),
             //.resolving(DefaultResolver),
             Player {
                 kind,
@@ -250,45 +176,49 @@ impl GameSystem {
     }
 
     fn player_hero(&self, ctx: &mut engine::Context, kind: &PlayerKind) -> shared::Hero {
-        let heroes = ctx.clone_one::<HeroesOnBoard>();
+        let heroes = ctx.resource::<HeroesOnBoard>().clone();
         match kind {
             PlayerKind::Left => heroes.hero_1,
             PlayerKind::Right => heroes.hero_2,
         }
     }
 
-    fn hero_texture(&self, ctx: &mut engine::Context, kind: &shared::HeroKind) -> engine::Texture {
-        let path = crate::hero_info::HeroInfo::from(kind).texture_path;
-        ctx.load_texture(path).unwrap()
+    fn hero_atlas(&self, ctx: &mut engine::Context, kind: &shared::HeroKind) -> Atlas {
+        let atlas = HeroInfo::from(kind).atlas;
+        ctx.resource::<Assets>().atlas(atlas).clone()
     }
 }
 
 struct DebugDrawer(pub u64);
 
 impl System for DebugDrawer {
-    fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
-        for id in query!(ctx, RigidBody, SolidCollider) {
-            let body = ctx.select::<RigidBody>(id).clone();
-            self.draw_outline(ctx, body.pos, body.size, 2.0, (0, 125, 255))?;
-        }
-        for id in query!(ctx, RigidBody, ShallowCollider) {
-            let body = ctx.select::<RigidBody>(id).clone();
-            self.draw_outline(ctx, body.pos, body.size, 2.0, (0, 125, 255))?;
-        }
-        for id in query!(ctx, RigidBody, Hurtbox) {
-            let body = ctx.select::<RigidBody>(id).clone();
-            self.draw_outline(ctx, body.pos, body.size, 2.0, (255, 0, 0))?;
-        }
-        for id in query!(ctx, RigidBody, Hitbox) {
-            let body = ctx.select::<RigidBody>(id).clone();
-            let hitbox = ctx.select::<Hitbox>(id).clone();
-            self.draw_outline(
-                ctx,
-                body.pos + hitbox.offset,
-                hitbox.size,
-                2.0,
-                (0, 255, 125),
-            )?;
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Render)
+            .after::<SpriteRenderer>()
+            .render_layer(engine::RenderLayer::Effects)
+    }
+
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let colliders = query!(ctx, RigidBody, SolidCollider)
+            .map(|(_, body, _)| (body.pos, body.size, (0, 125, 255)))
+            .chain(
+                query!(ctx, RigidBody, ShallowCollider)
+                    .map(|(_, body, _)| (body.pos, body.size, (0, 125, 255))),
+            );
+        let hurtboxes =
+            query!(ctx, RigidBody, Hurtbox).map(|(_, body, _)| (body.pos, body.size, (255, 0, 0)));
+        let hitboxes = query!(ctx, RigidBody, Hitbox)
+            .map(|(_, body, hitbox)| (body.pos + hitbox.offset, hitbox.size, (0, 255, 125)));
+        let outlines = colliders
+            .chain(hurtboxes)
+            .chain(hitboxes)
+            .collect::<Vec<_>>();
+        for (pos, size, rgb) in outlines {
+            self.draw_outline(ctx, pos, size, 2.0, rgb)?;
         }
         Ok(())
     }
@@ -303,6 +233,10 @@ impl DebugDrawer {
         width: f64,
         color: (u8, u8, u8),
     ) -> Result<(), engine::Error> {
+        let (pos, size) = match ctx.try_resource::<Camera>() {
+            Some(camera) => (camera.world_to_screen(pos), camera.scale(size)),
+            None => (pos, size),
+        };
         ctx.draw_rect(
             color,
             pos.x as i32,
@@ -1,3 +1,4 @@
+use crate::assets::{Assets, SoundAsset, TextureAsset};
 use crate::hero_info::HeroInfo;
 use crate::main_menu::MainMenuSystem;
 use crate::server::Board;
@@ -7,14 +8,14 @@ use crate::server::Server;
 use crate::sound_player::SoundPlayer;
 use crate::ui_components::Button;
 use crate::ui_components::ProgressBar;
-use engine::query_one;
 use engine::spawn;
 use engine::ui::{
     self,
     utils::{change_image_node_content, change_text_node_content},
 };
 use engine::SharedPtr;
-use engine::{Component, System};
+use engine::{Component, Schedule, Stage, System};
+use engine::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
 
 #[derive(Component, Clone)]
 pub struct HeroCreator {
@@ -72,21 +73,27 @@ impl From<Event> for ui::EventId {
 
 pub struct HeroCreatorSystem(pub u64);
 impl System for HeroCreatorSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
     fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
         let strength_bar = ProgressBar::new("Strength", 24);
         let agility_bar = ProgressBar::new("Agility", 24);
         let defence_bar = ProgressBar::new("Defence", 24);
 
-        let mut dom = self.build_dom(&strength_bar, &agility_bar, &defence_bar);
+        let placeholder = ctx
+            .resource::<Assets>()
+            .texture_path(TextureAsset::Placeholder)
+            .to_string();
+        let mut dom = self.build_dom(&strength_bar, &agility_bar, &defence_bar, placeholder);
 
         strength_bar.add_event_handlers(&mut dom);
         agility_bar.add_event_handlers(&mut dom);
         defence_bar.add_event_handlers(&mut dom);
 
-        let id = self.0;
-        dom.add_event_handler(Event::ClosePopup, move |_dom, ctx, _node_id| {
-            ctx.remove_system(id);
-            ctx.add_system(MainMenuSystem);
+        dom.add_event_handler(Event::ClosePopup, |_dom, ctx, _node_id| {
+            ctx.replace_state(MainMenuSystem);
         });
 
         dom.add_event_handler(Event::UpdateHero, move |_dom, ctx, _node_id| {
@@ -105,13 +112,12 @@ impl System for HeroCreatorSystem {
                 defence: menu.defence_bar.lock().steps_filled(),
             };
 
-            let server = ctx.select_one::<Server>();
+            let server = ctx.resource::<Server>();
             server.update_hero_stats(shared::UpdateHeroStatsParams { rfid, stats });
         });
 
-        dom.add_event_handler(Event::Back, move |_dom, ctx, _node_id| {
-            ctx.remove_system(id);
-            ctx.add_system(MainMenuSystem);
+        dom.add_event_handler(Event::Back, |_dom, ctx, _node_id| {
+            ctx.replace_state(MainMenuSystem);
         });
 
         use shared::HeroKind::{Centrist, Speed, Strong, Tankie};
@@ -132,7 +138,7 @@ impl System for HeroCreatorSystem {
                     HeroResult::UnknownRfid(rfid) => rfid,
                 };
 
-                let server = ctx.select_one::<Server>();
+                let server = ctx.resource::<Server>();
                 server.create_hero(shared::CreateHeroParams {
                     rfid,
                     hero_type: hero_type.clone(),
@@ -141,8 +147,12 @@ impl System for HeroCreatorSystem {
             });
         }
 
-        let sound_player = ctx.select_one::<SoundPlayer>();
-        sound_player.play_music("assets/sounds/theme_3.ogg");
+        let theme = ctx
+            .resource::<Assets>()
+            .sound(SoundAsset::HeroTheme)
+            .clone();
+        let sound_player = ctx.resource::<SoundPlayer>();
+        sound_player.play_music(theme);
 
         spawn!(
             ctx,
@@ -205,9 +215,13 @@ impl System for HeroCreatorSystem {
         Ok(())
     }
 
-    fn on_remove(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        let id = query_one!(ctx, HeroCreator);
-        ctx.despawn(id);
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let menu = ctx.clone_one::<HeroCreator>();
+        menu.dom.lock().draw(ctx);
         Ok(())
     }
 }
@@ -218,6 +232,7 @@ impl HeroCreatorSystem {
         strength_bar: &ProgressBar,
         agility_bar: &ProgressBar,
         defence_bar: &ProgressBar,
+        placeholder: String,
     ) -> ui::Dom {
         use ui::constructors::{Hori, Image, Rect, Stack, Text, Vert};
         ui::Dom::new(
@@ -225,18 +240,18 @@ impl HeroCreatorSystem {
                 Hori([
                     Vert([
                         Vert([
-                            Image("./assets/placeholder.png")
+                            Image(placeholder)
                                 .id(Node::HeroImage)
                                 .width(128)
                                 .height(128),
                             Text("? ? ?").id(Node::HeroTypeText).padding(30),
-                            Rect().height(720 / 16),
+                            Rect().height(LOGICAL_HEIGHT as i32 / 16),
                         ])
                         .padding(50)
                         .border_thickness(2),
-                        Rect().height(720 / 4),
+                        Rect().height(LOGICAL_HEIGHT as i32 / 4),
                     ]),
-                    Rect().width(1280 / 4),
+                    Rect().width(LOGICAL_WIDTH as i32 / 4),
                     Vert([
                         Text("Available points: 0").id(Node::AvailablePoints),
                         strength_bar.build(),
@@ -249,7 +264,7 @@ impl HeroCreatorSystem {
                             Button("Back").on_click(Event::Back).id(Node::Back),
                         ])
                         .gap(4),
-                        Rect().height(720 / 2 - 100),
+                        Rect().height(LOGICAL_HEIGHT as i32 / 2 - 100),
                     ]),
                 ]),
                 Vert([
@@ -296,14 +311,14 @@ impl HeroCreatorSystem {
                 .border_thickness(2)
                 .padding(5),
                 Text("Loading...")
-                    .width(1280)
-                    .height(720)
+                    .width(LOGICAL_WIDTH as i32)
+                    .height(LOGICAL_HEIGHT as i32)
                     .background_color((50, 50, 50))
                     .id(Node::Loading),
             ])
             .font_size(20)
-            .width(1280)
-            .height(720)
+            .width(LOGICAL_WIDTH as i32)
+            .height(LOGICAL_HEIGHT as i32)
             .background_color((50, 50, 50)),
         )
     }
@@ -317,7 +332,7 @@ impl HeroCreatorSystem {
         let responder = match menu.board_responder.clone() {
             Some(responder) => responder,
             None => {
-                let server = ctx.select_one::<Server>();
+                let server = ctx.resource::<Server>();
                 let responder = SharedPtr::new(server.board_status());
                 let menu = ctx.select_one::<HeroCreator>();
                 menu.board_responder = Some(responder.clone());
@@ -394,6 +409,9 @@ fn initialize_hero(
         .unwrap()
         .set_visible(false);
 
+    let hero_atlas = HeroInfo::from(&hero.kind).atlas;
+    let hero_image = ctx.resource::<Assets>().atlas(hero_atlas).path.clone();
+
     let menu = ctx.select_one::<HeroCreator>();
     let old_hero_info = &menu.hero;
     if let Some(old_hero) = old_hero_info {
@@ -423,6 +441,6 @@ fn initialize_hero(
         .set_steps_filled(hero.defence_points)
         .set_lower_limit(hero.defence_points);
     change_text_node_content(dom.select_mut(Node::HeroTypeText), hero_info.name);
-    change_image_node_content(dom.select_mut(Node::HeroImage), hero_info.texture_path);
+    change_image_node_content(dom.select_mut(Node::HeroImage), hero_image);
     menu.hero = Some(HeroResult::Hero(hero));
 }
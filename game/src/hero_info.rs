@@ -1,10 +1,10 @@
-use std::path::PathBuf;
-
 use shared::HeroKind;
 
+use crate::assets::AtlasAsset;
+
 pub struct HeroInfo {
     pub base_stats: shared::HeroStats,
-    pub texture_path: PathBuf,
+    pub atlas: AtlasAsset,
     pub name: String,
 }
 
@@ -25,15 +25,15 @@ impl From<&HeroKind> for HeroInfo {
             Tankie => "Tankie",
         }
         .to_string();
-        let texture_path = match value {
-            Centrist => PathBuf::from("./assets/heroes/centrist.png"),
-            Strong => PathBuf::from("./assets/heroes/strong.png"),
-            Speed => PathBuf::from("./assets/heroes/speed.png"),
-            Tankie => PathBuf::from("./assets/heroes/tankie.png"),
+        let atlas = match value {
+            Centrist => AtlasAsset::Centrist,
+            Strong => AtlasAsset::Strong,
+            Speed => AtlasAsset::Speed,
+            Tankie => AtlasAsset::Tankie,
         };
         Self {
             base_stats,
-            texture_path,
+            atlas,
             name,
         }
     }
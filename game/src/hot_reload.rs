@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use engine::{camera::Camera, query_entities, Context, Error, System};
+
+use crate::{
+    sound_player::SoundPlayer,
+    stage::{StageData, StagePiece, STAGES_DIR},
+    timer::Timer,
+};
+
+pub const ASSETS_DIR: &str = "assets";
+
+/// Seconds between looking for changed files.
+const POLL_INTERVAL: f64 = 0.5;
+
+/// Finds files in a directory which changed since it was last polled,
+/// by comparing modification times.
+pub struct FileWatcher {
+    dir: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+    /// Files already in the directory aren't reported as changed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        let mut watcher = Self {
+            dir: dir.as_ref().to_path_buf(),
+            modified: HashMap::new(),
+        };
+        watcher.poll();
+        watcher
+    }
+
+    /// Files which were added or modified since the last poll, sorted by path.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        collect_files(&self.dir, &mut files);
+        let mut changed = files
+            .into_iter()
+            .filter_map(|path| {
+                let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+                let previous = self.modified.insert(path.clone(), modified);
+                (previous != Some(modified)).then_some(path)
+            })
+            .collect::<Vec<_>>();
+        changed.sort();
+        changed
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Inserted as a resource in development mode, enabling [`HotReloadSystem`].
+pub struct HotReload {
+    watcher: FileWatcher,
+    timer: Timer,
+}
+
+impl HotReload {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            watcher: FileWatcher::new(dir),
+            timer: Timer::new(POLL_INTERVAL),
+        }
+    }
+}
+
+/// A stage file which changed during a match, rebuilt by [`StageReloadSystem`].
+pub struct PendingStageReload(pub PathBuf);
+
+/// Watches the assets directory in development mode. Changed textures are reloaded
+/// in place, changed sounds are swapped and changed stages are rebuilt.
+/// Added outside of any state, so assets can be edited from any screen.
+pub struct HotReloadSystem(pub u64);
+impl System for HotReloadSystem {
+    fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
+        let Some(hot_reload) = ctx.try_resource::<HotReload>() else {
+            return Ok(());
+        };
+        hot_reload.timer.update(delta);
+        if !hot_reload.timer.done() {
+            return Ok(());
+        }
+        hot_reload.timer.reset();
+        let changed = hot_reload.watcher.poll();
+
+        for path in changed {
+            match path.extension().and_then(|ext| ext.to_str()) {
+                Some("png") => match ctx.reload_texture(&path) {
+                    Ok(true) => println!("reloaded {}", path.display()),
+                    Ok(false) => {}
+                    // likely saved halfway, the next save is picked up again
+                    Err(err) => println!("could not reload {}: {err}", path.display()),
+                },
+                Some("ogg") => {
+                    ctx.resource::<SoundPlayer>().reload(&path);
+                    println!("reloaded {}", path.display());
+                }
+                Some("json") if path.starts_with(STAGES_DIR) => {
+                    ctx.insert_resource(PendingStageReload(path));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Rebuilds the backgrounds and platforms of the stage being played when its file changes.
+/// Stages are otherwise read when picked, so only the running match needs rebuilding.
+pub struct StageReloadSystem(pub u64);
+impl System for StageReloadSystem {
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        let Some(PendingStageReload(path)) = ctx.remove_resource::<PendingStageReload>() else {
+            return Ok(());
+        };
+        let stage = match StageData::load(&path) {
+            Ok(stage) => stage,
+            Err(err) => {
+                println!("could not reload stage: {err}");
+                return Ok(());
+            }
+        };
+        if ctx
+            .try_resource::<StageData>()
+            .is_none_or(|current| current.id != stage.id)
+        {
+            return Ok(());
+        }
+        for id in query_entities!(ctx, StagePiece) {
+            ctx.despawn(id);
+        }
+        stage.spawn_layout(ctx)?;
+        if let Some(camera) = ctx.try_resource::<Camera>() {
+            *camera = camera
+                .clone()
+                .with_bounds(stage.blast_zone.pos(), stage.blast_zone.size());
+        }
+        ctx.insert_resource(stage);
+        println!("reloaded {}", path.display());
+        Ok(())
+    }
+}
+
+#[test]
+fn file_watcher_reports_added_and_modified_files() {
+    use std::time::Duration;
+
+    let dir = std::env::temp_dir().join(format!("file_watcher_{}", std::process::id()));
+    let nested = dir.join("stages");
+    std::fs::create_dir_all(&nested).unwrap();
+    let texture = dir.join("texture.png");
+    std::fs::write(&texture, "a").unwrap();
+
+    let mut watcher = FileWatcher::new(&dir);
+    assert!(watcher.poll().is_empty(), "existing files aren't changes");
+
+    let stage = nested.join("stage.json");
+    std::fs::write(&stage, "{}").unwrap();
+    assert_eq!(watcher.poll(), vec![stage.clone()]);
+
+    let later = SystemTime::now() + Duration::from_secs(10);
+    std::fs::File::options()
+        .write(true)
+        .open(&texture)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert_eq!(watcher.poll(), vec![texture]);
+    assert!(watcher.poll().is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
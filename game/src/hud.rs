@@ -1,50 +1,76 @@
-use engine::{query, Component, Context, DrawTextureOpts, Error, System, V2};
+use engine::{
+    query, query_entities, spawn, Component, Context, DrawTextureOpts, Error, RenderLayer,
+    Schedule, Stage, System, TextAlign, TextStyle, LOGICAL_SIZE, V2,
+};
 use shared::HeroKind;
 
 use crate::{
-    game::Game,
+    events::MatchEnded,
     main_menu::{MainMenuSystem},
+    assets::{Assets, FontAsset, TextureAsset},
+    hero_info::HeroInfo,
     player::{Player, PlayerKind},
-    FONT,
 };
 
 pub struct HudSystem(pub u64);
 
 impl System for HudSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui).render_layer(RenderLayer::Hud)
+    }
+
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
-        for id in query!(ctx, Player).clone() {
-            let player = ctx.select::<Player>(id).clone();
-            draw_hud(ctx, &player);
+        for MatchEnded { winner, loser } in ctx.read_events::<MatchEnded>() {
+            spawn!(ctx, TrashTalk::new(winner.kind, loser.kind));
+            spawn!(ctx, ReturnToMenu::new());
         }
-        for id in query!(ctx, TrashTalk).clone() {
-            let trash_talk = ctx.select::<TrashTalk>(id);
+        for (_, trash_talk) in ctx.components_mut::<TrashTalk>() {
             trash_talk.text_cycle_clock += delta;
-            let trash_talk = ctx.select::<TrashTalk>(id).clone();
-            trash_talk.draw(ctx);
         }
-        for id in query!(ctx, ReturnToMenu).clone() {
+        for id in query_entities!(ctx, ReturnToMenu) {
             let ret = ctx.select::<ReturnToMenu>(id).clone();
-            ret.draw(ctx);
             if ret.should_return(ctx) {
-                let system_id = ctx.select_one::<Game>().system_id;
-                ctx.remove_system(system_id);
-                ctx.add_system(MainMenuSystem);
+                ctx.replace_state(MainMenuSystem);
             }
         }
         Ok(())
     }
 
-    fn on_remove(&self, ctx: &mut Context) -> Result<(), Error> {
-        for id in query!(ctx, TrashTalk).clone() {
-            ctx.despawn(id);
+    fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+        // drawing needs the context, so the components are copied out first
+        let players = query!(ctx, Player)
+            .map(|(_, player)| player.clone())
+            .collect::<Vec<_>>();
+        for player in players {
+            draw_hud(ctx, &player);
         }
-        for id in query!(ctx, ReturnToMenu).clone() {
-            ctx.despawn(id);
+        let trash_talks = query!(ctx, TrashTalk)
+            .map(|(_, trash_talk)| trash_talk.clone())
+            .collect::<Vec<_>>();
+        for trash_talk in trash_talks {
+            trash_talk.draw(ctx);
+        }
+        let returns = query!(ctx, ReturnToMenu)
+            .map(|(_, ret)| ret.clone())
+            .collect::<Vec<_>>();
+        for ret in returns {
+            ret.draw(ctx);
         }
         Ok(())
     }
 }
 
+/// Trash talk longer than this wraps onto more lines.
+const TRASH_TALK_WIDTH: u32 = 900;
+
+/// Centered and outlined, so it reads over any stage.
+fn overlay_style() -> TextStyle {
+    TextStyle::new()
+        .align(TextAlign::Center)
+        .outline(3, (0, 0, 0))
+        .shadow((3, 3), (0, 0, 0))
+}
+
 #[derive(Clone, Component)]
 pub struct ReturnToMenu(engine::Keycode);
 
@@ -56,15 +82,13 @@ impl ReturnToMenu {
         ctx.key_just_pressed(self.0)
     }
     fn draw(&self, ctx: &mut Context) {
-        let font = ctx.load_font(FONT, 36).unwrap();
+        let font = ctx.resource::<Assets>().font(FontAsset::Jaro, 36);
         let text = format!("Press [{}] to return to menu!", self.0);
-        let text = ctx.render_text(font, text, (255, 255, 255)).unwrap();
-        ctx.draw_texture(
-            text.texture,
-            (V2::new(1280.0, 720.0) - text.size).div_comps(2.0),
-            DrawTextureOpts::new(),
-        )
-        .unwrap();
+        let text = ctx
+            .render_text_styled(font, text, &overlay_style())
+            .unwrap();
+        let pos = V2::new(LOGICAL_SIZE.x / 2.0, (LOGICAL_SIZE.y - text.size.y) / 2.0);
+        ctx.draw_text(text, pos, TextAlign::Center).unwrap();
     }
 }
 
@@ -86,23 +110,11 @@ impl TrashTalk {
 
     fn draw(&self, ctx: &mut Context) {
         let trash_talk = self.loser_text();
-        let font = ctx.load_font(FONT, 48).unwrap();
-        let text = ctx.render_text(font, &trash_talk, (255, 255, 255)).unwrap();
-        ctx.draw_rect_transparent(
-            (0, 0, 0),
-            ((1280.0 - text.size.x) / 2.0 - 10.0) as i32,
-            (100.0 - 5.0) as i32,
-            (text.size.x + 20.0) as u32,
-            (text.size.y + 10.0) as u32,
-            100,
-        )
-        .unwrap();
-        ctx.draw_texture(
-            text.texture,
-            V2::new((1280.0 - text.size.x) / 2.0, 100.0),
-            DrawTextureOpts::new(),
-        )
-        .unwrap();
+        let font = ctx.resource::<Assets>().font(FontAsset::Jaro, 48);
+        let style = overlay_style().max_width(TRASH_TALK_WIDTH);
+        let text = ctx.render_text_styled(font, &trash_talk, &style).unwrap();
+        let pos = V2::new(LOGICAL_SIZE.x / 2.0, 100.0);
+        ctx.draw_text(text, pos, TextAlign::Center).unwrap();
     }
 
     fn loser_text(&self) -> String {
@@ -165,16 +177,13 @@ fn draw_player_background(
     border_color: (u8, u8, u8),
     border_pos: V2,
 ) {
-    let border_path = match player.kind {
-        PlayerKind::Left => "assets/stats_left.png",
-        PlayerKind::Right => "assets/stats_right.png",
-    };
-    let border_outline_path = match player.kind {
-        PlayerKind::Left => "assets/stats_left_outline.png",
-        PlayerKind::Right => "assets/stats_right_outline.png",
+    let (border, border_outline) = match player.kind {
+        PlayerKind::Left => (TextureAsset::StatsLeft, TextureAsset::StatsLeftOutline),
+        PlayerKind::Right => (TextureAsset::StatsRight, TextureAsset::StatsRightOutline),
     };
-    let border = ctx.load_texture(border_path).unwrap();
-    let border_outline = ctx.load_texture(border_outline_path).unwrap();
+    let assets = ctx.resource::<Assets>();
+    let border = assets.texture(border);
+    let border_outline = assets.texture(border_outline);
 
     ctx.draw_texture(border, border_pos, DrawTextureOpts::new())
         .unwrap();
@@ -193,19 +202,20 @@ fn draw_player_stats(
     avatar_size: V2,
     text_pos: V2,
 ) {
-    let hero_sprite = {
-        let path = crate::hero_info::HeroInfo::from(&player.hero.kind).texture_path;
-        ctx.load_texture(path).unwrap()
-    };
-
-    let font = ctx.load_font(FONT, 24).unwrap();
+    let assets = ctx.resource::<Assets>();
+    let hero_atlas = assets
+        .atlas(HeroInfo::from(&player.hero.kind).atlas)
+        .clone();
+    let font = assets.font(FontAsset::Jaro, 24);
     let lives = player.lives.to_string();
     let lives = ctx.render_text(font, lives, (255, 255, 255)).unwrap();
 
     ctx.draw_texture(
-        hero_sprite,
+        hero_atlas.texture,
         avatar_pos,
-        DrawTextureOpts::new().size(avatar_size),
+        DrawTextureOpts::new()
+            .size(avatar_size)
+            .source(Some((V2::new(0.0, 0.0), hero_atlas.frame_size))),
     )
     .unwrap();
     ctx.draw_texture(lives.texture, text_pos, DrawTextureOpts::new())
@@ -218,7 +228,7 @@ fn draw_hud(ctx: &mut Context, player: &Player) {
 
     let border_pos = match player.kind {
         PlayerKind::Left => V2::new(8.0, 8.0),
-        PlayerKind::Right => V2::new(1280.0 - stats_size.x - 8.0, 8.0),
+        PlayerKind::Right => V2::new(LOGICAL_SIZE.x - stats_size.x - 8.0, 8.0),
     };
 
     draw_player_background(ctx, player, border_color, border_pos);
@@ -1,12 +1,15 @@
 use std::rc::Rc;
 
+use engine::broadphase::Broadphase;
+use engine::physics::Rect;
 use engine::rigid_body::RigidBody;
-use engine::{query, SolidCollider, Component, V2};
+use engine::{query_entities, SolidCollider, Component, V2};
 use engine::{Context, Error, System};
 
+use crate::effects::{hit_sparks, launch_trail, spawn_effect, TRAIL_KNOCKBACK};
+use crate::events::HitLanded;
 use crate::player::Player;
 use crate::player_interaction::{DodgeState, PlayerInteraction};
-use crate::sprite_renderer::Sprite;
 use crate::timer::Timer;
 
 #[derive(Default, Clone)]
@@ -18,6 +21,8 @@ pub enum HurtDirection {
     Right,
 }
 
+/// Where a player can be hit, relative to its body. Kept within the body, as hurtboxes
+/// find their victims through the [`Broadphase`] of bodies.
 #[derive(Component, Default, Clone)]
 pub struct Hitbox {
     pub size: V2,
@@ -42,104 +47,91 @@ pub trait HurtboxProfile {
 
 #[derive(Component, Clone)]
 pub struct Hurtbox {
-    pub owner: Option<engine::Id>,
+    pub owner: Option<engine::Entity>,
     pub timer: Timer,
-    pub textures: Vec<engine::Texture>,
     pub profile: Rc<dyn HurtboxProfile>,
 }
 
 #[derive(Component, Default, Clone)]
 pub struct Victim {
-    pub hurt_by: Vec<engine::Id>,
+    pub hurt_by: Vec<engine::Entity>,
     pub stunned: Option<f64>,
 }
 
-fn rects_collide(pos_a: V2, size_a: V2, pos_b: V2, size_b: V2) -> bool {
-    pos_a.x < pos_b.x + size_b.x
-        && pos_a.x + size_a.x > pos_b.x
-        && pos_a.y < pos_b.y + size_b.y
-        && pos_a.y + size_a.y > pos_b.y
-}
-
 pub struct HurtboxSystem(pub u64);
 impl System for HurtboxSystem {
     fn on_update(&self, ctx: &mut Context, delta: f64) -> Result<(), Error> {
-        for id in query!(ctx, Victim) {
-            let victim = ctx.select::<Victim>(id);
+        for (_, victim) in ctx.components_mut::<Victim>() {
             self.update_victim_stun_timer(victim, delta);
         }
         self.despawn_expired_hurtboxes(ctx, delta);
-        for hurtbox_id in query!(ctx, Hurtbox, RigidBody).clone() {
-            let hurtbox_body = ctx.select::<RigidBody>(hurtbox_id).clone();
-            let hurtbox = ctx.select::<Hurtbox>(hurtbox_id).clone();
-            for victim_id in query!(
-                ctx,
-                PlayerInteraction,
-                RigidBody,
-                SolidCollider,
-                Player,
-                Victim,
-                Hitbox
-            ) {
-                if hurtbox.owner.is_some_and(|owner| owner == victim_id) {
-                    continue;
-                };
-                let victim = ctx.select::<Victim>(victim_id);
-                if victim.hurt_by.iter().any(|i_id| *i_id == hurtbox_id) {
-                    continue;
-                }
-
-                let hitbox = ctx.select::<Hitbox>(victim_id).clone();
-                let victim_body = ctx.select::<RigidBody>(victim_id).clone();
-
-                if !rects_collide(
-                    hurtbox_body.pos,
-                    hurtbox_body.size,
-                    victim_body.pos + hitbox.offset,
-                    hitbox.size,
-                ) {
-                    continue;
-                };
-
-                let dodge_state = ctx
-                    .select::<PlayerInteraction>(victim_id)
-                    .clone()
-                    .dodge_state;
-
-                if matches!(dodge_state, DodgeState::Dodging(_)) {
-                    continue;
-                }
-
-                self.hurt_victim(hurtbox_id, &hurtbox, ctx, victim_id, &hurtbox_body);
+        for hurtbox_id in query_entities!(ctx, Hurtbox, RigidBody) {
+            for victim_id in self.victims_hit(ctx, hurtbox_id) {
+                self.hurt_victim(ctx, hurtbox_id, victim_id);
             }
         }
-        for id in query!(ctx, Hurtbox, Sprite).clone() {
-            let hurtbox = ctx.select::<Hurtbox>(id).clone();
-            let sprite = ctx.select::<Sprite>(id);
-            self.draw_hurtbox_animation(hurtbox, sprite);
-        }
         Ok(())
     }
 }
 
 impl HurtboxSystem {
+    /// Victims whose hitbox the hurtbox overlaps, and who it hasn't hurt yet.
+    /// Only victims near the hurtbox in the [`Broadphase`] kept by
+    /// [`CollisionSystem`](engine::CollisionSystem) are checked, if there is one.
+    fn victims_hit(&self, ctx: &mut Context, hurtbox_id: engine::Entity) -> Vec<engine::Entity> {
+        let Some(hurtbox_body) = ctx.get::<RigidBody>(hurtbox_id) else {
+            return Vec::new();
+        };
+        let hurtbox_rect = Rect::new(hurtbox_body.pos, hurtbox_body.size);
+        let candidates = match ctx.try_resource::<Broadphase>() {
+            Some(broadphase) => broadphase.query(hurtbox_rect),
+            None => query_entities!(ctx, Victim),
+        };
+        let Some(hurtbox) = ctx.get::<Hurtbox>(hurtbox_id) else {
+            return Vec::new();
+        };
+        candidates
+            .into_iter()
+            .filter(|&victim_id| hurtbox.owner != Some(victim_id))
+            .filter(|&victim_id| {
+                let (Some(victim), Some(body), Some(hitbox), Some(interaction)) = (
+                    ctx.get::<Victim>(victim_id),
+                    ctx.get::<RigidBody>(victim_id),
+                    ctx.get::<Hitbox>(victim_id),
+                    ctx.get::<PlayerInteraction>(victim_id),
+                ) else {
+                    return false;
+                };
+                ctx.get::<Player>(victim_id).is_some()
+                    && ctx.get::<SolidCollider>(victim_id).is_some()
+                    && !victim.hurt_by.contains(&hurtbox_id)
+                    && !matches!(interaction.dodge_state, DodgeState::Dodging(_))
+                    && hurtbox_rect.overlaps(Rect::new(body.pos + hitbox.offset, hitbox.size))
+            })
+            .collect()
+    }
+
     fn hurt_victim(
         &self,
-        hurtbox_id: u64,
-        hurtbox: &Hurtbox,
         ctx: &mut Context,
-        victim_id: u64,
-        hurtbox_body: &RigidBody,
+        hurtbox_id: engine::Entity,
+        victim_id: engine::Entity,
     ) {
-        let attacker = hurtbox.owner.map(|id| ctx.select::<Player>(id).clone());
+        let (Some(hurtbox), Some(hurtbox_body), Some(victim), Some(victim_body)) = (
+            ctx.get::<Hurtbox>(hurtbox_id),
+            ctx.get::<RigidBody>(hurtbox_id),
+            ctx.get::<Player>(victim_id),
+            ctx.get::<RigidBody>(victim_id),
+        ) else {
+            return;
+        };
+        // the attacker may have been knocked out while the hurtbox lingers
+        let attacker_id = hurtbox.owner;
+        let attacker = attacker_id.and_then(|id| ctx.get::<Player>(id));
 
-        let attacker_strength = attacker
-            .as_ref()
-            .map(|a| a.hero.strength_points)
-            .unwrap_or(0);
+        let attacker_strength = attacker.map(|a| a.hero.strength_points).unwrap_or(0);
 
-        let victim_body = ctx.select::<RigidBody>(victim_id).clone();
-        let victim = ctx.select::<Player>(victim_id);
+        let victim_center = victim_body.pos + victim_body.size.extend(0.5);
         let victim_defence = victim.hero.defence_points;
 
         let Outcome {
@@ -148,7 +140,7 @@ impl HurtboxSystem {
             stun_time,
         } = hurtbox
             .profile
-            .outcome(victim, attacker.as_ref(), hurtbox_body, &victim_body);
+            .outcome(victim, attacker, hurtbox_body, victim_body);
 
         let max_points = 24.0;
         let damage_multiplier = 1.0 + attacker_strength as f64 / (max_points * 2.0)
@@ -165,27 +157,30 @@ impl HurtboxSystem {
         let player = ctx.select::<Player>(victim_id);
 
         player.damage_taken += damage;
-    }
 
-    fn despawn_expired_hurtboxes(&self, ctx: &mut Context, delta: f64) {
-        for hurtbox_id in query!(ctx, Hurtbox) {
-            let hurtbox = ctx.select::<Hurtbox>(hurtbox_id);
-            hurtbox.timer.update(delta);
-            if hurtbox.timer.done() {
-                ctx.despawn(hurtbox_id);
-                continue;
-            }
+        spawn_effect(ctx, victim_center, hit_sparks());
+        if delta_vel.len() > TRAIL_KNOCKBACK {
+            ctx.insert(victim_id, launch_trail());
         }
+
+        ctx.send_event(HitLanded {
+            attacker: attacker_id,
+            victim: victim_id,
+            damage,
+        });
     }
 
-    fn draw_hurtbox_animation(&self, hurtbox: Hurtbox, sprite: &mut Sprite) {
-        let texture = hurtbox.textures[std::cmp::min(
-            ((hurtbox.timer.time_passed() / hurtbox.timer.duration())
-                * hurtbox.textures.len() as f64)
-                .floor() as usize,
-            hurtbox.textures.len(),
-        )];
-        sprite.texture = texture;
+    fn despawn_expired_hurtboxes(&self, ctx: &mut Context, delta: f64) {
+        let expired = ctx
+            .components_mut::<Hurtbox>()
+            .filter_map(|(hurtbox_id, hurtbox)| {
+                hurtbox.timer.update(delta);
+                hurtbox.timer.done().then_some(hurtbox_id)
+            })
+            .collect::<Vec<_>>();
+        for hurtbox_id in expired {
+            ctx.despawn(hurtbox_id);
+        }
     }
 
     fn update_victim_stun_timer(&self, victim: &mut Victim, delta: f64) {
@@ -197,3 +192,140 @@ impl HurtboxSystem {
         }
     }
 }
+
+#[test]
+fn headless_up_attack_damages_and_launches_victim() {
+    use crate::{attacks::UpAttackProfile, keyset::Keyset, player::PlayerKind};
+    use engine::spawn;
+
+    let hero = shared::Hero {
+        id: 0,
+        kind: shared::HeroKind::Centrist,
+        rfid: String::new(),
+        level: 0,
+        strength_points: 8,
+        agility_points: 8,
+        defence_points: 8,
+    };
+    let player = |kind| Player {
+        kind,
+        hero: hero.clone(),
+        damage_taken: 0.0,
+        lives: 3,
+    };
+
+    let mut game = engine::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(HurtboxSystem);
+    let attacker_id = spawn!(&mut ctx, player(PlayerKind::Left));
+    let victim_id = spawn!(
+        &mut ctx,
+        player(PlayerKind::Right),
+        PlayerInteraction::new(Keyset::ArrowKeys, 0.0),
+        RigidBody::new()
+            .with_pos(V2::new(100.0, 100.0))
+            .with_size(V2::new(128.0, 128.0)),
+        SolidCollider::new(),
+        Victim::default(),
+        Hitbox {
+            size: V2::new(96.0, 112.0),
+            offset: V2::new(16.0, 8.0),
+        },
+    );
+    spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(100.0, 80.0))
+            .with_size(V2::new(128.0, 64.0)),
+        Hurtbox {
+            owner: Some(attacker_id),
+            timer: Timer::new(0.3),
+            profile: Rc::new(UpAttackProfile),
+        },
+    );
+
+    game.step();
+
+    let mut ctx = game.context();
+    assert!(ctx.select::<Player>(victim_id).damage_taken > 0.0);
+    assert!(ctx.select::<RigidBody>(victim_id).vel.y < 0.0);
+    assert!(ctx.select::<Victim>(victim_id).stunned.is_some());
+    assert_eq!(
+        ctx.entities_with_component::<engine::particles::ParticleEmitter>()
+            .len(),
+        1,
+        "hit sparks fly"
+    );
+    let hits = ctx.read_events::<HitLanded>();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].attacker, Some(attacker_id));
+    assert_eq!(hits[0].victim, victim_id);
+}
+
+#[test]
+fn hurtboxes_found_through_the_collision_broadphase_only_hit_overlapped_hitboxes() {
+    use crate::{attacks::DownAttackProfile, keyset::Keyset, player::PlayerKind};
+    use engine::{spawn, CollisionSystem};
+
+    let player = || Player {
+        kind: PlayerKind::Right,
+        hero: shared::Hero {
+            id: 0,
+            kind: shared::HeroKind::Centrist,
+            rfid: String::new(),
+            level: 0,
+            strength_points: 8,
+            agility_points: 8,
+            defence_points: 8,
+        },
+        damage_taken: 0.0,
+        lives: 3,
+    };
+
+    let mut game = engine::Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(CollisionSystem);
+    ctx.add_system(HurtboxSystem);
+    let mut spawn_victim = |pos| {
+        spawn!(
+            &mut ctx,
+            player(),
+            PlayerInteraction::new(Keyset::ArrowKeys, 0.0),
+            RigidBody::new()
+                .with_pos(pos)
+                .with_size(V2::new(128.0, 128.0)),
+            SolidCollider::new(),
+            Victim::default(),
+            Hitbox {
+                size: V2::new(96.0, 112.0),
+                offset: V2::new(16.0, 8.0),
+            },
+        )
+    };
+    let hit_id = spawn_victim(V2::new(100.0, 100.0));
+    // the body touches the hurtbox, but the hitbox inside it doesn't
+    let grazed_id = spawn_victim(V2::new(228.0, 100.0));
+    let far_id = spawn_victim(V2::new(2000.0, 100.0));
+    spawn!(
+        &mut ctx,
+        RigidBody::new()
+            .with_pos(V2::new(100.0, 80.0))
+            .with_size(V2::new(128.0, 64.0)),
+        Hurtbox {
+            owner: None,
+            timer: Timer::new(0.3),
+            profile: Rc::new(DownAttackProfile),
+        },
+    );
+
+    game.step();
+
+    let mut ctx = game.context();
+    assert!(ctx.resource::<Broadphase>().len() >= 4);
+    assert!(ctx.select::<Player>(hit_id).damage_taken > 0.0);
+    assert_eq!(ctx.select::<Player>(grazed_id).damage_taken, 0.0);
+    assert_eq!(ctx.select::<Player>(far_id).damage_taken, 0.0);
+    let hits = ctx.read_events::<HitLanded>();
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].victim, hit_id);
+}
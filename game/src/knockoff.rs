@@ -1,109 +1,80 @@
 use engine::{
-    clamp, query, rigid_body::RigidBody, spawn, Component, Context, Error, System, Texture, V2,
+    animation::{Animation, Clip},
+    clamp,
+    particles::ParticleEmitter,
+    query,
+    rigid_body::RigidBody,
+    spawn, Component, Context, Error, RenderLayer, System, LOGICAL_SIZE, V2,
 };
-use shared::Hero;
 
 use crate::{
-    hud::{ReturnToMenu, TrashTalk},
+    assets::{Assets, SequenceAsset},
+    events::{MatchEnded, PlayerKnockedOut},
     player::Player,
     player_interaction::PlayerInteraction,
-    server::Server,
-    sound_player::SoundPlayer,
     sprite_renderer::Sprite,
-    timer::Timer,
+    stage::StageData,
 };
 
 pub struct KnockoffSystem(pub u64);
 impl System for KnockoffSystem {
     fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
-        let max_offset_from_screen = 200.0;
-        for id in query!(ctx, PlayerInteraction, RigidBody, Player).clone() {
-            let rigid_body = ctx.select::<RigidBody>(id).clone();
-            if body_outside_area(&rigid_body, max_offset_from_screen) {
+        let blast_zone = ctx.resource::<StageData>().blast_zone.clone();
+        let bodies = query!(ctx, PlayerInteraction, RigidBody, Player)
+            .map(|(id, _, body, _)| (id, body.clone()))
+            .collect::<Vec<_>>();
+        for (id, rigid_body) in bodies {
+            if !blast_zone.contains(&rigid_body) {
                 let loser_id = id;
                 let player = ctx.select::<Player>(loser_id);
                 if player.is_alive() {
                     player.damage_taken = 0.0;
                     player.lives -= 1;
-                    let player_pos = rigid_body.pos;
-                    let player_size = rigid_body.size;
-                    spawn_death_animation(ctx, player_pos, player_size);
-                    let sound_player = ctx.select_one::<SoundPlayer>();
-                    sound_player.set_effect_volume(0.5);
-                    sound_player.play_effect("assets/sounds/explosion.ogg");
+                    let kind = player.kind.clone();
+                    ctx.send_event(PlayerKnockedOut {
+                        player: loser_id,
+                        kind,
+                        pos: rigid_body.pos,
+                        size: rigid_body.size,
+                    });
                 };
                 let player = ctx.select::<Player>(loser_id);
                 let player_is_dead = player.is_dead();
                 if player_is_dead {
-                    let loser_hero = player.hero.clone();
-                    let loser_hero_kind = loser_hero.kind.clone();
+                    let loser = player.hero.clone();
                     ctx.despawn(loser_id);
-                    let winner = ctx.select_one::<Player>().clone();
-                    let winner_hero_kind = winner.hero.kind.clone();
-                    spawn!(ctx, TrashTalk::new(winner_hero_kind, loser_hero_kind));
-                    spawn!(ctx, ReturnToMenu::new());
-                    send_match_result(ctx, &winner.hero, &loser_hero);
+                    let winner = ctx.select_one::<Player>().hero.clone();
+                    ctx.send_event(MatchEnded { winner, loser });
                     continue;
                 }
                 let rigid_body = ctx.select::<RigidBody>(loser_id);
-                rigid_body.pos = V2::new((1280.0 - rigid_body.size.x) / 2.0, 100.0);
+                rigid_body.teleport(V2::new((LOGICAL_SIZE.x - rigid_body.size.x) / 2.0, 100.0));
                 rigid_body.vel = V2::new(0.0, 0.0);
+                if let Some(trail) = ctx.try_select::<ParticleEmitter>(loser_id) {
+                    trail.stop();
+                }
             }
         }
         Ok(())
     }
 }
 
-fn body_outside_area(rigid_body: &RigidBody, max_offset_from_screen: f64) -> bool {
-    rigid_body.pos.x + rigid_body.size.x < -max_offset_from_screen
-        || rigid_body.pos.x > 1280.0 + max_offset_from_screen
-        || rigid_body.pos.y + rigid_body.size.y < -max_offset_from_screen
-        || rigid_body.pos.y > 720.0 + max_offset_from_screen
-}
-
-fn send_match_result(ctx: &mut Context, winner: &Hero, loser: &Hero) {
-    let server = ctx.select_one::<Server>();
-    server.create_match(shared::CreateMatchParams {
-        winner_hero_id: winner.id,
-        loser_hero_id: loser.id,
-    });
-}
-
+/// Marks the splash left by a knocked out player, despawned once its [`Animation`] finishes.
 #[derive(Component)]
-pub struct DeathAnimation {
-    timer: Timer,
-    textures: Vec<Texture>,
-}
-
-impl DeathAnimation {
-    pub fn new(textures: Vec<Texture>) -> Self {
-        Self {
-            timer: Timer::new(0.5),
-            textures,
-        }
-    }
-}
+pub struct DeathAnimation;
 
 pub struct DeathAnimationSystem(pub u64);
 impl System for DeathAnimationSystem {
-    fn on_update(&self, ctx: &mut engine::Context, delta: f64) -> Result<(), engine::Error> {
-        for id in query!(ctx, Sprite, DeathAnimation) {
-            let animation = ctx.select::<DeathAnimation>(id);
-            animation.timer.update(delta);
-
-            if animation.timer.done() {
-                ctx.despawn(id);
-                continue;
-            }
-
-            let texture = animation.textures[std::cmp::min(
-                ((animation.timer.time_passed() / animation.timer.duration())
-                    * animation.textures.len() as f64)
-                    .floor() as usize,
-                animation.textures.len(),
-            )];
-            let sprite = ctx.select::<Sprite>(id);
-            sprite.texture = texture;
+    fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
+        for knocked_out in ctx.read_events::<PlayerKnockedOut>() {
+            spawn_death_animation(ctx, knocked_out.pos, knocked_out.size);
+        }
+        let finished = query!(ctx, Animation, DeathAnimation)
+            .filter(|(_, animation, _)| animation.finished())
+            .map(|(id, ..)| id)
+            .collect::<Vec<_>>();
+        for id in finished {
+            ctx.despawn(id);
         }
         Ok(())
     }
@@ -114,22 +85,12 @@ fn spawn_death_animation(ctx: &mut engine::Context, player_pos: V2, player_size:
 
     let size = V2::new(30.0, 60.0).extend(8.0);
 
-    let textures = [
-        "assets/death_0.png".to_string(),
-        "assets/death_1.png".to_string(),
-        "assets/death_2.png".to_string(),
-        "assets/death_3.png".to_string(),
-        "assets/death_4.png".to_string(),
-        "assets/death_5.png".to_string(),
-        "assets/death_6.png".to_string(),
-    ]
-    .into_iter()
-    .map(|path| ctx.load_texture(path).unwrap())
-    .collect::<Vec<_>>();
+    let textures = ctx.resource::<Assets>().sequence(SequenceAsset::Death);
+    let frame_duration = 0.5 / textures.len() as f64;
 
-    let a = 720.0 / 1280.0;
+    let a = LOGICAL_SIZE.y / LOGICAL_SIZE.x;
     let above_descending = player_pos.y > player_pos.x * a;
-    let above_ascending = player_pos.y > player_pos.x * -a + 720.0;
+    let above_ascending = player_pos.y > player_pos.x * -a + LOGICAL_SIZE.y;
 
     let dir = match (above_descending, above_ascending) {
         (true, true) => Bottom,
@@ -143,18 +104,21 @@ fn spawn_death_animation(ctx: &mut engine::Context, player_pos: V2, player_size:
 
     let pos = match dir {
         Top => V2::new(
-            clamp(player_pos.x, 0.0, 1280.0 - player_size.x) + size.x - comp_x,
+            clamp(player_pos.x, 0.0, LOGICAL_SIZE.x - player_size.x) + size.x - comp_x,
             size.y,
         ),
         Bottom => V2::new(
-            clamp(player_pos.x, 0.0, 1280.0 - player_size.x) - comp_x,
-            720.0 - size.y,
+            clamp(player_pos.x, 0.0, LOGICAL_SIZE.x - player_size.x) - comp_x,
+            LOGICAL_SIZE.y - size.y,
         ),
         Right => V2::new(
-            1280.0 - size.y,
-            clamp(player_pos.y, 0.0, 720.0 - size.x) + size.x - comp_y,
+            LOGICAL_SIZE.x - size.y,
+            clamp(player_pos.y, 0.0, LOGICAL_SIZE.y - size.x) + size.x - comp_y,
+        ),
+        Left => V2::new(
+            size.y,
+            clamp(player_pos.y, 0.0, LOGICAL_SIZE.y - size.x) - comp_y,
         ),
-        Left => V2::new(size.y, clamp(player_pos.y, 0.0, 720.0 - size.x) - comp_y),
     };
 
     let angle = match dir {
@@ -167,7 +131,10 @@ fn spawn_death_animation(ctx: &mut engine::Context, player_pos: V2, player_size:
     spawn!(
         ctx,
         RigidBody::new().with_pos(pos).with_size(size),
-        Sprite::new(textures[0]).angle(angle),
-        DeathAnimation::new(textures),
+        Sprite::new(textures[0])
+            .layer(RenderLayer::Effects)
+            .angle(angle),
+        Animation::new(Clip::from_textures(textures, frame_duration)),
+        DeathAnimation,
     );
 }
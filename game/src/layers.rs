@@ -0,0 +1,8 @@
+use engine::collision::CollisionLayers;
+
+/// Platforms and walls of the stage.
+pub const TERRAIN: CollisionLayers = CollisionLayers::layer(1);
+pub const PLAYERS: CollisionLayers = CollisionLayers::layer(2);
+
+/// Players stand on terrain but pass through each other.
+pub const PLAYER_MASK: CollisionLayers = CollisionLayers::ALL.without(PLAYERS);
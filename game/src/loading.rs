@@ -0,0 +1,121 @@
+use engine::{
+    query, query_entities, spawn, Component, Context, Error, Schedule, Stage, System,
+    LOGICAL_HEIGHT, LOGICAL_SIZE, LOGICAL_WIDTH,
+};
+#[cfg(test)]
+use engine::{query_one, Game};
+
+use crate::{
+    assets::{Assets, LoadStep, Manifest, MANIFEST_PATH},
+    main_menu::MainMenuSystem,
+};
+
+#[derive(Component, Clone)]
+pub struct Loading {
+    /// Read on the first update, where a failure is reported instead of panicking in `on_add`.
+    manifest_path: Option<&'static str>,
+    steps: Vec<LoadStep>,
+    total: usize,
+    assets: Assets,
+}
+
+/// Steps run per update, few enough that the progress bar keeps moving.
+const STEPS_PER_UPDATE: usize = 2;
+
+/// Loads every asset in the manifest before the main menu is shown,
+/// and quits with the reason if any of them are missing or broken.
+pub struct LoadingSystem(pub u64);
+impl System for LoadingSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
+    fn on_add(&self, ctx: &mut Context) -> Result<(), Error> {
+        spawn!(
+            ctx,
+            Loading {
+                manifest_path: Some(MANIFEST_PATH),
+                steps: Vec::new(),
+                total: 0,
+                assets: Assets::default(),
+            }
+        );
+        Ok(())
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        for id in query_entities!(ctx, Loading) {
+            if let Some(path) = ctx.select::<Loading>(id).manifest_path.take() {
+                let manifest = match Manifest::load(path) {
+                    Ok(manifest) => manifest,
+                    Err(err) => {
+                        ctx.quit();
+                        return Err(format!("could not load assets: {err}").into());
+                    }
+                };
+                let mut steps = manifest.steps();
+                // popped from the back
+                steps.reverse();
+                let loading = ctx.select::<Loading>(id);
+                loading.total = steps.len();
+                loading.steps = steps;
+            }
+            // taken out while the steps run, as they need the context
+            let loading = ctx.select::<Loading>(id);
+            let mut steps = std::mem::take(&mut loading.steps);
+            let mut assets = std::mem::take(&mut loading.assets);
+            for _ in 0..STEPS_PER_UPDATE {
+                let Some(step) = steps.pop() else {
+                    break;
+                };
+                if let Err(err) = step.run(ctx, &mut assets) {
+                    ctx.quit();
+                    return Err(format!("could not load assets: {err}").into());
+                }
+            }
+            if steps.is_empty() {
+                ctx.insert_resource(assets);
+                ctx.replace_state(MainMenuSystem);
+                return Ok(());
+            }
+            let loading = ctx.select::<Loading>(id);
+            loading.steps = steps;
+            loading.assets = assets;
+        }
+        Ok(())
+    }
+
+    fn on_render(&self, ctx: &mut Context, _interpolation: f64) -> Result<(), Error> {
+        let progresses = query!(ctx, Loading)
+            .filter(|(_, loading)| loading.total > 0)
+            .map(|(_, loading)| 1.0 - loading.steps.len() as f64 / loading.total as f64)
+            .collect::<Vec<_>>();
+        for progress in progresses {
+            let bar_width = 400.0;
+            let bar_height = 20.0;
+            let x = ((LOGICAL_SIZE.x - bar_width) / 2.0) as i32;
+            let y = ((LOGICAL_SIZE.y - bar_height) / 2.0) as i32;
+            ctx.draw_rect((50, 50, 50), 0, 0, LOGICAL_WIDTH, LOGICAL_HEIGHT)?;
+            ctx.draw_rect((25, 25, 25), x, y, bar_width as u32, bar_height as u32)?;
+            ctx.draw_rect(
+                (255, 255, 255),
+                x,
+                y,
+                (bar_width * progress) as u32,
+                bar_height as u32,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn missing_manifest_quits_without_panicking() {
+    let mut game = Game::new_headless();
+    let mut ctx = game.context();
+    ctx.add_system(LoadingSystem);
+    let id = query_one!(&ctx, Loading);
+    ctx.select::<Loading>(id).manifest_path = Some("assets/missing_manifest.json");
+    game.step();
+    assert!(game.should_quit());
+}
@@ -1,37 +1,52 @@
 #![allow(dead_code)]
 
-use engine::spawn;
+use capture::{highlight_recorder, CaptureSystem};
+use debug_overlay::{DebugOverlay, DebugOverlaySystem};
+use hot_reload::{HotReload, HotReloadSystem, ASSETS_DIR};
 use server::Server;
+use settings::{DisplaySettingsSystem, Settings, SETTINGS_PATH};
 use sound_player::sound_player;
 
+mod assets;
 mod attacks;
 mod backend_connection;
+mod camera;
+mod capture;
+mod debug_overlay;
+mod effects;
+mod events;
 mod game;
 mod hero_creator;
 mod hero_info;
+mod hot_reload;
 mod hud;
 mod hurtbox;
 mod keyset;
 mod knockoff;
+mod layers;
+mod loading;
 mod main_menu;
 mod mock_connection;
 mod pause;
 mod player;
+mod player_animation;
 mod player_interaction;
 mod server;
+mod settings;
 mod sound_player;
 mod sprite_renderer;
+mod stage;
+mod stage_select;
 mod start_game;
 mod timer;
 mod ui_components;
 
-// pub const FONT: &str = "assets/ttf/OpenSans.ttf";
-pub const FONT: &str = "assets/ttf/Jaro-Regular.ttf";
-
 fn main() {
     // let mut connection = backend_connection::BackendConnection::new();
     let connection = mock_connection::MockConnection::new();
     let mut server = Server::new(connection.clone());
+    // watches the assets directory and reloads what changes, F3 shows debug counters
+    let dev_mode = std::env::args().any(|arg| arg == "--dev");
 
     let (mut sound_player, sound_player_join_handle) = sound_player();
     sound_player.set_music_volume(0.3);
@@ -40,9 +55,25 @@ fn main() {
         let mut game = engine::Game::new().unwrap();
 
         let mut ctx = game.context();
-        spawn!(&mut ctx, sound_player.clone());
-        spawn!(&mut ctx, server.clone());
-        ctx.add_system(main_menu::MainMenuSystem);
+        let settings = Settings::load(SETTINGS_PATH);
+        if let Err(err) = ctx.set_display_mode(settings.display_mode.into()) {
+            println!("could not set display mode: {err}");
+        }
+        if settings.record_highlights {
+            ctx.start_recording(highlight_recorder());
+        }
+        ctx.insert_resource(settings);
+        ctx.add_system(DisplaySettingsSystem);
+        ctx.add_system(CaptureSystem);
+        ctx.insert_resource(sound_player.clone());
+        ctx.insert_resource(server.clone());
+        if dev_mode {
+            ctx.insert_resource(HotReload::new(ASSETS_DIR));
+            ctx.add_system(HotReloadSystem);
+            ctx.insert_resource(DebugOverlay::default());
+            ctx.add_system(DebugOverlaySystem);
+        }
+        ctx.push_state(loading::LoadingSystem);
 
         game.run();
         server.quit();
@@ -1,3 +1,4 @@
+use crate::assets::{Assets, SoundAsset, TextureAsset};
 use crate::hero_creator::HeroCreatorSystem;
 use crate::sound_player::SoundPlayer;
 use crate::start_game::StartGameSystem;
@@ -5,11 +6,11 @@ use crate::ui_components::Button;
 use engine::ui;
 use engine::SharedPtr;
 use engine::{query, spawn};
-use engine::{Component, System};
+use engine::{Component, Schedule, Stage, System};
+use engine::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
 
 #[derive(Component, Clone)]
 pub struct MainMenu {
-    system_id: u64,
     dom: SharedPtr<ui::Dom>,
     focus: SharedPtr<ui::focus::Focus>,
 }
@@ -43,14 +44,22 @@ impl From<Event> for ui::EventId {
 
 pub struct MainMenuSystem(pub u64);
 impl System for MainMenuSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
     fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
         use ui::constructors::{Hori, Image, Rect, Stack, Text, Vert};
 
-        let system_id = self.0;
-
+        let background = ctx
+            .resource::<Assets>()
+            .texture_path(TextureAsset::MainMenu)
+            .to_string();
         let mut dom = ui::Dom::new(
             Stack([
-                Image("assets/main_menu.png").width(1280).height(720),
+                Image(background)
+                    .width(LOGICAL_WIDTH as i32)
+                    .height(LOGICAL_HEIGHT as i32),
                 Vert([
                     Stack([
                         Vert([
@@ -92,31 +101,32 @@ impl System for MainMenuSystem {
             ])
             .background_color((50, 50, 50))
             .font_size(20)
-            .width(1280)
-            .height(720),
+            .width(LOGICAL_WIDTH as i32)
+            .height(LOGICAL_HEIGHT as i32),
         );
 
-        dom.add_event_handler(Event::StartGame, move |_dom, ctx, _node_id| {
-            ctx.remove_system(system_id);
-            ctx.add_system(StartGameSystem);
+        dom.add_event_handler(Event::StartGame, |_dom, ctx, _node_id| {
+            ctx.replace_state(StartGameSystem);
         });
 
-        dom.add_event_handler(Event::HeroCreator, move |_dom, ctx, _node_id| {
-            ctx.remove_system(system_id);
-            ctx.add_system(HeroCreatorSystem);
+        dom.add_event_handler(Event::HeroCreator, |_dom, ctx, _node_id| {
+            ctx.replace_state(HeroCreatorSystem);
         });
 
         dom.add_event_handler(Event::Exit, |_dom, ctx, _node_id| {
             ctx.quit();
         });
 
-        let sound_player = ctx.select_one::<SoundPlayer>();
-        sound_player.play_music("assets/sounds/theme_1.ogg");
+        let theme = ctx
+            .resource::<Assets>()
+            .sound(SoundAsset::MenuTheme)
+            .clone();
+        let sound_player = ctx.resource::<SoundPlayer>();
+        sound_player.play_music(theme);
 
         spawn!(
             ctx,
             MainMenu {
-                system_id: self.0,
                 dom: SharedPtr::new(dom),
                 focus: SharedPtr::new(ui::focus::Focus::new([
                     Node::StartGame,
@@ -130,8 +140,10 @@ impl System for MainMenuSystem {
     }
 
     fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
-        for id in query!(ctx, MainMenu) {
-            let main_menu = ctx.select::<MainMenu>(id).clone();
+        let main_menus = query!(ctx, MainMenu)
+            .map(|(_, main_menu)| main_menu.clone())
+            .collect::<Vec<_>>();
+        for main_menu in main_menus {
             let mut dom = main_menu.dom.lock();
             let mut focus = main_menu.focus.lock();
             focus.update(&mut dom, ctx);
@@ -140,12 +152,16 @@ impl System for MainMenuSystem {
         Ok(())
     }
 
-    fn on_remove(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        for id in query!(ctx, MainMenu) {
-            let main_menu = ctx.select::<MainMenu>(id).clone();
-            if main_menu.system_id == self.0 {
-                ctx.despawn(id);
-            }
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let main_menus = query!(ctx, MainMenu)
+            .map(|(_, main_menu)| main_menu.clone())
+            .collect::<Vec<_>>();
+        for main_menu in main_menus {
+            main_menu.dom.lock().draw(ctx);
         }
         Ok(())
     }
@@ -119,6 +119,7 @@ impl ServerStrategy for MockConnection {
             id,
             winner: params.winner_hero_id,
             loser: params.loser_hero_id,
+            stage: params.stage,
         });
         self.save();
     }
@@ -1,4 +1,3 @@
-use crate::game::Game;
 use crate::main_menu::MainMenuSystem;
 use crate::ui_components::Button;
 use engine::ui;
@@ -7,11 +6,12 @@ use engine::Component;
 use engine::SharedPtr;
 use engine::System;
 use engine::{query, spawn};
+use engine::{Schedule, Stage};
+use engine::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
 
 #[derive(Component, Clone)]
 pub struct Pause {
     dom: SharedPtr<ui::Dom>,
-    system_id: u64,
     focus: SharedPtr<Focus>,
 }
 
@@ -41,11 +41,13 @@ impl From<Event> for ui::EventId {
 pub struct PauseSystem(pub u64);
 
 impl System for PauseSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
     fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
         use ui::constructors::{Rect, Text, Vert};
 
-        let system_id = self.0;
-
         let mut dom = ui::Dom::new(
             Vert([
                 //
@@ -70,30 +72,23 @@ impl System for PauseSystem {
                     .on_click(Event::ReturnToMenu),
             ])
             .gap(8)
-            .width(1280)
-            .height(720),
+            .width(LOGICAL_WIDTH as i32)
+            .height(LOGICAL_HEIGHT as i32),
         );
 
-        dom.add_event_handler(Event::Resume, move |_dom, ctx, _node_id| {
-            ctx.remove_system(system_id);
-
-            let game = ctx.select_one::<Game>();
-            game.paused = false;
+        dom.add_event_handler(Event::Resume, |_dom, ctx, _node_id| {
+            ctx.pop_state();
         });
 
-        dom.add_event_handler(Event::ReturnToMenu, move |_dom, ctx, _node_id| {
-            ctx.remove_system(system_id);
-
-            let game = ctx.select_one::<Game>().system_id;
-            ctx.remove_system(game);
-            ctx.add_system(MainMenuSystem);
+        dom.add_event_handler(Event::ReturnToMenu, |_dom, ctx, _node_id| {
+            ctx.pop_state();
+            ctx.replace_state(MainMenuSystem);
         });
 
         spawn!(
             ctx,
             Pause {
                 dom: dom.into(),
-                system_id: self.0,
                 focus: SharedPtr::new(Focus::new([Node::ResumeButton, Node::MainMenuButton,])),
             }
         );
@@ -102,10 +97,10 @@ impl System for PauseSystem {
     }
 
     fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
-        let _ = ctx.draw_rect_alpha((0, 0, 0), 100, 0, 0, 1280, 720);
-
-        for id in query!(ctx, Pause) {
-            let pause = ctx.select::<Pause>(id).clone();
+        let pauses = query!(ctx, Pause)
+            .map(|(_, pause)| pause.clone())
+            .collect::<Vec<_>>();
+        for pause in pauses {
             let mut dom = pause.dom.lock();
             let mut focus = pause.focus.lock();
             focus.update(&mut dom, ctx);
@@ -115,12 +110,18 @@ impl System for PauseSystem {
         Ok(())
     }
 
-    fn on_remove(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        for id in query!(ctx, Pause) {
-            let pause = ctx.select::<Pause>(id).clone();
-            if pause.system_id == self.0 {
-                ctx.despawn(id);
-            }
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let _ = ctx.draw_rect_alpha((0, 0, 0), 100, 0, 0, LOGICAL_WIDTH, LOGICAL_HEIGHT);
+
+        let pauses = query!(ctx, Pause)
+            .map(|(_, pause)| pause.clone())
+            .collect::<Vec<_>>();
+        for pause in pauses {
+            pause.dom.lock().draw(ctx);
         }
 
         Ok(())
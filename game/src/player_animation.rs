@@ -0,0 +1,228 @@
+use engine::{
+    animation::{Animation, AnimationSystem, Clip},
+    query,
+    rigid_body::RigidBody,
+    Component, Context, Error, Schedule, Stage, System, Texture, V2,
+};
+
+use crate::{
+    events::AttackStarted,
+    hurtbox::Victim,
+    player_interaction::{DodgeState, JumpState, PlayerInteraction},
+};
+
+/// What a fighter is doing, as far as their sprite is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerAnimationState {
+    Idle,
+    Run,
+    Jump,
+    Attack,
+    Hitstun,
+    Dodge,
+}
+
+impl PlayerAnimationState {
+    /// In the order of the rows of a hero sprite sheet.
+    const ALL: [Self; 6] = [
+        Self::Idle,
+        Self::Run,
+        Self::Jump,
+        Self::Attack,
+        Self::Hitstun,
+        Self::Dodge,
+    ];
+
+    fn sheet_row(self) -> u32 {
+        Self::ALL
+            .iter()
+            .position(|state| *state == self)
+            .expect("every state is listed") as u32
+    }
+
+    /// Attacks play once, as long as the hurtbox they spawn lingers.
+    fn clip_duration(self) -> Option<f64> {
+        match self {
+            Self::Attack => Some(0.3),
+            _ => None,
+        }
+    }
+
+    /// Being hit or dodging interrupts anything, an attack plays to its end
+    /// unless interrupted, and movement decides the rest.
+    pub fn next(self, conditions: &PlayerConditions, clip_finished: bool) -> Self {
+        if conditions.stunned {
+            Self::Hitstun
+        } else if conditions.dodging {
+            Self::Dodge
+        } else if conditions.attack_started || (self == Self::Attack && !clip_finished) {
+            Self::Attack
+        } else if conditions.airborne {
+            Self::Jump
+        } else if conditions.running {
+            Self::Run
+        } else {
+            Self::Idle
+        }
+    }
+}
+
+/// The parts of a fighter's state which decide their animation.
+#[derive(Default)]
+pub struct PlayerConditions {
+    pub stunned: bool,
+    pub dodging: bool,
+    pub attack_started: bool,
+    pub airborne: bool,
+    pub running: bool,
+}
+
+impl PlayerConditions {
+    pub fn new(
+        interaction: &PlayerInteraction,
+        victim: &Victim,
+        body: &RigidBody,
+        attack_started: bool,
+    ) -> Self {
+        Self {
+            stunned: victim.stunned.is_some(),
+            dodging: matches!(interaction.dodge_state, DodgeState::Dodging(_)),
+            attack_started,
+            airborne: !matches!(interaction.jump_state, JumpState::OnGround),
+            running: body.vel.x.abs() > 50.0,
+        }
+    }
+}
+
+/// A fighter's clip for every [`PlayerAnimationState`], and the state currently shown.
+#[derive(Component, Clone)]
+pub struct PlayerAnimation {
+    state: PlayerAnimationState,
+    clips: Vec<Clip>,
+}
+
+impl PlayerAnimation {
+    /// Hero sheets hold a row of `frame_size` frames per state, in the order of
+    /// [`PlayerAnimationState`], with as many frames as fit in the width of the sheet.
+    /// States without a row use the first one, so a single image works as a sheet too.
+    pub fn from_sheet(ctx: &mut Context, texture: Texture, frame_size: V2) -> Result<Self, Error> {
+        let (width, height) = ctx.texture_size(texture)?;
+        let columns = std::cmp::max(1, width / frame_size.x as u32);
+        let rows = std::cmp::max(1, height / frame_size.y as u32);
+        let clips = PlayerAnimationState::ALL
+            .iter()
+            .map(|state| {
+                let row = if state.sheet_row() < rows {
+                    state.sheet_row()
+                } else {
+                    0
+                };
+                match state.clip_duration() {
+                    Some(duration) => Clip::from_sheet_row(
+                        texture,
+                        frame_size,
+                        row,
+                        columns,
+                        duration / columns as f64,
+                    ),
+                    None => Clip::from_sheet_row(texture, frame_size, row, columns, 0.1).looping(),
+                }
+            })
+            .collect();
+        Ok(Self {
+            state: PlayerAnimationState::Idle,
+            clips,
+        })
+    }
+
+    pub fn state(&self) -> PlayerAnimationState {
+        self.state
+    }
+
+    pub fn clip(&self, state: PlayerAnimationState) -> Clip {
+        self.clips[state.sheet_row() as usize].clone()
+    }
+}
+
+/// Switches each fighter's [`Animation`] to the clip matching what they are doing.
+pub struct PlayerAnimationSystem(pub u64);
+impl System for PlayerAnimationSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Gameplay).before::<AnimationSystem>()
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        let attackers = ctx
+            .read_events::<AttackStarted>()
+            .into_iter()
+            .map(|event| event.player)
+            .collect::<Vec<_>>();
+        let players = query!(
+            ctx,
+            PlayerAnimation,
+            Animation,
+            PlayerInteraction,
+            Victim,
+            RigidBody
+        )
+        .map(|(id, _, animation, interaction, victim, body)| {
+            let attack_started = attackers.contains(&id);
+            let conditions = PlayerConditions::new(interaction, victim, body, attack_started);
+            (id, conditions, animation.finished(), attack_started)
+        })
+        .collect::<Vec<_>>();
+        for (id, conditions, clip_finished, attack_started) in players {
+            let player_animation = ctx.select::<PlayerAnimation>(id);
+            let state = player_animation.state.next(&conditions, clip_finished);
+            // a new attack restarts the clip, even in the middle of the previous one
+            if state == player_animation.state && !attack_started {
+                continue;
+            }
+            player_animation.state = state;
+            let clip = player_animation.clip(state);
+            ctx.select::<Animation>(id).play(clip);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn player_animation_state_follows_priorities() {
+    use PlayerAnimationState::*;
+
+    let idle = PlayerConditions::default();
+    assert_eq!(Idle.next(&idle, false), Idle);
+    let running = PlayerConditions {
+        running: true,
+        ..Default::default()
+    };
+    assert_eq!(Idle.next(&running, false), Run);
+    let jumping = PlayerConditions {
+        airborne: true,
+        running: true,
+        ..Default::default()
+    };
+    assert_eq!(Run.next(&jumping, false), Jump);
+
+    let attacking = PlayerConditions {
+        attack_started: true,
+        airborne: true,
+        ..Default::default()
+    };
+    assert_eq!(Jump.next(&attacking, false), Attack);
+    assert_eq!(Attack.next(&jumping, false), Attack, "attacks play out");
+    assert_eq!(Attack.next(&jumping, true), Jump);
+
+    let hit_while_dodging = PlayerConditions {
+        stunned: true,
+        dodging: true,
+        attack_started: true,
+        ..Default::default()
+    };
+    assert_eq!(Attack.next(&hit_while_dodging, false), Hitstun);
+    let dodging = PlayerConditions {
+        dodging: true,
+        ..Default::default()
+    };
+    assert_eq!(Attack.next(&dodging, false), Dodge);
+}
@@ -1,7 +1,16 @@
-use engine::{query, rigid_body::RigidBody, spawn, Component, SolidCollider, System, V2};
+use engine::{
+    animation::{Animation, Clip},
+    collision::ShallowCollider,
+    query, query_entities,
+    rigid_body::RigidBody,
+    spawn, Component, Schedule, SolidCollider, Stage, System, V2,
+};
 
 use crate::{
+    assets::{Assets, SequenceAsset, SoundAsset},
     attacks::{self, AttackKind},
+    effects::{landing_dust, spawn_effect},
+    events::AttackStarted,
     hurtbox::{HurtDirection, Hurtbox, HurtboxProfile, Victim},
     keyset::Keyset,
     player::Player,
@@ -66,8 +75,12 @@ impl PlayerInteraction {
 
 pub struct PlayerInteractionSystem(pub u64);
 impl System for PlayerInteractionSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Input)
+    }
+
     fn on_update(&self, ctx: &mut engine::Context, delta: f64) -> Result<(), engine::Error> {
-        for id in query!(ctx, PlayerInteraction, Victim, RigidBody, SolidCollider) {
+        for id in query_entities!(ctx, PlayerInteraction, Victim, RigidBody, SolidCollider) {
             self.update_player_attack(ctx, delta, id)?;
             self.update_player_movement(ctx, delta, id)?;
             self.update_dodge(ctx, delta, id)?;
@@ -81,34 +94,39 @@ impl PlayerInteractionSystem {
         &self,
         ctx: &mut engine::Context,
         attack_kind: AttackKind,
-        id: u64,
+        id: engine::Entity,
         body: &RigidBody,
     ) {
-        let sound_player = ctx.select_one::<SoundPlayer>();
+        let swoosh = ctx.resource::<Assets>().sound(SoundAsset::Swoosh).clone();
+        let sound_player = ctx.resource::<SoundPlayer>();
         sound_player.set_effect_volume(0.2);
-        sound_player.play_effect("assets/sounds/swoosh.ogg");
+        sound_player.play_effect(swoosh);
 
         let attack_size = self.attack_size(&attack_kind);
         let pos = self.attack_pos(&attack_kind, body, attack_size);
         let vel = self.attack_vel(&attack_kind, body.vel);
         let textures = self.attack_textures(ctx, &attack_kind);
         let profile = self.attack_profile(&attack_kind).into();
+        let duration = 0.3;
+        let frame_duration = duration / textures.len() as f64;
         spawn!(
             ctx,
-            Sprite::new(textures[0]),
+            // in front of the fighters
+            Sprite::new(textures[0]).depth(1),
             // .size((256.0, 64.0))
             // .offset((0.0, -16.0)),
+            Animation::new(Clip::from_textures(textures, frame_duration)),
             RigidBody::new()
                 .with_pos(pos)
                 .with_vel(vel)
                 .with_size(attack_size),
             Hurtbox {
                 owner: Some(id),
-                timer: Timer::new(0.3),
-                textures,
+                timer: Timer::new(duration),
                 profile,
             }
         );
+        ctx.send_event(AttackStarted { player: id });
     }
 
     fn attack_size(&self, attack_kind: &AttackKind) -> V2 {
@@ -155,49 +173,20 @@ impl PlayerInteractionSystem {
         ctx: &mut engine::Context,
         attack_kind: &AttackKind,
     ) -> Vec<engine::Texture> {
-        match attack_kind {
-            AttackKind::Up => vec![
-                "assets/attacks/up_0.png".to_string(),
-                "assets/attacks/up_1.png".to_string(),
-                "assets/attacks/up_2.png".to_string(),
-                "assets/attacks/up_3.png".to_string(),
-                "assets/attacks/up_4.png".to_string(),
-            ],
-            AttackKind::Down => vec![
-                "assets/attacks/down_0.png".to_string(),
-                "assets/attacks/down_1.png".to_string(),
-                "assets/attacks/down_2.png".to_string(),
-                "assets/attacks/down_3.png".to_string(),
-                "assets/attacks/down_4.png".to_string(),
-                "assets/attacks/down_5.png".to_string(),
-                "assets/attacks/down_6.png".to_string(),
-                "assets/attacks/down_7.png".to_string(),
-            ],
-            AttackKind::Left => vec![
-                "assets/attacks/left_0.png".to_string(),
-                "assets/attacks/left_1.png".to_string(),
-                "assets/attacks/left_2.png".to_string(),
-                "assets/attacks/left_3.png".to_string(),
-                "assets/attacks/left_4.png".to_string(),
-            ],
-            AttackKind::Right => vec![
-                "assets/attacks/right_0.png".to_string(),
-                "assets/attacks/right_1.png".to_string(),
-                "assets/attacks/right_2.png".to_string(),
-                "assets/attacks/right_3.png".to_string(),
-                "assets/attacks/right_4.png".to_string(),
-            ],
-        }
-        .into_iter()
-        .map(|path| ctx.load_texture(path).unwrap())
-        .collect::<Vec<_>>()
+        let sequence = match attack_kind {
+            AttackKind::Up => SequenceAsset::AttackUp,
+            AttackKind::Down => SequenceAsset::AttackDown,
+            AttackKind::Left => SequenceAsset::AttackLeft,
+            AttackKind::Right => SequenceAsset::AttackRight,
+        };
+        ctx.resource::<Assets>().sequence(sequence)
     }
 
     fn update_player_attack(
         &self,
         ctx: &mut engine::Context,
         delta: f64,
-        id: u64,
+        id: engine::Entity,
     ) -> Result<(), engine::Error> {
         let player_attack = ctx.select::<PlayerInteraction>(id).clone();
         let keyset = player_attack.keyset;
@@ -213,11 +202,12 @@ impl PlayerInteractionSystem {
         }
 
         if victim.stunned.is_some() {
-            for hurtbox_id in query!(ctx, Hurtbox, RigidBody) {
-                let hurtbox = ctx.select::<Hurtbox>(hurtbox_id);
-                if hurtbox.owner.is_some_and(|owner| owner == id) {
-                    ctx.despawn(hurtbox_id);
-                };
+            let owned = query!(ctx, Hurtbox, RigidBody)
+                .filter(|(_, hurtbox, _)| hurtbox.owner.is_some_and(|owner| owner == id))
+                .map(|(hurtbox_id, ..)| hurtbox_id)
+                .collect::<Vec<_>>();
+            for hurtbox_id in owned {
+                ctx.despawn(hurtbox_id);
             }
             return Ok(());
         }
@@ -260,13 +250,14 @@ impl PlayerInteractionSystem {
         &self,
         ctx: &mut engine::Context,
         delta: f64,
-        id: u64,
+        id: engine::Entity,
     ) -> Result<(), engine::Error> {
         let keyset = ctx.select::<PlayerInteraction>(id).clone().keyset;
 
         let right_pressed = ctx.key_pressed(keyset.right());
         let left_pressed = ctx.key_pressed(keyset.left());
         let down_pressed = ctx.key_pressed(keyset.down());
+        let down_just_pressed = ctx.key_just_pressed(keyset.down());
 
         let up_pressed = ctx.key_just_pressed(keyset.up());
 
@@ -293,15 +284,24 @@ impl PlayerInteractionSystem {
             body.vel.y += 3200.0 * delta;
         }
 
-        if collider
+        let on_ground = collider
             .colliding
-            .is_some_and(|dir| dir.facing(engine::physics::OctoDirection::Bottom))
-        {
+            .is_some_and(|dir| dir.facing(engine::physics::OctoDirection::Bottom));
+
+        if on_ground && down_just_pressed {
+            self.drop_through_platforms(ctx, id);
+        }
+
+        if on_ground {
             let player_movement = ctx.select::<PlayerInteraction>(id);
             if !matches!(player_movement.jump_state, JumpState::OnGround) {
                 player_movement.jump_state = JumpState::OnGround;
-                let sound_player = ctx.select_one::<SoundPlayer>();
-                sound_player.play_effect("assets/sounds/click.ogg");
+                let body = ctx.select::<RigidBody>(id);
+                let feet = body.pos + V2::new(body.size.x / 2.0, body.size.y);
+                spawn_effect(ctx, feet, landing_dust());
+                let click = ctx.resource::<Assets>().sound(SoundAsset::Click).clone();
+                let sound_player = ctx.resource::<SoundPlayer>();
+                sound_player.play_effect(click);
             }
         }
 
@@ -314,11 +314,29 @@ impl PlayerInteractionSystem {
         Ok(())
     }
 
+    /// Lets the player fall through the one-way platforms they stand on.
+    fn drop_through_platforms(&self, ctx: &mut engine::Context, id: engine::Entity) {
+        let body = ctx.select::<RigidBody>(id).clone();
+        let feet = body.pos.y + body.size.y;
+        let under_feet = query!(ctx, ShallowCollider, RigidBody)
+            .filter(|(_, _, platform)| {
+                (platform.pos.y - feet).abs() < 1.0
+                    && platform.pos.x < body.pos.x + body.size.x
+                    && body.pos.x < platform.pos.x + platform.size.x
+            })
+            .map(|(platform_id, ..)| platform_id)
+            .collect::<Vec<_>>();
+        for platform_id in under_feet {
+            let collider = ctx.select::<ShallowCollider>(platform_id);
+            collider.ignore(id, 0.25);
+        }
+    }
+
     fn update_dodge(
         &self,
         ctx: &mut engine::Context,
         delta: f64,
-        id: u64,
+        id: engine::Entity,
     ) -> Result<(), engine::Error> {
         let player_interaction = ctx.select::<PlayerInteraction>(id);
         let keyset = player_interaction.keyset.clone();
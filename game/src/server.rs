@@ -1,6 +1,13 @@
 use std::sync::{Arc, Mutex};
 
-use engine::Component;
+use engine::{query, Context, Error, System};
+
+use crate::{
+    events::{HitLanded, MatchEnded, PlayerKnockedOut},
+    hud::player_damage_color,
+    player::{Player, PlayerKind},
+    stage::StageData,
+};
 
 #[derive(Clone, Debug)]
 pub enum HeroResult {
@@ -27,7 +34,7 @@ pub trait ServerStrategy {
     fn create_match(&mut self, params: shared::CreateMatchParams);
 }
 
-#[derive(Component, Clone)]
+#[derive(Clone)]
 pub struct Server {
     strategy: Arc<Mutex<dyn ServerStrategy + Send>>,
 }
@@ -63,3 +70,42 @@ impl Server {
         self.strategy.lock().unwrap().create_match(params)
     }
 }
+
+/// Reports the state of the match to the server, as it changes.
+pub struct ServerReportSystem(pub u64);
+impl System for ServerReportSystem {
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        let damage_changed = !ctx.read_events::<HitLanded>().is_empty()
+            || !ctx.read_events::<PlayerKnockedOut>().is_empty();
+        if damage_changed {
+            notify_server_about_player_colors(ctx);
+        }
+        for MatchEnded { winner, loser } in ctx.read_events::<MatchEnded>() {
+            let stage = ctx.resource::<StageData>().id.clone();
+            let server = ctx.resource::<Server>();
+            server.create_match(shared::CreateMatchParams {
+                winner_hero_id: winner.id,
+                loser_hero_id: loser.id,
+                stage,
+            });
+        }
+        Ok(())
+    }
+}
+
+pub fn notify_server_about_player_colors(ctx: &mut engine::Context) {
+    let mut hero_1_color = (255, 255, 255);
+    let mut hero_2_color = (255, 255, 255);
+    for (_, player) in query!(ctx, Player) {
+        match player.kind {
+            PlayerKind::Left => hero_1_color = player_damage_color(player.damage_taken),
+            PlayerKind::Right => hero_2_color = player_damage_color(player.damage_taken),
+        }
+    }
+    let board_colors = shared::UpdateBoardColorsParams {
+        hero_1_color,
+        hero_2_color,
+    };
+    let server = ctx.resource::<Server>();
+    server.update_board_colors(board_colors);
+}
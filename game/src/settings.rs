@@ -0,0 +1,120 @@
+use std::path::Path;
+
+use engine::{Context, DisplayMode, Error, Keycode, Schedule, Stage, System};
+use serde::{Deserialize, Serialize};
+
+pub const SETTINGS_PATH: &str = "settings.json";
+
+/// Player preferences which outlive a session, stored as json in [`SETTINGS_PATH`].
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub display_mode: DisplayModeSetting,
+    /// Keeps the last seconds of play in memory, so they can be exported as a highlight.
+    pub record_highlights: bool,
+    pub highlight_format: HighlightFormat,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayModeSetting {
+    #[default]
+    Windowed,
+    Fullscreen,
+    Borderless,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HighlightFormat {
+    #[default]
+    Gif,
+    /// A directory of numbered pngs, e.g. for editing into a video.
+    PngSequence,
+}
+
+impl From<DisplayModeSetting> for DisplayMode {
+    fn from(value: DisplayModeSetting) -> Self {
+        match value {
+            DisplayModeSetting::Windowed => DisplayMode::Windowed,
+            DisplayModeSetting::Fullscreen => DisplayMode::Fullscreen,
+            DisplayModeSetting::Borderless => DisplayMode::Borderless,
+        }
+    }
+}
+
+impl From<DisplayMode> for DisplayModeSetting {
+    fn from(value: DisplayMode) -> Self {
+        match value {
+            DisplayMode::Windowed => DisplayModeSetting::Windowed,
+            DisplayMode::Fullscreen => DisplayModeSetting::Fullscreen,
+            DisplayMode::Borderless => DisplayModeSetting::Borderless,
+        }
+    }
+}
+
+impl Settings {
+    /// Falls back to the defaults if the file is missing or can't be read,
+    /// so a broken settings file never keeps the game from starting.
+    pub fn load<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref();
+        let Ok(json) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|err| {
+            println!("{}: {err}, using default settings", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Error> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        std::fs::write(path, json).map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(())
+    }
+}
+
+/// Toggles fullscreen with F11 and remembers the choice in the [`Settings`] resource.
+/// Added outside of any state, so it works in menus and during matches alike.
+pub struct DisplaySettingsSystem(pub u64);
+impl System for DisplaySettingsSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Input)
+    }
+
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        if !ctx.key_just_pressed(Keycode::F11) {
+            return Ok(());
+        }
+        let mode = match ctx.display_mode() {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Fullscreen | DisplayMode::Borderless => DisplayMode::Windowed,
+        };
+        ctx.set_display_mode(mode)?;
+
+        let settings = ctx.resource::<Settings>();
+        settings.display_mode = mode.into();
+        settings.save(SETTINGS_PATH)
+    }
+}
+
+#[test]
+fn settings_fall_back_to_defaults() {
+    assert_eq!(
+        Settings::load("assets/does_not_exist.json"),
+        Settings::default()
+    );
+    let settings: Settings = serde_json::from_str("{}").unwrap();
+    assert_eq!(settings.display_mode, DisplayModeSetting::Windowed);
+    assert!(!settings.record_highlights);
+    assert_eq!(settings.highlight_format, HighlightFormat::Gif);
+    let settings: Settings =
+        serde_json::from_str(r#"{"display_mode":"borderless","highlight_format":"png_sequence"}"#)
+            .unwrap();
+    assert_eq!(settings.highlight_format, HighlightFormat::PngSequence);
+    assert_eq!(
+        DisplayMode::from(settings.display_mode),
+        DisplayMode::Borderless
+    );
+}
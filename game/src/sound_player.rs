@@ -7,7 +7,12 @@ use std::{
     thread::{spawn, JoinHandle},
 };
 
-use engine::Component;
+use engine::{Context, Error, System};
+
+use crate::{
+    assets::{Assets, SoundAsset},
+    events::PlayerKnockedOut,
+};
 
 #[derive(Clone)]
 pub enum Message {
@@ -17,9 +22,12 @@ pub enum Message {
     StopMusic,
     PlayMusic(PathBuf),
     PlayEffect(PathBuf),
+    /// The file changed on disk. Effects are read each time they are played,
+    /// so only music which is playing from it has to be restarted.
+    Reload(PathBuf),
 }
 
-#[derive(Component, Clone)]
+#[derive(Clone)]
 pub struct SoundPlayer {
     sender: Sender<Message>,
 }
@@ -56,6 +64,12 @@ impl SoundPlayer {
             .send(Message::PlayEffect(path.as_ref().to_path_buf()))
             .unwrap()
     }
+
+    pub fn reload<P: AsRef<Path>>(&mut self, path: P) {
+        self.sender
+            .send(Message::Reload(path.as_ref().to_path_buf()))
+            .unwrap()
+    }
 }
 
 pub fn sound_player() -> (SoundPlayer, JoinHandle<()>) {
@@ -64,6 +78,7 @@ pub fn sound_player() -> (SoundPlayer, JoinHandle<()>) {
         let (_stream, stream_handle) = OutputStream::try_default().unwrap();
         let music_sink = Sink::try_new(&stream_handle).unwrap();
         let effect_sink = Sink::try_new(&stream_handle).unwrap();
+        let mut music: Option<PathBuf> = None;
 
         loop {
             let Ok(message) = receiver.recv() else {
@@ -80,13 +95,15 @@ pub fn sound_player() -> (SoundPlayer, JoinHandle<()>) {
                 }
                 Message::StopMusic => {
                     music_sink.clear();
+                    music = None;
                 }
                 Message::PlayMusic(path) => {
-                    let file = BufReader::new(File::open(path).unwrap());
+                    let file = BufReader::new(File::open(&path).unwrap());
                     let source = Decoder::new(file).unwrap();
                     music_sink.clear();
                     music_sink.play();
                     music_sink.append(source.convert_samples::<f32>().repeat_infinite());
+                    music = Some(path);
                 }
                 Message::PlayEffect(path) => {
                     let file = BufReader::new(File::open(path).unwrap());
@@ -95,8 +112,41 @@ pub fn sound_player() -> (SoundPlayer, JoinHandle<()>) {
                     effect_sink.play();
                     effect_sink.append(source.convert_samples::<f32>());
                 }
+                Message::Reload(path) => {
+                    if music.as_ref() != Some(&path) {
+                        continue;
+                    }
+                    // the file may be half written, in which case the next change restarts it
+                    let Ok(file) = File::open(&path) else {
+                        continue;
+                    };
+                    let Ok(source) = Decoder::new(BufReader::new(file)) else {
+                        continue;
+                    };
+                    music_sink.clear();
+                    music_sink.play();
+                    music_sink.append(source.convert_samples::<f32>().repeat_infinite());
+                }
             }
         }
     });
     (SoundPlayer::new(sender), join_handle)
 }
+
+/// Plays sound effects in response to gameplay events.
+pub struct SoundEffectSystem(pub u64);
+impl System for SoundEffectSystem {
+    fn on_update(&self, ctx: &mut Context, _delta: f64) -> Result<(), Error> {
+        if ctx.read_events::<PlayerKnockedOut>().is_empty() {
+            return Ok(());
+        }
+        let explosion = ctx
+            .resource::<Assets>()
+            .sound(SoundAsset::Explosion)
+            .clone();
+        let sound_player = ctx.resource::<SoundPlayer>();
+        sound_player.set_effect_volume(0.5);
+        sound_player.play_effect(explosion);
+        Ok(())
+    }
+}
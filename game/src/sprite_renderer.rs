@@ -1,11 +1,19 @@
-use engine::{query, rigid_body::RigidBody, Component, DrawTextureOpts, System, V2};
+use engine::{
+    animation::{Animation, Frame},
+    particles::{ParticleEmitter, ParticleSprite},
+    query,
+    rigid_body::RigidBody,
+    Component, DrawCommand, DrawTextureOpts, RenderLayer, Schedule, Stage, System, V2,
+};
 
 #[derive(Component, Debug, Clone)]
 pub struct Sprite {
     pub offset: V2,
     pub size: Option<V2>,
     pub texture: engine::Texture,
-    pub layer: i32,
+    pub layer: RenderLayer,
+    /// Order within the layer, higher is drawn on top.
+    pub depth: i32,
     pub opacity: Option<f64>,
     pub angle: Option<f64>,
 }
@@ -14,7 +22,8 @@ impl Sprite {
     pub fn new(texture: engine::Texture) -> Self {
         Self {
             texture,
-            layer: 0,
+            layer: RenderLayer::World,
+            depth: 0,
             offset: V2::new(0.0, 0.0),
             size: None,
             opacity: None,
@@ -22,10 +31,14 @@ impl Sprite {
         }
     }
 
-    pub fn layer(self, layer: i32) -> Self {
+    pub fn layer(self, layer: RenderLayer) -> Self {
         Self { layer, ..self }
     }
 
+    pub fn depth(self, depth: i32) -> Self {
+        Self { depth, ..self }
+    }
+
     pub fn size(self, size: V2) -> Self {
         Self {
             size: Some(size),
@@ -49,28 +62,70 @@ impl Sprite {
     }
 }
 
+/// Submits sprites and particles to the render queue, on their layer and depth.
 pub struct SpriteRenderer(pub u64);
 impl System for SpriteRenderer {
-    fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
-        let mut sprites = Vec::<(Sprite, V2, V2)>::new();
-        for id in query!(ctx, RigidBody, Sprite) {
-            let body = ctx.select::<RigidBody>(id).clone();
-            let sprite = ctx.select::<Sprite>(id).clone();
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Render)
+    }
 
-            sprites.push((sprite, body.pos, body.size));
-        }
-        sprites.sort_by(|(a, _, _), (b, _, _)| b.layer.cmp(&a.layer));
-        for (sprite, pos, body_size) in sprites {
-            let size = sprite.size.unwrap_or(body_size);
-            let opacity = sprite.opacity.unwrap_or(1.0);
-            ctx.draw_texture(
-                sprite.texture,
-                pos + sprite.offset,
-                DrawTextureOpts::new()
-                    .size(size)
-                    .opacity(opacity)
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let mut commands = Vec::new();
+        for (id, body, sprite) in query!(ctx, RigidBody, Sprite) {
+            let pos = body.interpolated_pos(interpolation);
+            // animated sprites show the current frame instead of their own texture
+            let frame = ctx
+                .get::<Animation>(id)
+                .map(|animation| animation.frame().clone())
+                .unwrap_or_else(|| Frame::new(sprite.texture, 0.0));
+            let command = DrawCommand::Texture {
+                texture: frame.texture,
+                pos: pos + sprite.offset,
+                opts: frame
+                    .draw_opts()
+                    .size(sprite.size.unwrap_or(body.size))
+                    .opacity(sprite.opacity.unwrap_or(1.0))
                     .angle(sprite.angle),
-            )?;
+            };
+            commands.push((sprite.layer, sprite.depth, command));
+        }
+        for (_, _, emitter) in query!(ctx, RigidBody, ParticleEmitter) {
+            let (layer, depth, texture) = (emitter.layer, emitter.depth, emitter.texture);
+            for ParticleSprite {
+                pos,
+                size,
+                color,
+                alpha,
+            } in emitter.sprites()
+            {
+                let command = match texture {
+                    Some(texture) => DrawCommand::Texture {
+                        texture,
+                        pos,
+                        opts: DrawTextureOpts::new()
+                            .size(size)
+                            .color_mod(color)
+                            .opacity(alpha),
+                    },
+                    None => {
+                        let (r, g, b) = color;
+                        let a = (alpha.clamp(0.0, 1.0) * 255.0) as u8;
+                        DrawCommand::Rect {
+                            rgba: (r, g, b, a),
+                            pos,
+                            size,
+                        }
+                    }
+                };
+                commands.push((layer, depth, command));
+            }
+        }
+        for (layer, depth, command) in commands {
+            ctx.draw_world(layer, depth, command)?;
         }
         Ok(())
     }
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use engine::{
+    collision::ShallowCollider, physics::QuadDirection, rigid_body::RigidBody, spawn, Component,
+    Context, Error, RenderLayer, SolidCollider, LOGICAL_SIZE, V2,
+};
+use serde::Deserialize;
+
+use crate::{layers::TERRAIN, sound_player::SoundPlayer, sprite_renderer::Sprite};
+
+pub const STAGES_DIR: &str = "assets/stages";
+
+/// An arena, described by a json file in [`STAGES_DIR`].
+#[derive(Deserialize, Clone)]
+pub struct StageData {
+    /// File name without extension, used to record which stage a match was played on.
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub music: String,
+    pub backgrounds: Vec<Background>,
+    pub platforms: Vec<Platform>,
+    /// Where the left and right player start.
+    pub spawn_points: [Point; 2],
+    pub blast_zone: BlastZone,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Background {
+    pub texture: String,
+    pub layer: i32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum PlatformKind {
+    Solid,
+    /// Can be jumped through from below and stood on from above.
+    Shallow,
+}
+
+#[derive(Deserialize, Clone)]
+pub struct Platform {
+    pub kind: PlatformKind,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl From<Point> for V2 {
+    fn from(value: Point) -> Self {
+        V2::new(value.x, value.y)
+    }
+}
+
+/// Players entirely outside these bounds are knocked out.
+#[derive(Deserialize, Clone)]
+pub struct BlastZone {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+}
+
+impl BlastZone {
+    pub fn contains(&self, body: &RigidBody) -> bool {
+        body.pos.x + body.size.x >= self.left
+            && body.pos.x <= self.right
+            && body.pos.y + body.size.y >= self.top
+            && body.pos.y <= self.bottom
+    }
+
+    pub fn pos(&self) -> V2 {
+        V2::new(self.left, self.top)
+    }
+
+    pub fn size(&self) -> V2 {
+        V2::new(self.right - self.left, self.bottom - self.top)
+    }
+}
+
+/// Marks the backgrounds and platforms spawned for a stage, so they can be rebuilt.
+#[derive(Component, Clone)]
+pub struct StagePiece;
+
+impl StageData {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)
+            .map_err(|err| format!("unable to read stage {}: {err}", path.display()))?;
+        let mut stage: StageData = serde_json::from_str(&json)
+            .map_err(|err| format!("invalid stage {}: {err}", path.display()))?;
+        stage.id = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(stage)
+    }
+
+    /// Loads every stage in the directory, sorted by file name.
+    pub fn load_all<P: AsRef<Path>>(dir: P) -> Result<Vec<Self>, Error> {
+        stage_paths(dir.as_ref())?
+            .into_iter()
+            .map(StageData::load)
+            .collect()
+    }
+
+    /// Like [`StageData::load_all`], but prints and skips the stages that fail to load,
+    /// as a stage edited while the game runs may be broken.
+    pub fn load_valid<P: AsRef<Path>>(dir: P) -> Vec<Self> {
+        let paths = match stage_paths(dir.as_ref()) {
+            Ok(paths) => paths,
+            Err(err) => {
+                println!("could not list stages: {err}");
+                return Vec::new();
+            }
+        };
+        paths
+            .into_iter()
+            .filter_map(|path| match StageData::load(path) {
+                Ok(stage) => Some(stage),
+                Err(err) => {
+                    println!("skipping stage: {err}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Spawns the backgrounds and platforms, and starts the music.
+    pub fn spawn(&self, ctx: &mut Context) -> Result<(), Error> {
+        self.spawn_layout(ctx)?;
+        let sound_player = ctx.resource::<SoundPlayer>();
+        sound_player.play_music(&self.music);
+        Ok(())
+    }
+
+    /// Spawns the backgrounds and platforms, each marked as a [`StagePiece`].
+    pub fn spawn_layout(&self, ctx: &mut Context) -> Result<(), Error> {
+        for background in &self.backgrounds {
+            let texture = ctx.load_texture(&background.texture)?;
+            spawn!(
+                ctx,
+                // stage files number backgrounds from the front, higher is further back
+                Sprite::new(texture)
+                    .layer(RenderLayer::Background)
+                    .depth(-background.layer),
+                RigidBody::new().with_size(LOGICAL_SIZE),
+                StagePiece,
+            );
+        }
+
+        for platform in &self.platforms {
+            let body = RigidBody::new()
+                .with_pos(V2::new(platform.x, platform.y))
+                .with_size(V2::new(platform.width, platform.height));
+            match platform.kind {
+                PlatformKind::Solid => {
+                    spawn!(ctx, body, SolidCollider::new().layers(TERRAIN), StagePiece)
+                }
+                PlatformKind::Shallow => spawn!(
+                    ctx,
+                    body,
+                    ShallowCollider::new()
+                        .with_direction(QuadDirection::Top)
+                        .layers(TERRAIN),
+                    StagePiece,
+                ),
+            };
+        }
+        Ok(())
+    }
+}
+
+fn stage_paths(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|err| format!("unable to read stages in {}: {err}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+#[test]
+fn shipped_stages_are_valid() {
+    let stages = StageData::load_all(STAGES_DIR).unwrap();
+    assert!(stages.len() > 1);
+    for stage in stages {
+        assert!(
+            !stage.backgrounds.is_empty(),
+            "{} has no background",
+            stage.id
+        );
+        for point in stage.spawn_points {
+            let body = RigidBody::new().with_pos(point.into());
+            assert!(
+                stage.blast_zone.contains(&body),
+                "{} spawns players outside the blast zone",
+                stage.id
+            );
+        }
+    }
+}
+
+#[test]
+fn broken_stages_are_skipped_by_load_valid() {
+    let dir = std::env::temp_dir().join(format!("load_valid_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let shipped = stage_paths(Path::new(STAGES_DIR)).unwrap();
+    std::fs::copy(&shipped[0], dir.join("a.json")).unwrap();
+    std::fs::write(dir.join("b.json"), "{ not a stage").unwrap();
+
+    assert!(StageData::load_all(&dir).is_err());
+    let stages = StageData::load_valid(&dir);
+    assert_eq!(stages.len(), 1);
+    assert_eq!(stages[0].id, "a");
+    assert!(StageData::load_valid(dir.join("missing")).is_empty());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
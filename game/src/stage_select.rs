@@ -0,0 +1,157 @@
+use crate::assets::{Assets, TextureAsset};
+use crate::game::{GameSystem, HeroesOnBoard};
+use crate::stage::{StageData, STAGES_DIR};
+use crate::start_game::StartGameSystem;
+use crate::ui_components::Button;
+use engine::ui;
+use engine::ui::focus::Focus;
+use engine::SharedPtr;
+use engine::{query, spawn};
+use engine::{Component, Schedule, Stage, System};
+use engine::{LOGICAL_HEIGHT, LOGICAL_WIDTH};
+
+#[derive(Component, Clone)]
+pub struct StageSelect {
+    dom: SharedPtr<ui::Dom>,
+    focus: SharedPtr<Focus>,
+}
+
+#[derive(Clone, Copy)]
+enum Node {
+    Back,
+    Stage(usize),
+}
+
+impl From<Node> for ui::NodeId {
+    fn from(value: Node) -> Self {
+        match value {
+            Node::Back => Self::from_u64(0),
+            Node::Stage(index) => Self::from_u64(1 + index as u64),
+        }
+    }
+}
+
+enum Event {
+    Back,
+    Stage(usize),
+}
+
+impl From<Event> for ui::EventId {
+    fn from(value: Event) -> Self {
+        match value {
+            Event::Back => Self::from_u64(0),
+            Event::Stage(index) => Self::from_u64(1 + index as u64),
+        }
+    }
+}
+
+/// Picks the stage to play on, after both heroes are on the board.
+pub struct StageSelectSystem(pub u64);
+impl System for StageSelectSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
+    fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
+        use ui::constructors::{Hori, Image, Rect, Text, Vert};
+
+        let stages = StageData::load_valid(STAGES_DIR);
+        let placeholder = ctx
+            .resource::<Assets>()
+            .texture_path(TextureAsset::Placeholder)
+            .to_string();
+
+        let stage_buttons = stages.iter().enumerate().map(|(index, stage)| {
+            let preview = stage
+                .backgrounds
+                .first()
+                .map(|background| background.texture.clone())
+                .unwrap_or_else(|| placeholder.clone());
+            Vert([
+                Image(preview).width(256).height(144),
+                Button(stage.name.clone())
+                    .width(256)
+                    .color((255, 255, 255))
+                    .background_color((50, 50, 50))
+                    .id(Node::Stage(index))
+                    .on_click(Event::Stage(index)),
+            ])
+            .gap(8)
+        });
+
+        let mut dom = ui::Dom::new(
+            Vert([
+                Rect().height(60),
+                Text("Select Stage").font_size(70),
+                Rect().height(60),
+                Hori(stage_buttons).gap(32),
+                Rect().height(60),
+                Button("Back")
+                    .width(200)
+                    .color((255, 255, 255))
+                    .background_color((50, 50, 50))
+                    .id(Node::Back)
+                    .on_click(Event::Back),
+            ])
+            .background_color((50, 50, 50))
+            .font_size(20)
+            .width(LOGICAL_WIDTH as i32)
+            .height(LOGICAL_HEIGHT as i32),
+        );
+
+        for (index, stage) in stages.iter().enumerate() {
+            let stage = stage.clone();
+            dom.add_event_handler(Event::Stage(index), move |_dom, ctx, _node_id| {
+                ctx.insert_resource(stage.clone());
+                ctx.replace_state(GameSystem);
+            });
+        }
+
+        dom.add_event_handler(Event::Back, |_dom, ctx, _node_id| {
+            ctx.remove_resource::<HeroesOnBoard>();
+            ctx.replace_state(StartGameSystem);
+        });
+
+        let focus_nodes = (0..stages.len())
+            .map(Node::Stage)
+            .chain([Node::Back])
+            .collect::<Vec<_>>();
+
+        spawn!(
+            ctx,
+            StageSelect {
+                dom: SharedPtr::new(dom),
+                focus: SharedPtr::new(Focus::new(focus_nodes)),
+            }
+        );
+
+        Ok(())
+    }
+
+    fn on_update(&self, ctx: &mut engine::Context, _delta: f64) -> Result<(), engine::Error> {
+        let stage_selects = query!(ctx, StageSelect)
+            .map(|(_, stage_select)| stage_select.clone())
+            .collect::<Vec<_>>();
+        for stage_select in stage_selects {
+            let mut dom = stage_select.dom.lock();
+            let mut focus = stage_select.focus.lock();
+            focus.update(&mut dom, ctx);
+            dom.update(ctx);
+        }
+        Ok(())
+    }
+
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let stage_selects = query!(ctx, StageSelect)
+            .map(|(_, stage_select)| stage_select.clone())
+            .collect::<Vec<_>>();
+        for stage_select in stage_selects {
+            stage_select.dom.lock().draw(ctx);
+        }
+        Ok(())
+    }
+}
@@ -1,27 +1,27 @@
 use std::{borrow::BorrowMut, sync::MutexGuard};
 
 use engine::{
-    query, spawn,
+    spawn,
     ui::{
         self,
         focus::Focus,
         utils::{change_image_node_content, change_text_node_content},
     },
-    Component, SharedPtr, System,
+    Component, Schedule, SharedPtr, Stage, System, LOGICAL_HEIGHT, LOGICAL_WIDTH,
 };
 
 use crate::{
-    game::GameSystem,
+    assets::{Assets, SoundAsset, TextureAsset},
     hero_info::HeroInfo,
     main_menu::MainMenuSystem,
     server::{Board, HeroResult, Res, Server},
     sound_player::SoundPlayer,
+    stage_select::StageSelectSystem,
     ui_components::{Button, ProgressBar},
 };
 
 #[derive(Component, Clone)]
 pub struct StartGame {
-    system_id: u64,
     dom: SharedPtr<ui::Dom>,
     left_bars: SharedPtr<BarBundle>,
     right_bars: SharedPtr<BarBundle>,
@@ -72,11 +72,13 @@ struct MaybeHeroesOnBoard(Option<crate::game::HeroesOnBoard>);
 
 pub struct StartGameSystem(pub u64);
 impl System for StartGameSystem {
+    fn schedule(&self) -> Schedule {
+        Schedule::new(Stage::Ui)
+    }
+
     fn on_add(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
         use ui::constructors::{Hori, Image, Rect, Stack, Text, Vert};
 
-        let system_id = self.0;
-
         let left_strength_bar = ProgressBar::new_immutable("Strength", 24);
         let left_agility_bar = ProgressBar::new_immutable("Agility", 24);
         let left_defence_bar = ProgressBar::new_immutable("Defence", 24);
@@ -85,6 +87,11 @@ impl System for StartGameSystem {
         let right_agility_bar = ProgressBar::new_immutable("Agility", 24);
         let right_defence_bar = ProgressBar::new_immutable("Defence", 24);
 
+        let placeholder = ctx
+            .resource::<Assets>()
+            .texture_path(TextureAsset::Placeholder)
+            .to_string();
+
         let mut dom = ui::Dom::new(
             Stack([
                 Hori([
@@ -100,7 +107,7 @@ impl System for StartGameSystem {
                         .width(200)
                         .visible(false)
                         .id(Node::LeftBars),
-                        Image("./assets/placeholder.png")
+                        Image(placeholder.clone())
                             .id(Node::LeftImage)
                             .width(200)
                             .height(200)
@@ -128,7 +135,7 @@ impl System for StartGameSystem {
                         .width(200)
                         .visible(false)
                         .id(Node::RightBars),
-                        Image("./assets/placeholder.png")
+                        Image(placeholder)
                             .id(Node::RightImage)
                             .width(200)
                             .height(200)
@@ -150,31 +157,32 @@ impl System for StartGameSystem {
             ])
             .font_size(20)
             .background_color((50, 50, 50))
-            .width(1280)
-            .height(720),
+            .width(LOGICAL_WIDTH as i32)
+            .height(LOGICAL_HEIGHT as i32),
         );
 
-        dom.add_event_handler(Event::StartGame, move |_dom, ctx, _node_id| {
+        dom.add_event_handler(Event::StartGame, |_dom, ctx, _node_id| {
             if let Some(heroes_on_board) = ctx.clone_one::<MaybeHeroesOnBoard>().0 {
-                spawn!(ctx, heroes_on_board);
-                ctx.remove_system(system_id);
-                ctx.add_system(GameSystem);
+                ctx.insert_resource(heroes_on_board);
+                ctx.replace_state(StageSelectSystem);
             }
         });
 
-        dom.add_event_handler(Event::ErrorPopupClick, move |dom, ctx, _node_id| {
-            ctx.remove_system(system_id);
-            ctx.add_system(MainMenuSystem);
+        dom.add_event_handler(Event::ErrorPopupClick, |dom, ctx, _node_id| {
+            ctx.replace_state(MainMenuSystem);
             dom.select_mut(Node::ErrorPopup).unwrap().set_visible(false);
         });
 
-        let sound_player = ctx.select_one::<SoundPlayer>();
-        sound_player.play_music("assets/sounds/theme_3.ogg");
+        let theme = ctx
+            .resource::<Assets>()
+            .sound(SoundAsset::HeroTheme)
+            .clone();
+        let sound_player = ctx.resource::<SoundPlayer>();
+        sound_player.play_music(theme);
 
         spawn!(
             ctx,
             StartGame {
-                system_id: self.0,
                 dom: SharedPtr::new(dom),
                 left_bars: SharedPtr::new(BarBundle {
                     strength: left_strength_bar,
@@ -205,7 +213,7 @@ impl System for StartGameSystem {
         let responder = match start_game.board_responder {
             Some(responder) => responder,
             None => {
-                let responder = SharedPtr::new(ctx.select_one::<Server>().board_status());
+                let responder = SharedPtr::new(ctx.resource::<Server>().board_status());
                 let start_game = ctx.select_one::<StartGame>();
                 start_game.board_responder = Some(responder.clone());
                 responder
@@ -217,6 +225,7 @@ impl System for StartGameSystem {
             Some(heroes) => {
                 dom.select_mut(Node::ErrorPopup).unwrap().set_visible(false);
 
+                let assets = ctx.resource::<Assets>();
                 display_hero_result(
                     heroes.hero_1.as_ref(),
                     Node::LeftImage,
@@ -224,6 +233,7 @@ impl System for StartGameSystem {
                     Node::LeftOffset,
                     &mut dom,
                     start_game.left_bars.lock(),
+                    assets,
                 );
                 display_hero_result(
                     heroes.hero_2.as_ref(),
@@ -232,6 +242,7 @@ impl System for StartGameSystem {
                     Node::RightOffset,
                     &mut dom,
                     start_game.right_bars.lock(),
+                    assets,
                 );
 
                 if let (Some(HeroResult::Hero(hero_1)), Some(HeroResult::Hero(hero_2))) =
@@ -248,18 +259,13 @@ impl System for StartGameSystem {
         Ok(())
     }
 
-    fn on_remove(&self, ctx: &mut engine::Context) -> Result<(), engine::Error> {
-        for id in query!(ctx, StartGame) {
-            let start_game = ctx.select::<StartGame>(id).clone();
-            if start_game.system_id == self.0 {
-                ctx.despawn(id);
-            }
-        }
-
-        for id in query!(ctx, MaybeHeroesOnBoard) {
-            ctx.despawn(id);
-        }
-
+    fn on_render(
+        &self,
+        ctx: &mut engine::Context,
+        _interpolation: f64,
+    ) -> Result<(), engine::Error> {
+        let start_game = ctx.clone_one::<StartGame>();
+        start_game.dom.lock().draw(ctx);
         Ok(())
     }
 }
@@ -271,12 +277,13 @@ fn display_hero_result(
     offset_id: Node,
     dom: &mut MutexGuard<ui::Dom>,
     mut bars: MutexGuard<BarBundle>,
+    assets: &Assets,
 ) {
     match hero {
         Some(HeroResult::Hero(hero)) => {
             change_image_node_content(
                 dom.select_mut(image_id),
-                HeroInfo::from(&hero.kind).texture_path,
+                &assets.atlas(HeroInfo::from(&hero.kind).atlas).path,
             );
             bars.strength.set_steps_filled(hero.strength_points);
             bars.strength.update(dom);
@@ -8,10 +8,12 @@ CREATE TABLE IF NOT EXISTS heroes (
   defence_points INTEGER NOT NULL
 );
 
+-- columns added later are also added to existing databases on startup, see sqlite3_db.rs
 CREATE TABLE IF NOT EXISTS matches (
   id INTEGER PRIMARY KEY AUTOINCREMENT,
   loser INTEGER NOT NULL,
   winner INTEGER NOT NULL,
+  stage TEXT NOT NULL,
   FOREIGN KEY(loser) REFERENCES heroes(id),
   FOREIGN KEY(winner) REFERENCES heroes(id)
 );
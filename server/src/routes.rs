@@ -87,6 +87,7 @@ pub async fn create_match(
     let shared::CreateMatchParams {
         loser_hero_id,
         winner_hero_id,
+        stage,
     } = req_json.0;
     let _loser = match find_hero(db.clone(), loser_hero_id).await {
         Ok(player) => player,
@@ -111,6 +112,7 @@ pub async fn create_match(
         .create_match(shared::CreateMatchParams {
             winner_hero_id,
             loser_hero_id,
+            stage,
         })
         .await
     {
@@ -17,10 +17,29 @@ impl Sqlite3Db {
         )
         .await
         .with_context(|| "unable to connect to database")?;
+        migrate(&pool).await?;
         Ok(Self { pool })
     }
 }
 
+/// Brings databases prepared with an older `prepare.sql` up to date,
+/// as `CREATE TABLE IF NOT EXISTS` leaves existing tables as they were.
+async fn migrate(pool: &SqlitePool) -> Result<(), eyre::Report> {
+    let has_stage = sqlx::query_scalar::<_, bool>(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('matches') WHERE name = 'stage';",
+    )
+    .fetch_one(pool)
+    .await
+    .with_context(|| "could not read the matches table schema")?;
+    if !has_stage {
+        sqlx::query("ALTER TABLE matches ADD COLUMN stage TEXT NOT NULL DEFAULT '';")
+            .execute(pool)
+            .await
+            .with_context(|| "could not add stage to matches")?;
+    }
+    Ok(())
+}
+
 impl Database for Sqlite3Db {
     async fn create_hero(&mut self, hero: shared::CreateHeroParams) -> Result<(), eyre::Report> {
         let hero_type = hero.hero_type as i64;
@@ -78,9 +97,10 @@ impl Database for Sqlite3Db {
         params: shared::CreateMatchParams,
     ) -> Result<(), eyre::Report> {
         sqlx::query!(
-            "INSERT INTO matches (loser, winner) VALUES (?, ?);",
+            "INSERT INTO matches (loser, winner, stage) VALUES (?, ?, ?);",
             params.loser_hero_id,
             params.winner_hero_id,
+            params.stage,
         )
         .execute(&self.pool)
         .await
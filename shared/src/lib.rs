@@ -124,6 +124,9 @@ pub struct Match {
     pub loser: i64,
     // hero id
     pub winner: i64,
+    /// name of the stage file, without extension
+    #[serde(default)]
+    pub stage: String,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -149,6 +152,9 @@ pub struct UpdateBoardColorsParams {
 pub struct CreateMatchParams {
     pub winner_hero_id: i64,
     pub loser_hero_id: i64,
+    /// empty when sent by game clients from before stages were recorded
+    #[serde(default)]
+    pub stage: String,
 }
 
 #[derive(Clone, Debug)]